@@ -0,0 +1,231 @@
+use std::collections::{BTreeSet, HashMap};
+
+use wvr_data::config::project_config::RenderStageConfig;
+
+use super::validation::get_sampled_input_name;
+
+/// How a stage samples the stages rendered after it, which only hold their previous frame.
+#[derive(Default, Debug)]
+pub struct StageDependencies {
+    /// Later stages sampled as part of a feedback loop.
+    pub feedback_source_list: Vec<usize>,
+    /// Later stages sampled outside of any loop, auto-ordering renders those first.
+    pub forward_reference_list: Vec<usize>,
+}
+
+/// Indices of the stages sampled by each stage of the render chain, inputs are left out.
+pub fn build_dependency_graph(render_chain: &[RenderStageConfig]) -> Vec<Vec<usize>> {
+    let mut stage_index_list = HashMap::new();
+    for (stage_index, stage) in render_chain.iter().enumerate() {
+        stage_index_list
+            .entry(stage.name.as_str())
+            .or_insert(stage_index);
+    }
+
+    render_chain
+        .iter()
+        .map(|stage| {
+            let mut dependency_list: Vec<usize> = stage
+                .inputs
+                .values()
+                .filter_map(|sampled_input| {
+                    stage_index_list
+                        .get(get_sampled_input_name(sampled_input))
+                        .copied()
+                })
+                .collect();
+            dependency_list.sort_unstable();
+            dependency_list.dedup();
+
+            dependency_list
+        })
+        .collect()
+}
+
+// `reachability[a][b]` tells whether stage `a` depends on stage `b` through one or more edges
+fn get_reachability(graph: &[Vec<usize>]) -> Vec<Vec<bool>> {
+    graph
+        .iter()
+        .map(|dependency_list| {
+            let mut reached = vec![false; graph.len()];
+            let mut pending_list = dependency_list.clone();
+            while let Some(stage_index) = pending_list.pop() {
+                if !reached[stage_index] {
+                    reached[stage_index] = true;
+                    pending_list.extend(&graph[stage_index]);
+                }
+            }
+
+            reached
+        })
+        .collect()
+}
+
+pub fn analyze_render_chain(render_chain: &[RenderStageConfig]) -> Vec<StageDependencies> {
+    let graph = build_dependency_graph(render_chain);
+    let reachability = get_reachability(&graph);
+
+    graph
+        .iter()
+        .enumerate()
+        .map(|(stage_index, dependency_list)| {
+            let mut stage_dependencies = StageDependencies::default();
+            for &dependency_index in dependency_list {
+                if dependency_index < stage_index {
+                    continue;
+                }
+
+                if reachability[dependency_index][stage_index] {
+                    stage_dependencies
+                        .feedback_source_list
+                        .push(dependency_index);
+                } else {
+                    stage_dependencies
+                        .forward_reference_list
+                        .push(dependency_index);
+                }
+            }
+
+            stage_dependencies
+        })
+        .collect()
+}
+
+/// Order of the render chain where each stage comes after the stages it samples.
+///
+/// Edges inside feedback loops are ignored, the stages of a loop keep their relative order
+/// as far as their other dependencies allow it.
+pub fn get_sorted_stage_order(render_chain: &[RenderStageConfig]) -> Vec<usize> {
+    let graph = build_dependency_graph(render_chain);
+    let reachability = get_reachability(&graph);
+
+    let mut pending_dependency_count_list = vec![0; graph.len()];
+    let mut dependent_list = vec![Vec::new(); graph.len()];
+    for (stage_index, dependency_list) in graph.iter().enumerate() {
+        for &dependency_index in dependency_list {
+            let is_in_loop = reachability[stage_index][dependency_index]
+                && reachability[dependency_index][stage_index];
+            if !is_in_loop {
+                pending_dependency_count_list[stage_index] += 1;
+                dependent_list[dependency_index].push(stage_index);
+            }
+        }
+    }
+
+    // Picking the lowest ready index keeps already sorted chains untouched
+    let mut ready_list: BTreeSet<usize> = pending_dependency_count_list
+        .iter()
+        .enumerate()
+        .filter(|(_, pending_dependency_count)| **pending_dependency_count == 0)
+        .map(|(stage_index, _)| stage_index)
+        .collect();
+
+    let mut stage_order = Vec::with_capacity(graph.len());
+    while let Some(stage_index) = ready_list.iter().next().copied() {
+        ready_list.remove(&stage_index);
+        stage_order.push(stage_index);
+
+        for &dependent_index in &dependent_list[stage_index] {
+            pending_dependency_count_list[dependent_index] -= 1;
+            if pending_dependency_count_list[dependent_index] == 0 {
+                ready_list.insert(dependent_index);
+            }
+        }
+    }
+
+    stage_order
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wvr_data::config::project_config::{BufferPrecision, FilterMode, SampledInput};
+
+    use super::*;
+
+    fn build_stage(name: &str, sampled_stage_name_list: &[&str]) -> RenderStageConfig {
+        RenderStageConfig {
+            name: name.to_owned(),
+            filter: "generic/copy".to_owned(),
+            filter_mode_params: FilterMode::Rectangle(0.0, 0.0, 1.0, 1.0),
+            inputs: sampled_stage_name_list
+                .iter()
+                .enumerate()
+                .map(|(input_index, stage_name)| {
+                    (
+                        format!("iChannel{:}", input_index),
+                        SampledInput::Linear((*stage_name).to_owned()),
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+            variables: HashMap::new(),
+            precision: BufferPrecision::U8,
+        }
+    }
+
+    #[test]
+    fn sorted_chain_is_unchanged() {
+        let render_chain = vec![
+            build_stage("a", &[]),
+            build_stage("b", &["a"]),
+            build_stage("c", &["b"]),
+        ];
+
+        assert_eq!(get_sorted_stage_order(&render_chain), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn reversed_chain_is_sorted() {
+        let render_chain = vec![
+            build_stage("c", &["b"]),
+            build_stage("b", &["a"]),
+            build_stage("a", &[]),
+        ];
+
+        assert_eq!(get_sorted_stage_order(&render_chain), vec![2, 1, 0]);
+
+        let stage_dependencies_list = analyze_render_chain(&render_chain);
+        assert_eq!(stage_dependencies_list[0].forward_reference_list, vec![1]);
+        assert!(stage_dependencies_list[0].feedback_source_list.is_empty());
+    }
+
+    #[test]
+    fn diamond_is_sorted() {
+        let render_chain = vec![
+            build_stage("d", &["b", "c"]),
+            build_stage("b", &["a"]),
+            build_stage("c", &["a"]),
+            build_stage("a", &[]),
+        ];
+
+        assert_eq!(get_sorted_stage_order(&render_chain), vec![3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn self_reference_is_ignored() {
+        let render_chain = vec![build_stage("b", &["a"]), build_stage("a", &["a"])];
+
+        assert_eq!(get_sorted_stage_order(&render_chain), vec![1, 0]);
+
+        let stage_dependencies_list = analyze_render_chain(&render_chain);
+        assert_eq!(stage_dependencies_list[1].feedback_source_list, vec![1]);
+        assert!(stage_dependencies_list[1].forward_reference_list.is_empty());
+    }
+
+    #[test]
+    fn cycle_keeps_its_order() {
+        let render_chain = vec![
+            build_stage("c", &["a"]),
+            build_stage("a", &["b"]),
+            build_stage("b", &["a"]),
+        ];
+
+        // The loop between `a` and `b` is left as is, `c` moves after `a`
+        assert_eq!(get_sorted_stage_order(&render_chain), vec![1, 0, 2]);
+
+        let stage_dependencies_list = analyze_render_chain(&render_chain);
+        assert_eq!(stage_dependencies_list[0].forward_reference_list, vec![1]);
+        assert_eq!(stage_dependencies_list[1].feedback_source_list, vec![2]);
+    }
+}
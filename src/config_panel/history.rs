@@ -0,0 +1,648 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use wvr_com::data::{InputUpdate, Message, RenderStageUpdate, SetInfo};
+use wvr_data::config::project_config::{InputConfig, ProjectConfig, RenderStageConfig};
+
+const MAX_HISTORY_LENGTH: usize = 256;
+
+// Consecutive edits of the same value (slider drags, typing) are merged into a single step
+pub const MERGE_DELAY: Duration = Duration::from_millis(750);
+
+// Edits emitted as a side effect of another edit (e.g. variables reset by a filter change)
+// arrive almost immediately and are merged regardless of their target
+const CASCADE_DELAY: Duration = Duration::from_millis(100);
+
+struct HistoryEntry {
+    merge_key: String,
+    config: ProjectConfig,
+}
+
+pub struct History {
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    last_edit: Option<(String, Instant)>,
+    suspended: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            suspended: false,
+        }
+    }
+
+    /// Stores the state preceding an edit, unless the edit did not change anything.
+    pub fn record(
+        &mut self,
+        merge_key: String,
+        previous_config: ProjectConfig,
+        current_config: &ProjectConfig,
+    ) {
+        if self.suspended || !differs(&previous_config, current_config) {
+            return;
+        }
+
+        let now = Instant::now();
+        let merge_with_last_edit = match &self.last_edit {
+            Some((last_merge_key, last_edit_time)) => {
+                let elapsed = now.duration_since(*last_edit_time);
+                elapsed < CASCADE_DELAY || (last_merge_key == &merge_key && elapsed < MERGE_DELAY)
+            }
+            None => false,
+        };
+
+        if !merge_with_last_edit || self.undo_stack.is_empty() {
+            self.undo_stack.push(HistoryEntry {
+                merge_key: merge_key.clone(),
+                config: previous_config,
+            });
+
+            if self.undo_stack.len() > MAX_HISTORY_LENGTH {
+                self.undo_stack.remove(0);
+            }
+        }
+
+        self.redo_stack.clear();
+        self.last_edit = Some((merge_key, now));
+    }
+
+    /// Returns the configuration to restore, saving the current one for redo.
+    pub fn undo(&mut self, current_config: &ProjectConfig) -> Option<ProjectConfig> {
+        let entry = self.undo_stack.pop()?;
+
+        self.redo_stack.push(HistoryEntry {
+            merge_key: entry.merge_key,
+            config: current_config.clone(),
+        });
+        self.last_edit = None;
+
+        Some(entry.config)
+    }
+
+    /// Returns the configuration to restore, saving the current one for undo.
+    pub fn redo(&mut self, current_config: &ProjectConfig) -> Option<ProjectConfig> {
+        let entry = self.redo_stack.pop()?;
+
+        self.undo_stack.push(HistoryEntry {
+            merge_key: entry.merge_key,
+            config: current_config.clone(),
+        });
+        self.last_edit = None;
+
+        Some(entry.config)
+    }
+
+    /// Ignores edits until `resume` is called, used while widgets are rebuilt from a restored state.
+    pub fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.suspended = false;
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compares values through their serialization, the config types not implementing `PartialEq`.
+pub fn differs<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() != serde_json::to_value(b).ok()
+}
+
+fn build_input_message_list(
+    input_name: &str,
+    old_input: &InputConfig,
+    new_input: &InputConfig,
+) -> Vec<Message> {
+    let mut message_list = Vec::new();
+
+    match (old_input, new_input) {
+        (
+            InputConfig::Cam {
+                path: old_path,
+                width: old_width,
+                height: old_height,
+            },
+            InputConfig::Cam {
+                path,
+                width,
+                height,
+            },
+        )
+        | (
+            InputConfig::Picture {
+                path: old_path,
+                width: old_width,
+                height: old_height,
+            },
+            InputConfig::Picture {
+                path,
+                width,
+                height,
+            },
+        ) => {
+            if old_path != path {
+                message_list.push(InputUpdate::SetPath(path.clone()));
+            }
+            if old_width != width {
+                message_list.push(InputUpdate::SetWidth(*width));
+            }
+            if old_height != height {
+                message_list.push(InputUpdate::SetHeight(*height));
+            }
+        }
+        (
+            InputConfig::Video {
+                path: old_path,
+                width: old_width,
+                height: old_height,
+                speed: old_speed,
+            },
+            InputConfig::Video {
+                path,
+                width,
+                height,
+                speed,
+            },
+        ) => {
+            if old_path != path {
+                message_list.push(InputUpdate::SetPath(path.clone()));
+            }
+            if old_width != width {
+                message_list.push(InputUpdate::SetWidth(*width));
+            }
+            if old_height != height {
+                message_list.push(InputUpdate::SetHeight(*height));
+            }
+            if differs(old_speed, speed) {
+                message_list.push(InputUpdate::SetSpeed(*speed));
+            }
+        }
+        (InputConfig::Midi { name: old_name }, InputConfig::Midi { name }) => {
+            if old_name != name {
+                message_list.push(InputUpdate::SetPath(name.clone()));
+            }
+        }
+        _ => {
+            return vec![
+                Message::RemoveInput(input_name.to_string()),
+                Message::AddInput(input_name.to_string(), new_input.clone()),
+            ];
+        }
+    }
+
+    message_list
+        .into_iter()
+        .map(|input_update| Message::UpdateInput(input_name.to_string(), input_update))
+        .collect()
+}
+
+/// Builds the updates bringing a rendered stage from `old_stage` to `new_stage`.
+pub fn build_render_stage_message_list(
+    old_stage: &RenderStageConfig,
+    new_stage: &RenderStageConfig,
+) -> Vec<RenderStageUpdate> {
+    let mut update_list = Vec::new();
+
+    if old_stage.name != new_stage.name {
+        update_list.push(RenderStageUpdate::Name(new_stage.name.clone()));
+    }
+
+    if old_stage.filter != new_stage.filter {
+        update_list.push(RenderStageUpdate::Filter(new_stage.filter.clone()));
+    }
+
+    if differs(&old_stage.filter_mode_params, &new_stage.filter_mode_params) {
+        update_list.push(RenderStageUpdate::FilterModeParams(
+            new_stage.filter_mode_params,
+        ));
+    }
+
+    if differs(&old_stage.precision, &new_stage.precision) {
+        update_list.push(RenderStageUpdate::Precision(new_stage.precision));
+    }
+
+    for (input_name, input) in &new_stage.inputs {
+        if old_stage
+            .inputs
+            .get(input_name)
+            .map(|old_input| differs(old_input, input))
+            .unwrap_or(true)
+        {
+            update_list.push(RenderStageUpdate::Input(input_name.clone(), input.clone()));
+        }
+    }
+
+    for (variable_name, (value, automation)) in &new_stage.variables {
+        let (value_changed, automation_changed) = match old_stage.variables.get(variable_name) {
+            Some((old_value, old_automation)) => (
+                differs(old_value, value),
+                differs(old_automation, automation),
+            ),
+            None => (true, !automation.is_none()),
+        };
+
+        if value_changed {
+            update_list.push(RenderStageUpdate::Variable(
+                variable_name.clone(),
+                value.clone(),
+            ));
+        }
+        if automation_changed {
+            update_list.push(RenderStageUpdate::VariableAutomation(
+                variable_name.clone(),
+                *automation,
+            ));
+        }
+    }
+
+    update_list
+}
+
+/// Whether inputs or variables were dropped from a stage, which no stage update can express.
+fn has_removed_entries(old_stage: &RenderStageConfig, new_stage: &RenderStageConfig) -> bool {
+    old_stage
+        .inputs
+        .keys()
+        .any(|input_name| !new_stage.inputs.contains_key(input_name))
+        || old_stage
+            .variables
+            .keys()
+            .any(|variable_name| !new_stage.variables.contains_key(variable_name))
+}
+
+/// Builds the messages bringing the stage at `stage_index` from `old_stage` to `new_stage`,
+/// replacing it when it cannot be updated in place.
+fn build_stage_sync_message_list(
+    stage_index: usize,
+    stage_count: usize,
+    old_stage: &RenderStageConfig,
+    new_stage: &RenderStageConfig,
+) -> Vec<Message> {
+    if !has_removed_entries(old_stage, new_stage) {
+        return build_render_stage_message_list(old_stage, new_stage)
+            .into_iter()
+            .map(|update| Message::UpdateRenderStage(stage_index, update))
+            .collect();
+    }
+
+    // Added stages are appended to the chain, then moved back in place
+    let mut message_list = vec![
+        Message::RemoveRenderStage(stage_index),
+        Message::AddRenderStage(new_stage.clone()),
+    ];
+    if stage_index + 1 < stage_count {
+        message_list.push(Message::MoveRenderStage(stage_count - 1, stage_index));
+    }
+
+    message_list
+}
+
+/// Whether each stage kept its position, the ones with a new name having been renamed in place.
+fn is_renamed_in_place(old_stage_names: &[&String], new_stage_names: &[&String]) -> bool {
+    old_stage_names.len() == new_stage_names.len()
+        && old_stage_names
+            .iter()
+            .zip(new_stage_names)
+            .all(|(old_stage_name, new_stage_name)| {
+                old_stage_name == new_stage_name
+                    || (!new_stage_names.contains(old_stage_name)
+                        && !old_stage_names.contains(new_stage_name))
+            })
+}
+
+/// Builds the messages bringing a running renderer from `old_config` to `new_config`.
+pub fn build_sync_message_list(
+    old_config: &ProjectConfig,
+    new_config: &ProjectConfig,
+) -> Vec<Message> {
+    let mut message_list = Vec::new();
+
+    if (old_config.bpm - new_config.bpm).abs() > f32::EPSILON {
+        message_list.push(Message::Set(SetInfo::Bpm(new_config.bpm as f64)));
+    }
+
+    let (old_view, new_view) = (&old_config.view, &new_config.view);
+    if old_view.width != new_view.width {
+        message_list.push(Message::Set(SetInfo::Width(new_view.width as usize)));
+    }
+    if old_view.height != new_view.height {
+        message_list.push(Message::Set(SetInfo::Height(new_view.height as usize)));
+    }
+    if (old_view.target_fps - new_view.target_fps).abs() > f32::EPSILON {
+        message_list.push(Message::Set(SetInfo::TargetFps(new_view.target_fps as f64)));
+    }
+    if old_view.dynamic != new_view.dynamic {
+        message_list.push(Message::Set(SetInfo::DynamicResolution(new_view.dynamic)));
+    }
+    if old_view.vsync != new_view.vsync {
+        message_list.push(Message::Set(SetInfo::VSync(new_view.vsync)));
+    }
+    if old_view.screenshot != new_view.screenshot {
+        message_list.push(Message::Set(SetInfo::Screenshot(new_view.screenshot)));
+    }
+    if old_view.fullscreen != new_view.fullscreen {
+        message_list.push(Message::Set(SetInfo::Fullscreen(new_view.fullscreen)));
+    }
+    if old_view.locked_speed != new_view.locked_speed {
+        message_list.push(Message::Set(SetInfo::LockedSpeed(new_view.locked_speed)));
+    }
+
+    // New inputs are declared before stages can reference them
+    for (input_name, input) in &new_config.inputs {
+        match old_config.inputs.get(input_name) {
+            Some(old_input) => {
+                if differs(old_input, input) {
+                    message_list.extend(build_input_message_list(input_name, old_input, input));
+                }
+            }
+            None => message_list.push(Message::AddInput(input_name.clone(), input.clone())),
+        }
+    }
+
+    let old_stage_names: Vec<&String> = old_config
+        .render_chain
+        .iter()
+        .map(|stage| &stage.name)
+        .collect();
+    let new_stage_names: Vec<&String> = new_config
+        .render_chain
+        .iter()
+        .map(|stage| &stage.name)
+        .collect();
+
+    let mut sorted_old_stage_names = old_stage_names.clone();
+    sorted_old_stage_names.sort();
+    let mut sorted_new_stage_names = new_stage_names.clone();
+    sorted_new_stage_names.sort();
+
+    if sorted_old_stage_names == sorted_new_stage_names {
+        // Same stages, possibly reordered or edited
+        let mut current_order = old_stage_names.clone();
+        for (target_index, stage_name) in new_stage_names.iter().enumerate() {
+            let original_index = current_order
+                .iter()
+                .position(|candidate| candidate == stage_name)
+                .unwrap();
+
+            if original_index != target_index {
+                let stage_name = current_order.remove(original_index);
+                current_order.insert(target_index, stage_name);
+                message_list.push(Message::MoveRenderStage(original_index, target_index));
+            }
+        }
+
+        let stage_count = new_config.render_chain.len();
+        for (stage_index, new_stage) in new_config.render_chain.iter().enumerate() {
+            if let Some(old_stage) = old_config
+                .render_chain
+                .iter()
+                .find(|stage| stage.name == new_stage.name)
+            {
+                message_list.extend(build_stage_sync_message_list(
+                    stage_index,
+                    stage_count,
+                    old_stage,
+                    new_stage,
+                ));
+            }
+        }
+    } else if is_renamed_in_place(&old_stage_names, &new_stage_names) {
+        // Same layout, at least one stage was renamed
+        let stage_count = new_config.render_chain.len();
+        for (stage_index, (old_stage, new_stage)) in old_config
+            .render_chain
+            .iter()
+            .zip(new_config.render_chain.iter())
+            .enumerate()
+        {
+            message_list.extend(build_stage_sync_message_list(
+                stage_index,
+                stage_count,
+                old_stage,
+                new_stage,
+            ));
+        }
+    } else {
+        // Stages were added, removed, or renamed and moved at once, the whole chain is rebuilt
+        for stage_index in (0..old_config.render_chain.len()).rev() {
+            message_list.push(Message::RemoveRenderStage(stage_index));
+        }
+        for stage in &new_config.render_chain {
+            message_list.push(Message::AddRenderStage(stage.clone()));
+        }
+    }
+
+    for input_name in old_config.inputs.keys() {
+        if !new_config.inputs.contains_key(input_name) {
+            message_list.push(Message::RemoveInput(input_name.clone()));
+        }
+    }
+
+    // The final stage cannot be replaced, inputs or variables dropped from it stay set
+    for update in build_render_stage_message_list(&old_config.final_stage, &new_config.final_stage)
+    {
+        message_list.push(Message::UpdateFinalStage(update));
+    }
+
+    message_list
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use wvr_data::config::project_config::{
+        Automation, BufferPrecision, FilterMode, SampledInput, ViewConfig,
+    };
+    use wvr_data::config::server_config::ServerConfig;
+    use wvr_data::DataHolder;
+
+    use super::*;
+
+    fn build_stage(name: &str) -> RenderStageConfig {
+        RenderStageConfig {
+            name: name.to_owned(),
+            filter: "generic/copy".to_owned(),
+            filter_mode_params: FilterMode::Rectangle(0.0, 0.0, 1.0, 1.0),
+            inputs: HashMap::new(),
+            variables: HashMap::new(),
+            precision: BufferPrecision::U8,
+        }
+    }
+
+    fn build_config(render_chain: Vec<RenderStageConfig>) -> ProjectConfig {
+        ProjectConfig {
+            bpm: 120.0,
+            view: ViewConfig {
+                width: 640,
+                height: 360,
+                fullscreen: false,
+                dynamic: true,
+                vsync: true,
+                screenshot: false,
+                screenshot_path: PathBuf::from("output/"),
+                screenshot_frame_count: -1,
+                target_fps: 60.0,
+                locked_speed: false,
+            },
+            server: ServerConfig {
+                ip: "localhost".to_owned(),
+                port: 3000,
+                enable: false,
+            },
+            inputs: HashMap::new(),
+            render_chain,
+            final_stage: build_stage("FinalStage"),
+        }
+    }
+
+    fn is_rebuild(
+        message_list: &[Message],
+        old_stage_count: usize,
+        new_stage_count: usize,
+    ) -> bool {
+        message_list.len() == old_stage_count + new_stage_count
+            && message_list[..old_stage_count]
+                .iter()
+                .all(|message| matches!(message, Message::RemoveRenderStage(_)))
+            && message_list[old_stage_count..]
+                .iter()
+                .all(|message| matches!(message, Message::AddRenderStage(_)))
+    }
+
+    #[test]
+    fn unchanged_config_sends_nothing() {
+        let config = build_config(vec![build_stage("A"), build_stage("B")]);
+
+        assert!(build_sync_message_list(&config, &config).is_empty());
+    }
+
+    #[test]
+    fn reorder_moves_stages() {
+        let old_config = build_config(vec![build_stage("A"), build_stage("B")]);
+        let new_config = build_config(vec![build_stage("B"), build_stage("A")]);
+
+        let message_list = build_sync_message_list(&old_config, &new_config);
+
+        assert_eq!(message_list.len(), 1);
+        assert!(matches!(message_list[0], Message::MoveRenderStage(1, 0)));
+    }
+
+    #[test]
+    fn rename_in_place_updates_the_renamed_stage() {
+        let old_config = build_config(vec![build_stage("A"), build_stage("B")]);
+        let new_config = build_config(vec![build_stage("A"), build_stage("C")]);
+
+        let message_list = build_sync_message_list(&old_config, &new_config);
+
+        assert_eq!(message_list.len(), 1);
+        assert!(matches!(
+            &message_list[0],
+            Message::UpdateRenderStage(1, RenderStageUpdate::Name(name)) if name == "C"
+        ));
+    }
+
+    #[test]
+    fn rename_and_reorder_rebuilds_the_chain() {
+        // B renamed to C and moved first
+        let old_config = build_config(vec![build_stage("A"), build_stage("B")]);
+        let new_config = build_config(vec![build_stage("C"), build_stage("A")]);
+
+        let message_list = build_sync_message_list(&old_config, &new_config);
+
+        assert!(is_rebuild(&message_list, 2, 2));
+    }
+
+    #[test]
+    fn added_and_removed_stages_rebuild_the_chain() {
+        let old_config = build_config(vec![build_stage("A")]);
+        let new_config = build_config(vec![build_stage("A"), build_stage("B")]);
+
+        assert!(is_rebuild(
+            &build_sync_message_list(&old_config, &new_config),
+            1,
+            2
+        ));
+        assert!(is_rebuild(
+            &build_sync_message_list(&new_config, &old_config),
+            2,
+            1
+        ));
+    }
+
+    #[test]
+    fn removed_variable_replaces_the_stage() {
+        let mut stage_with_variable = build_stage("A");
+        stage_with_variable.variables.insert(
+            "speed".to_owned(),
+            (DataHolder::Float(1.0), Automation::None),
+        );
+
+        let old_config = build_config(vec![stage_with_variable, build_stage("B")]);
+        let new_config = build_config(vec![build_stage("A"), build_stage("B")]);
+
+        let message_list = build_sync_message_list(&old_config, &new_config);
+
+        assert_eq!(message_list.len(), 3);
+        assert!(matches!(message_list[0], Message::RemoveRenderStage(0)));
+        assert!(matches!(
+            &message_list[1],
+            Message::AddRenderStage(stage) if stage.name == "A" && stage.variables.is_empty()
+        ));
+        assert!(matches!(message_list[2], Message::MoveRenderStage(1, 0)));
+    }
+
+    #[test]
+    fn changed_variable_updates_the_stage() {
+        let mut old_stage = build_stage("A");
+        old_stage.variables.insert(
+            "speed".to_owned(),
+            (DataHolder::Float(1.0), Automation::None),
+        );
+        let mut new_stage = old_stage.clone();
+        new_stage.variables.insert(
+            "speed".to_owned(),
+            (DataHolder::Float(2.0), Automation::None),
+        );
+
+        let message_list = build_sync_message_list(
+            &build_config(vec![old_stage]),
+            &build_config(vec![new_stage]),
+        );
+
+        assert_eq!(message_list.len(), 1);
+        assert!(matches!(
+            &message_list[0],
+            Message::UpdateRenderStage(0, RenderStageUpdate::Variable(name, _)) if name == "speed"
+        ));
+    }
+
+    #[test]
+    fn final_stage_input_is_updated() {
+        let old_config = build_config(vec![build_stage("A"), build_stage("B")]);
+        let mut new_config = old_config.clone();
+        new_config
+            .final_stage
+            .inputs
+            .insert("iChannel0".to_owned(), SampledInput::Linear("B".to_owned()));
+
+        let message_list = build_sync_message_list(&old_config, &new_config);
+
+        assert_eq!(message_list.len(), 1);
+        assert!(matches!(
+            &message_list[0],
+            Message::UpdateFinalStage(RenderStageUpdate::Input(name, _)) if name == "iChannel0"
+        ));
+    }
+}
@@ -1,6 +1,9 @@
 use wvr_data::config::project_config::ProjectConfig;
 
+pub mod graph;
+pub mod history;
 pub mod msg;
+pub mod validation;
 pub mod view;
 
 pub fn get_input_choice_list(config: &ProjectConfig) -> Vec<String> {
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use uuid::Uuid;
 
 use relm_derive::Msg;
@@ -8,8 +10,10 @@ use wvr_data::config::project_config::{
 };
 use wvr_data::DataHolder;
 
+use super::validation::ValidationTarget;
 use super::view::ConfigPanel;
 use crate::input_config::InputConfigViewMsg;
+use crate::stage_config::preset::VariablePreset;
 
 #[derive(Msg, Debug)]
 pub enum ConfigPanelMsg {
@@ -20,6 +24,8 @@ pub enum ConfigPanelMsg {
     SetDynamicResolution(bool),
     SetVSync(bool),
     SetScreenshot(bool),
+    SetScreenshotPath(String),
+    SetScreenshotFrameCount(i64),
     SetFullscreen(bool),
     SetLockedSpeed(bool),
 
@@ -40,6 +46,16 @@ pub enum ConfigPanelMsg {
     UpdateRenderStagePrecision(Uuid, BufferPrecision),
     UpdateRenderStageName(Uuid, String),
     MoveStage(Uuid, usize),
+    SortStages,
+    // Without id, the stage of the current tab is used
+    DuplicateRenderStage(Option<Uuid>),
+    CopyRenderStage(Option<Uuid>),
+    PasteRenderStage,
+    SetRenderStageBypass(Uuid, bool),
+    SetRenderStageSolo(Uuid, bool),
+    SetRenderStageColorEditor(Uuid, String, bool),
+    ResetRenderStageVariables(Uuid),
+    ApplyRenderStagePreset(Uuid, VariablePreset),
     RemoveRenderStage(Uuid),
 
     UpdateRenderedTextureName(SampledInput),
@@ -48,9 +64,128 @@ pub enum ConfigPanelMsg {
     PauseProject,
     StopProject,
     Save,
+    ShowExportDialog,
+
+    CaptureFrame,
+    RefreshCaptureGallery,
+    OpenCapture(PathBuf),
+    DeleteCapture(PathBuf),
+    SetCaptureAsThumbnail(PathBuf),
+
+    SetOutputWindowVisible(bool),
+    SetOutputMonitor(i32),
+    SetOutputBlackout(bool),
+
+    Undo,
+    Redo,
+    EndHistoryRestore,
+    Autosave,
+    FlushJournal,
+
+    ShowValidationTarget(ValidationTarget),
+    DismissValidationFindings,
 }
 
 impl ConfigPanelMsg {
+    /// Key identifying the edited value, `None` for messages that are not recorded in the history.
+    pub fn get_history_merge_key(&self) -> Option<String> {
+        match &self {
+            ConfigPanelMsg::SetBpm(_) => Some("bpm".to_string()),
+            ConfigPanelMsg::SetWidth(_) => Some("view:width".to_string()),
+            ConfigPanelMsg::SetHeight(_) => Some("view:height".to_string()),
+            ConfigPanelMsg::SetTargetFps(_) => Some("view:target_fps".to_string()),
+            ConfigPanelMsg::SetDynamicResolution(_) => Some("view:dynamic".to_string()),
+            ConfigPanelMsg::SetVSync(_) => Some("view:vsync".to_string()),
+            ConfigPanelMsg::SetScreenshot(_) => Some("view:screenshot".to_string()),
+            ConfigPanelMsg::SetScreenshotPath(_) => Some("view:screenshot_path".to_string()),
+            ConfigPanelMsg::SetScreenshotFrameCount(_) => {
+                Some("view:screenshot_frame_count".to_string())
+            }
+            ConfigPanelMsg::SetFullscreen(_) => Some("view:fullscreen".to_string()),
+            ConfigPanelMsg::SetLockedSpeed(_) => Some("view:locked_speed".to_string()),
+
+            ConfigPanelMsg::SetServerIp(_) => Some("server:ip".to_string()),
+            ConfigPanelMsg::SetServerPort(_) => Some("server:port".to_string()),
+            ConfigPanelMsg::SetServerEnabled(_) => Some("server:enable".to_string()),
+
+            ConfigPanelMsg::AddInput(input_name, _) => Some(format!("input:{:}:add", input_name)),
+            ConfigPanelMsg::UpdateInput(input_id, input_update_message) => {
+                let field = match input_update_message {
+                    InputConfigViewMsg::SetName(_) => "name",
+                    InputConfigViewMsg::SetWidth(_) => "width",
+                    InputConfigViewMsg::SetHeight(_) => "height",
+                    InputConfigViewMsg::SetPath(_) => "path",
+                    InputConfigViewMsg::SetSpeed(_) => "speed",
+                };
+                Some(format!("input:{:}:{:}", input_id, field))
+            }
+            ConfigPanelMsg::RemoveInput(input_id) => Some(format!("input:{:}:remove", input_id)),
+
+            ConfigPanelMsg::AddRenderStage(render_stage_config) => {
+                Some(format!("stage:{:}:add", render_stage_config.name))
+            }
+            ConfigPanelMsg::UpdateRenderStageFilter(stage_id, _) => {
+                Some(format!("stage:{:}:filter", stage_id))
+            }
+            ConfigPanelMsg::UpdateRenderStageFilterModeParams(stage_id, _) => {
+                Some(format!("stage:{:}:filter_mode_params", stage_id))
+            }
+            ConfigPanelMsg::UpdateRenderStageVariable(stage_id, variable_name, _) => {
+                Some(format!("stage:{:}:variable:{:}", stage_id, variable_name))
+            }
+            ConfigPanelMsg::UpdateRenderStageVariableAutomation(stage_id, variable_name, _) => {
+                Some(format!("stage:{:}:automation:{:}", stage_id, variable_name))
+            }
+            ConfigPanelMsg::UpdateRenderStageInput(stage_id, input_name, _) => {
+                Some(format!("stage:{:}:input:{:}", stage_id, input_name))
+            }
+            ConfigPanelMsg::ApplyRenderStagePreset(stage_id, _) => {
+                Some(format!("stage:{:}:preset", stage_id))
+            }
+            ConfigPanelMsg::UpdateRenderStagePrecision(stage_id, _) => {
+                Some(format!("stage:{:}:precision", stage_id))
+            }
+            ConfigPanelMsg::UpdateRenderStageName(stage_id, _) => {
+                Some(format!("stage:{:}:name", stage_id))
+            }
+            ConfigPanelMsg::MoveStage(stage_id, _) => Some(format!("stage:{:}:move", stage_id)),
+            ConfigPanelMsg::RemoveRenderStage(stage_id) => {
+                Some(format!("stage:{:}:remove", stage_id))
+            }
+
+            ConfigPanelMsg::UpdateRenderedTextureName(_) => Some("final_stage".to_string()),
+
+            ConfigPanelMsg::StartProject
+            | ConfigPanelMsg::PauseProject
+            | ConfigPanelMsg::StopProject
+            | ConfigPanelMsg::Save
+            | ConfigPanelMsg::ShowExportDialog
+            | ConfigPanelMsg::CaptureFrame
+            | ConfigPanelMsg::RefreshCaptureGallery
+            | ConfigPanelMsg::OpenCapture(_)
+            | ConfigPanelMsg::DeleteCapture(_)
+            | ConfigPanelMsg::SetCaptureAsThumbnail(_)
+            | ConfigPanelMsg::SetOutputWindowVisible(_)
+            | ConfigPanelMsg::SetOutputMonitor(_)
+            | ConfigPanelMsg::SetOutputBlackout(_)
+            | ConfigPanelMsg::SortStages
+            | ConfigPanelMsg::DuplicateRenderStage(_)
+            | ConfigPanelMsg::CopyRenderStage(_)
+            | ConfigPanelMsg::PasteRenderStage
+            | ConfigPanelMsg::SetRenderStageBypass(_, _)
+            | ConfigPanelMsg::SetRenderStageSolo(_, _)
+            | ConfigPanelMsg::SetRenderStageColorEditor(_, _, _)
+            | ConfigPanelMsg::ResetRenderStageVariables(_)
+            | ConfigPanelMsg::Undo
+            | ConfigPanelMsg::Redo
+            | ConfigPanelMsg::EndHistoryRestore
+            | ConfigPanelMsg::Autosave
+            | ConfigPanelMsg::FlushJournal
+            | ConfigPanelMsg::ShowValidationTarget(_)
+            | ConfigPanelMsg::DismissValidationFindings => None,
+        }
+    }
+
     pub fn to_wvr_message(&self, config_panel: &ConfigPanel) -> Option<Message> {
         match &self {
             ConfigPanelMsg::StartProject => Some(Message::Start),
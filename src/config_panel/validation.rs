@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::discriminant;
+use std::path::{Path, PathBuf};
+
+use wvr::utils::load_available_filter_list;
+use wvr_data::config::project_config::{
+    FilterConfig, InputConfig, ProjectConfig, RenderStageConfig, SampledInput,
+};
+
+use super::graph::analyze_render_chain;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Part of the project a finding relates to, used to navigate to the matching widget.
+#[derive(Clone, Debug)]
+pub enum ValidationTarget {
+    RenderStage(usize),
+    FinalStage,
+    Input(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub target: ValidationTarget,
+    pub message: String,
+}
+
+pub fn get_sampled_input_name(sampled_input: &SampledInput) -> &str {
+    match sampled_input {
+        SampledInput::Linear(input_name)
+        | SampledInput::Nearest(input_name)
+        | SampledInput::Mipmaps(input_name) => input_name,
+    }
+}
+
+pub fn load_filter_list(project_path: &Path) -> HashMap<String, (PathBuf, FilterConfig, bool)> {
+    let mut available_filter_list =
+        load_available_filter_list(&wvr_data::get_filters_path(), true).unwrap_or_default();
+    available_filter_list.extend(
+        load_available_filter_list(&project_path.join("filters"), false).unwrap_or_default(),
+    );
+
+    available_filter_list
+}
+
+fn validate_render_stage(
+    target: ValidationTarget,
+    stage: &RenderStageConfig,
+    available_input_names: &HashSet<&str>,
+    available_filter_list: &HashMap<String, (PathBuf, FilterConfig, bool)>,
+    finding_list: &mut Vec<Finding>,
+) {
+    let mut report = |severity, message| {
+        finding_list.push(Finding {
+            severity,
+            target: target.clone(),
+            message,
+        })
+    };
+
+    let mut uniform_name_list: Vec<&String> = stage.inputs.keys().collect();
+    uniform_name_list.sort();
+    for uniform_name in uniform_name_list {
+        let input_name = get_sampled_input_name(&stage.inputs[uniform_name]);
+        if !input_name.is_empty() && !available_input_names.contains(input_name) {
+            report(
+                Severity::Error,
+                format!(
+                    "{:} samples \"{:}\", which is neither an input nor a stage",
+                    uniform_name, input_name
+                ),
+            );
+        }
+    }
+
+    let filter_config = match available_filter_list.get(&stage.filter) {
+        Some((_, filter_config, _)) => filter_config,
+        None => {
+            report(
+                Severity::Error,
+                format!("Unknown filter \"{:}\"", stage.filter),
+            );
+            return;
+        }
+    };
+
+    for uniform_name in &filter_config.inputs {
+        let is_connected = stage
+            .inputs
+            .get(uniform_name)
+            .map(|sampled_input| !get_sampled_input_name(sampled_input).is_empty())
+            .unwrap_or(false);
+        if !is_connected {
+            report(
+                Severity::Warning,
+                format!(
+                    "Filter input {:} is not connected to any source",
+                    uniform_name
+                ),
+            );
+        }
+    }
+
+    let mut variable_name_list: Vec<&String> = stage.variables.keys().collect();
+    variable_name_list.sort();
+    for variable_name in variable_name_list {
+        let (value, _) = &stage.variables[variable_name];
+        match filter_config.variables.get(variable_name) {
+            Some((default_value, _)) => {
+                if discriminant(value) != discriminant(default_value) {
+                    report(
+                        Severity::Error,
+                        format!(
+                            "Variable {:} holds {:?} while the filter expects a value like {:?}",
+                            variable_name, value, default_value
+                        ),
+                    );
+                }
+            }
+            None => report(
+                Severity::Warning,
+                format!(
+                    "Variable {:} is not declared by filter \"{:}\"",
+                    variable_name, stage.filter
+                ),
+            ),
+        }
+    }
+}
+
+fn validate_input(
+    project_path: &Path,
+    input_name: &str,
+    input_config: &InputConfig,
+    finding_list: &mut Vec<Finding>,
+) {
+    let (path, severity) = match input_config {
+        InputConfig::Video { path, .. } | InputConfig::Picture { path, .. } => {
+            (path, Severity::Error)
+        }
+        // Cameras may be plugged in later
+        InputConfig::Cam { path, .. } => (path, Severity::Warning),
+        InputConfig::Midi { .. } => return,
+    };
+
+    if !project_path.join(path).exists() {
+        finding_list.push(Finding {
+            severity,
+            target: ValidationTarget::Input(input_name.to_owned()),
+            message: format!("File {:} does not exist", path),
+        });
+    }
+}
+
+/// Checks a project for problems preventing it from rendering as expected.
+pub fn validate_project(project_path: &Path, config: &ProjectConfig) -> Vec<Finding> {
+    let mut finding_list = Vec::new();
+
+    let available_filter_list = load_filter_list(project_path);
+
+    let mut available_input_names: HashSet<&str> =
+        config.inputs.keys().map(String::as_str).collect();
+
+    for (stage_index, stage) in config.render_chain.iter().enumerate() {
+        let target = ValidationTarget::RenderStage(stage_index);
+
+        if config.inputs.contains_key(&stage.name) {
+            finding_list.push(Finding {
+                severity: Severity::Error,
+                target,
+                message: format!("Name \"{:}\" is already used by an input", stage.name),
+            });
+        } else if !available_input_names.insert(stage.name.as_str()) {
+            finding_list.push(Finding {
+                severity: Severity::Error,
+                target,
+                message: format!("Name \"{:}\" is used by several stages", stage.name),
+            });
+        }
+    }
+
+    for (stage_index, stage) in config.render_chain.iter().enumerate() {
+        validate_render_stage(
+            ValidationTarget::RenderStage(stage_index),
+            stage,
+            &available_input_names,
+            &available_filter_list,
+            &mut finding_list,
+        );
+    }
+
+    for (stage_index, stage_dependencies) in analyze_render_chain(&config.render_chain)
+        .iter()
+        .enumerate()
+    {
+        for &dependency_index in &stage_dependencies.forward_reference_list {
+            finding_list.push(Finding {
+                severity: Severity::Warning,
+                target: ValidationTarget::RenderStage(stage_index),
+                message: format!(
+                    "Samples \"{:}\", which is rendered later and thus one frame late",
+                    config.render_chain[dependency_index].name
+                ),
+            });
+        }
+    }
+
+    validate_render_stage(
+        ValidationTarget::FinalStage,
+        &config.final_stage,
+        &available_input_names,
+        &available_filter_list,
+        &mut finding_list,
+    );
+
+    let mut input_name_list: Vec<&String> = config.inputs.keys().collect();
+    input_name_list.sort();
+    for input_name in input_name_list {
+        validate_input(
+            project_path,
+            input_name,
+            &config.inputs[input_name],
+            &mut finding_list,
+        );
+    }
+
+    finding_list
+}
@@ -1,7 +1,8 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::mpsc::Sender;
 use std::thread;
-use std::{collections::HashMap, sync::atomic::AtomicBool};
-use std::{io::Write, sync::atomic::Ordering};
 use std::{
     path::{Path, PathBuf},
     process::Command,
@@ -12,21 +13,26 @@ use uuid::Uuid;
 
 use anyhow::Result;
 
-use glib::Cast;
+use gio::{AppInfoExt, FileExt};
+
+use glib::{Cast, ObjectExt, WeakRef};
 
 use gtk::{
     prelude::{GtkListStoreExtManual, NotebookExtManual, TreeSortableExtManual},
-    EditableSignals, EntryExt, Expander, Separator,
+    Align, ButtonsType, DialogExt, EditableSignals, EntryExt, Expander, GtkWindowExt,
+    MessageDialogBuilder, MessageType, Separator, Stack, StackExt, StackSwitcher, StackSwitcherExt,
+    WindowPosition,
 };
 use gtk::{
     Adjustment,
     Orientation::{Horizontal, Vertical},
-    SpinButton,
+    SpinButton, SpinButtonExt,
 };
 use gtk::{
-    AspectFrame, Button, ButtonExt, ComboBoxExt, ComboBoxText, ContainerExt, FrameExt, GLArea,
-    GLAreaExt, GtkListStoreExt, Label, LabelExt, Notebook, NotebookExt, Paned, PanedExt,
-    ReliefStyle, ShadowType, SortColumn, SortType, WidgetExt,
+    AspectFrame, BinExt, Button, ButtonExt, ComboBoxExt, ComboBoxText, ComboBoxTextExt,
+    ContainerExt, DirectionType, FrameExt, GLArea, GLAreaExt, GtkListStoreExt, Inhibit, Label,
+    LabelExt, Notebook, NotebookExt, Paned, PanedExt, ReliefStyle, ShadowType, SortColumn,
+    SortType, ToggleButton, ToggleButtonExt, WidgetExt,
 };
 
 use relm::{connect, Component, Relm, Update, Widget};
@@ -36,24 +42,52 @@ use strsim::levenshtein;
 
 use wvr::utils::load_available_filter_list;
 use wvr_com::data::{Message, RenderStageUpdate};
-use wvr_data::config::project_config::{Automation, InputConfig, ProjectConfig, SampledInput};
+use wvr_data::config::project_config::{
+    Automation, InputConfig, ProjectConfig, RenderStageConfig, SampledInput,
+};
 
+use crate::editor_state::EditorState;
+use crate::export;
+use crate::gallery;
 use crate::input_config;
+use crate::preferences::PanedPositions;
+use crate::recovery::{self, RecoveryJournal};
 use crate::server_config;
 use crate::stage_config;
+use crate::stage_config::preset;
+use crate::stage_state::StageState;
 use crate::view_config;
+use crate::wvr_frame::output::{OutputWindow, SharedOutputWindow};
+use crate::wvr_frame::preview::{SharedStagePreviewList, StagePreviewList};
+use crate::wvr_frame::{CaptureRequest, SharedCaptureQueue};
 
 use crate::input_config::InputConfigViewMsg;
+use crate::stage_config::clipboard;
+use crate::stage_config::graph_view::{self, GraphTopology, RenderGraphView, RenderGraphViewMsg};
 use crate::stage_config::view::{RenderStageConfigView, RenderStageConfigViewMsg};
+use crate::stage_config::StageTabLabel;
 
 use super::get_input_choice_list;
+use super::graph;
+use super::history::{self, History};
 use super::msg::ConfigPanelMsg;
+use super::validation::{self, Finding, Severity, ValidationTarget};
 
 pub struct Model {
     parent_relm: Relm<crate::main_window::MainWindow>,
     project_path: PathBuf,
     config: ProjectConfig,
     control_channel: Option<Sender<Message>>,
+    history: History,
+    journal: RecoveryJournal,
+    stage_state: StageState,
+    editor_state: EditorState,
+    stage_preview_list: SharedStagePreviewList,
+    output_window: SharedOutputWindow,
+    capture_queue: SharedCaptureQueue,
+    // Recording folder of the running renderer, which keeps the one it was started with
+    recording_folder: Option<PathBuf>,
+    paned_positions: PanedPositions,
 }
 
 pub struct ConfigPanel {
@@ -61,15 +95,27 @@ pub struct ConfigPanel {
 
     root: gtk::Box,
 
+    start_button: Button,
+    output_button: ToggleButton,
     final_stage_name_chooser: ComboBoxText,
+    bpm_spin_button: SpinButton,
+    validation_container: gtk::Box,
+    capture_gallery_container: gtk::Box,
+
+    left_container: Paned,
+    settings_container: gtk::Box,
 
     input_list_container: gtk::Box,
     input_config_widget_list: HashMap<Uuid, (String, InputConfig, gtk::Box)>,
 
+    stage_list_wrapper: gtk::Box,
     render_stage_config_list_container: Notebook,
     render_stage_config_widget_list: HashMap<Uuid, (Component<RenderStageConfigView>, gtk::Box)>,
+    stage_tab_label_list: HashMap<Uuid, StageTabLabel>,
     render_stage_order: Vec<Uuid>,
     created_render_stage_count: usize,
+    render_graph_view: Component<RenderGraphView>,
+    render_graph_topology: GraphTopology,
 
     glarea_wrapper: AspectFrame,
 
@@ -85,23 +131,529 @@ impl ConfigPanel {
             .map(|(index, _)| index)
     }
 
+    /// Config of the given stage, or of the stage of the current tab.
+    fn get_render_stage_config(&self, render_stage_id: Option<Uuid>) -> Option<RenderStageConfig> {
+        let render_stage_index = match render_stage_id {
+            Some(render_stage_id) => self.get_render_stage_index(&render_stage_id)?,
+            None => self.render_stage_config_list_container.get_current_page()? as usize,
+        };
+
+        self.model
+            .config
+            .render_chain
+            .get(render_stage_index)
+            .cloned()
+    }
+
+    fn show_warning_dialog(&self, title: &str, text: &str) {
+        let warning_message = MessageDialogBuilder::new()
+            .title(title)
+            .text(title)
+            .secondary_text(text)
+            .message_type(MessageType::Warning)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::Ok)
+            .attached_to(&self.root)
+            .modal(true)
+            .build();
+        warning_message.run();
+        warning_message.close();
+    }
+
     pub fn get_input_name(&self, input_id: &Uuid) -> Option<String> {
         self.input_config_widget_list
             .get(input_id)
             .map(|(input_name, _, _)| input_name.clone())
     }
 
-    fn save_config(&mut self, project_config_file_path: &Path) {
-        println!("Saving to {:?}", project_config_file_path);
+    fn send_wvr_message_list(&mut self, message_list: Vec<Message>) {
+        let mut sender_disconnected = false;
+        if let Some(control_channel) = &mut self.model.control_channel {
+            for message in message_list {
+                if control_channel.send(message).is_err() {
+                    sender_disconnected = true;
+                    break;
+                }
+            }
+        }
+        if sender_disconnected {
+            self.model.control_channel = None;
+            self.model.recording_folder = None;
+            self.refresh_capture_gallery();
+        }
+    }
+
+    fn record_edit(&mut self, merge_key: &str, edit: &str, previous_config: &ProjectConfig) {
+        match self
+            .model
+            .journal
+            .record_edit(merge_key, edit, previous_config)
+        {
+            Ok(true) => self.schedule_journal_flush(),
+            Ok(false) => (),
+            Err(e) => eprintln!("Failed to write recovery journal: {:?}", e),
+        }
+    }
+
+    /// Writes the last edit to the journal once it stops being merged into.
+    fn schedule_journal_flush(&self) {
+        let stream = self.relm.stream().clone();
+        glib::source::timeout_add_local(history::MERGE_DELAY.as_millis() as u32, move || {
+            stream.emit(ConfigPanelMsg::FlushJournal);
+            glib::source::Continue(false)
+        });
+    }
+
+    fn restore_config(&mut self, config: ProjectConfig) {
+        let message_list = history::build_sync_message_list(
+            &self
+                .model
+                .stage_state
+                .apply(&self.model.project_path, &self.model.config),
+            &self
+                .model
+                .stage_state
+                .apply(&self.model.project_path, &config),
+        );
+        self.model.config = config;
+
+        // Widgets emit change messages while being rebuilt, those are ignored until the
+        // end-of-restore marker queued after them is processed
+        self.model.history.suspend();
+        self.rebuild_views();
+        self.relm.stream().emit(ConfigPanelMsg::EndHistoryRestore);
+
+        self.send_wvr_message_list(message_list);
+
+        self.model
+            .parent_relm
+            .stream()
+            .emit(crate::main_window::Msg::UpdateConfig(
+                self.model.config.clone(),
+            ));
+    }
 
-        let config_path = project_config_file_path;
-        if let Ok(mut project_config_file) = std::fs::File::create(config_path) {
-            let config_as_bytes = serde_json::ser::to_string_pretty(&self.model.config)
-                .unwrap()
-                .into_bytes();
+    fn rebuild_views(&mut self) {
+        let current_page = self.render_stage_config_list_container.get_current_page();
 
-            project_config_file.write_all(&config_as_bytes).unwrap();
+        for children in &self.stage_list_wrapper.get_children() {
+            self.stage_list_wrapper.remove(children);
         }
+        if let Some(input_list_panel) = self.left_container.get_child2() {
+            self.left_container.remove(&input_list_panel);
+        }
+
+        self.input_config_widget_list.clear();
+        let (input_list_panel, input_list_container) = input_config::build_list_view(
+            &self.relm,
+            &self.model.project_path,
+            &mut self.input_config_widget_list,
+            &self.model.config.inputs,
+        );
+
+        self.render_stage_config_widget_list.clear();
+        self.stage_tab_label_list.clear();
+        let (render_stage_config_list_container, render_stage_order) =
+            stage_config::build_list_view(
+                &self.relm,
+                &self.model.project_path,
+                &self.model.config.render_chain,
+                &get_input_choice_list(&self.model.config),
+                &mut self.render_stage_config_widget_list,
+                &mut self.stage_tab_label_list,
+                &self.model.stage_preview_list,
+                &self.model.editor_state,
+                self.model.config.bpm as f64,
+            );
+
+        self.stage_list_wrapper
+            .add(&render_stage_config_list_container);
+        self.left_container.pack2(&input_list_panel, false, false);
+        self.left_container.show_all();
+
+        render_stage_config_list_container.set_current_page(current_page);
+
+        self.input_list_container = input_list_container;
+        self.render_stage_config_list_container = render_stage_config_list_container;
+        self.render_stage_order = render_stage_order;
+        self.created_render_stage_count = self.model.config.render_chain.len();
+
+        for children in &self.settings_container.get_children() {
+            self.settings_container.remove(children);
+        }
+        self.settings_container.add(&build_settings_panel(
+            &self.relm,
+            &self.model.project_path,
+            &self.model.config,
+        ));
+        self.settings_container.show_all();
+
+        self.bpm_spin_button.set_value(self.model.config.bpm as f64);
+        self.update_final_stage_chooser();
+        self.update_dependency_markers();
+        self.update_stage_state_toggles();
+        self.update_render_graph_view();
+        self.update_stage_preview_list();
+        self.refresh_capture_gallery();
+    }
+
+    /// Folder frames are recorded to, the one of the config until a renderer is started.
+    fn get_recording_folder(&self) -> PathBuf {
+        self.model.recording_folder.clone().unwrap_or_else(|| {
+            gallery::get_capture_folder(&self.model.project_path, &self.model.config.view)
+        })
+    }
+
+    fn refresh_capture_gallery(&self) {
+        for children in &self.capture_gallery_container.get_children() {
+            self.capture_gallery_container.remove(children);
+        }
+        self.capture_gallery_container
+            .add(&gallery::build_gallery_view(
+                &self.relm,
+                &self.get_recording_folder(),
+            ));
+        self.capture_gallery_container.show_all();
+    }
+
+    /// Asks the renderer to save its next frame to the recording folder.
+    fn capture_frame(&self) {
+        if self.model.config.server.enable || self.model.control_channel.is_none() {
+            self.show_warning_dialog(
+                "Nothing to capture",
+                "Start the project in the launcher to capture its output",
+            );
+            return;
+        }
+
+        let capture_folder = self.get_recording_folder();
+        if let Err(e) = std::fs::create_dir_all(&capture_folder) {
+            self.show_warning_dialog("Could not create the recording folder", &format!("{:?}", e));
+            return;
+        }
+
+        // The capture is written along with the next rendered frame
+        let stream = self.relm.stream().clone();
+        self.model
+            .capture_queue
+            .borrow_mut()
+            .push(CaptureRequest::Image(
+                gallery::get_new_capture_path(&capture_folder),
+                Box::new(move || stream.emit(ConfigPanelMsg::RefreshCaptureGallery)),
+            ));
+    }
+
+    // Variable edits are frequent and do not show in the graph, only its topology is sent
+    fn update_render_graph_view(&mut self) {
+        let render_graph_topology =
+            GraphTopology::new(&self.model.config, &self.render_stage_order);
+        if render_graph_topology == self.render_graph_topology {
+            return;
+        }
+        self.render_graph_topology = render_graph_topology;
+
+        self.render_graph_view.emit(RenderGraphViewMsg::SetConfig(
+            self.model.config.clone(),
+            self.render_stage_order.clone(),
+        ));
+    }
+
+    fn update_stage_preview_list(&self) {
+        let stage_list = self
+            .render_stage_order
+            .iter()
+            .copied()
+            .zip(
+                self.model
+                    .config
+                    .render_chain
+                    .iter()
+                    .map(|render_stage_config| render_stage_config.name.clone()),
+            )
+            .collect();
+        self.model
+            .stage_preview_list
+            .borrow_mut()
+            .set_stage_list(stage_list);
+    }
+
+    fn update_final_stage_chooser(&self) {
+        let input_name_store = self
+            .final_stage_name_chooser
+            .get_model()
+            .unwrap()
+            .downcast::<gtk::ListStore>()
+            .unwrap();
+        input_name_store.clear();
+
+        for name in &get_input_choice_list(&self.model.config) {
+            input_name_store.insert_with_values(None, &[0, 1], &[name, name]);
+        }
+
+        if let Some(final_stage_input) = self.model.config.final_stage.inputs.get("iChannel0") {
+            let input_name = match final_stage_input {
+                SampledInput::Linear(input_name)
+                | SampledInput::Nearest(input_name)
+                | SampledInput::Mipmaps(input_name) => input_name,
+            };
+            self.final_stage_name_chooser
+                .set_active_id(Some(input_name));
+        }
+    }
+
+    /// Marks the stages sampling later stages, which get the previous frame of those.
+    fn update_dependency_markers(&self) {
+        let stage_dependencies_list = graph::analyze_render_chain(&self.model.config.render_chain);
+
+        for (stage_id, stage_dependencies) in
+            self.render_stage_order.iter().zip(&stage_dependencies_list)
+        {
+            let dependency_marker = match self.stage_tab_label_list.get(stage_id) {
+                Some(stage_tab_label) => &stage_tab_label.dependency_marker,
+                None => continue,
+            };
+
+            let get_name_list = |stage_index_list: &[usize]| {
+                stage_index_list
+                    .iter()
+                    .map(|stage_index| {
+                        format!("\"{:}\"", self.model.config.render_chain[*stage_index].name)
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+
+            let mut tooltip_line_list = Vec::new();
+            if !stage_dependencies.feedback_source_list.is_empty() {
+                tooltip_line_list.push(format!(
+                    "Feedback loop: reads the previous frame of {:}",
+                    get_name_list(&stage_dependencies.feedback_source_list)
+                ));
+            }
+            if !stage_dependencies.forward_reference_list.is_empty() {
+                tooltip_line_list.push(format!(
+                    "Samples {:}, rendered later, one frame late. Auto-order fixes this.",
+                    get_name_list(&stage_dependencies.forward_reference_list)
+                ));
+            }
+
+            if tooltip_line_list.is_empty() {
+                dependency_marker.hide();
+            } else {
+                dependency_marker.set_text(
+                    if stage_dependencies.forward_reference_list.is_empty() {
+                        emoji::symbols::av_symbol::REPEAT_BUTTON
+                    } else {
+                        emoji::symbols::warning::WARNING
+                    },
+                );
+                dependency_marker.set_tooltip_text(Some(&tooltip_line_list.join("\n")));
+                dependency_marker.show();
+            }
+        }
+    }
+
+    /// Applies new bypass and solo toggles, both to the renderer and to the stage tabs.
+    fn set_stage_state(&mut self, stage_state: StageState) {
+        if stage_state == self.model.stage_state {
+            return;
+        }
+
+        let message_list = history::build_sync_message_list(
+            &self
+                .model
+                .stage_state
+                .apply(&self.model.project_path, &self.model.config),
+            &stage_state.apply(&self.model.project_path, &self.model.config),
+        );
+
+        self.model.stage_state = stage_state;
+        self.save_stage_state();
+
+        self.send_wvr_message_list(message_list);
+        self.update_stage_state_toggles();
+    }
+
+    fn save_stage_state(&self) {
+        if let Err(e) = self.model.stage_state.save(&self.model.project_path) {
+            eprintln!("Failed to save stage state: {:?}", e);
+        }
+    }
+
+    fn save_editor_state(&self) {
+        if let Err(e) = self.model.editor_state.save(&self.model.project_path) {
+            eprintln!("Failed to save editor state: {:?}", e);
+        }
+    }
+
+    // Toggling a button emits its message again, which is ignored as the state is unchanged
+    fn update_stage_state_toggles(&self) {
+        for (stage_id, render_stage_config) in self
+            .render_stage_order
+            .iter()
+            .zip(&self.model.config.render_chain)
+        {
+            if let Some(stage_tab_label) = self.stage_tab_label_list.get(stage_id) {
+                stage_tab_label.bypass_button.set_active(
+                    self.model
+                        .stage_state
+                        .is_bypassed(&render_stage_config.name),
+                );
+                stage_tab_label
+                    .solo_button
+                    .set_active(self.model.stage_state.is_solo(&render_stage_config.name));
+            }
+        }
+    }
+
+    /// Moves the stages so that each one is rendered after the stages it samples.
+    fn sort_stages(&self) {
+        let mut stage_order = self.render_stage_order.clone();
+
+        // Each move is applied on the order left by the previous ones
+        for (target_index, stage_index) in
+            graph::get_sorted_stage_order(&self.model.config.render_chain)
+                .into_iter()
+                .enumerate()
+        {
+            let stage_id = self.render_stage_order[stage_index];
+            if stage_order[target_index] != stage_id {
+                let original_index = stage_order
+                    .iter()
+                    .position(|candidate| candidate == &stage_id)
+                    .unwrap();
+                stage_order.remove(original_index);
+                stage_order.insert(target_index, stage_id);
+
+                self.relm
+                    .stream()
+                    .emit(ConfigPanelMsg::MoveStage(stage_id, target_index));
+            }
+        }
+    }
+
+    /// Checks the project and lists the findings above the settings.
+    fn validate(&self) -> Vec<Finding> {
+        let finding_list =
+            validation::validate_project(&self.model.project_path, &self.model.config);
+        self.update_validation_findings(&finding_list);
+
+        finding_list
+    }
+
+    fn update_validation_findings(&self, finding_list: &[Finding]) {
+        for children in &self.validation_container.get_children() {
+            self.validation_container.remove(children);
+        }
+
+        if finding_list.is_empty() {
+            self.validation_container.hide();
+            return;
+        }
+
+        let error_count = finding_list
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .count();
+
+        let header = gtk::Box::new(Horizontal, 4);
+        let summary_label = Label::new(Some(&format!(
+            "{:} error(s), {:} warning(s)",
+            error_count,
+            finding_list.len() - error_count
+        )));
+        summary_label.set_xalign(0.0);
+        summary_label.set_hexpand(true);
+
+        let dismiss_button = Button::new();
+        dismiss_button.set_label("Dismiss");
+        dismiss_button.set_relief(ReliefStyle::None);
+        connect!(
+            self.relm,
+            dismiss_button,
+            connect_clicked(_),
+            ConfigPanelMsg::DismissValidationFindings
+        );
+
+        header.add(&summary_label);
+        header.add(&dismiss_button);
+        self.validation_container.add(&header);
+
+        for finding in finding_list {
+            let target_name = match &finding.target {
+                ValidationTarget::RenderStage(stage_index) => self
+                    .model
+                    .config
+                    .render_chain
+                    .get(*stage_index)
+                    .map(|stage| format!("Stage \"{:}\"", stage.name))
+                    .unwrap_or_default(),
+                ValidationTarget::FinalStage => "Final stage".to_string(),
+                ValidationTarget::Input(input_name) => format!("Input \"{:}\"", input_name),
+            };
+
+            let finding_button = Button::new();
+            finding_button.set_relief(ReliefStyle::None);
+            finding_button.set_label(&format!(
+                "{:} {:}: {:}",
+                match finding.severity {
+                    Severity::Error => emoji::symbols::warning::NO_ENTRY,
+                    Severity::Warning => emoji::symbols::warning::WARNING,
+                },
+                target_name,
+                finding.message
+            ));
+            if let Some(label) = finding_button
+                .get_child()
+                .and_then(|child| child.downcast::<Label>().ok())
+            {
+                label.set_xalign(0.0);
+                label.set_line_wrap(true);
+            }
+
+            let target = finding.target.clone();
+            connect!(
+                self.relm,
+                finding_button,
+                connect_clicked(_),
+                ConfigPanelMsg::ShowValidationTarget(target.clone())
+            );
+
+            self.validation_container.add(&finding_button);
+        }
+
+        // show_all is a no-op on widgets flagged no_show_all
+        for children in &self.validation_container.get_children() {
+            children.show_all();
+        }
+        self.validation_container.show();
+    }
+
+    fn show_validation_target(&self, target: &ValidationTarget) {
+        match target {
+            ValidationTarget::RenderStage(stage_index) => {
+                self.render_stage_config_list_container
+                    .set_current_page(Some(*stage_index as u32));
+            }
+            ValidationTarget::FinalStage => {
+                self.final_stage_name_chooser.grab_focus();
+            }
+            ValidationTarget::Input(input_name) => {
+                if let Some((_, _, input_view_wrapper)) = self
+                    .input_config_widget_list
+                    .values()
+                    .find(|(candidate_name, _, _)| candidate_name == input_name)
+                {
+                    input_view_wrapper.child_focus(DirectionType::TabForward);
+                }
+            }
+        }
+    }
+
+    fn save_config(&mut self, project_config_file_path: &Path) -> Result<()> {
+        println!("Saving to {:?}", project_config_file_path);
+
+        crate::utils::save_config(project_config_file_path, &self.model.config)
     }
 
     fn start_wvr(&mut self) -> Result<()> {
@@ -133,7 +685,13 @@ impl ConfigPanel {
                 let order_sender = crate::wvr_frame::build_wvr_frame(
                     &glarea,
                     &self.model.project_path,
-                    &self.model.config,
+                    &self
+                        .model
+                        .stage_state
+                        .apply(&self.model.project_path, &self.model.config),
+                    self.model.stage_preview_list.clone(),
+                    self.model.output_window.clone(),
+                    self.model.capture_queue.clone(),
                 )?;
 
                 self.model.control_channel = Some(order_sender);
@@ -148,7 +706,13 @@ impl ConfigPanel {
             } else if server_config.ip == "127.0.0.1" {
                 let config_path = self.model.project_path.join("config.tmp.json");
 
-                self.save_config(&config_path);
+                crate::utils::save_config(
+                    &config_path,
+                    &self
+                        .model
+                        .stage_state
+                        .apply(&self.model.project_path, &self.model.config),
+                )?;
 
                 thread::spawn(move || {
                     Command::new("wvr")
@@ -181,28 +745,285 @@ impl ConfigPanel {
             self.model.control_channel = Some(order_sender);
         }
 
+        // The renderer reads the recording settings once, later edits apply on its next start
+        self.model.recording_folder = Some(gallery::get_capture_folder(
+            &self.model.project_path,
+            &self.model.config.view,
+        ));
+
         Ok(())
     }
 }
 
 impl Update for ConfigPanel {
     type Model = Model;
-    type ModelParam = (Relm<crate::main_window::MainWindow>, PathBuf, ProjectConfig);
+    type ModelParam = (
+        Relm<crate::main_window::MainWindow>,
+        PathBuf,
+        ProjectConfig,
+        PanedPositions,
+    );
     type Msg = ConfigPanelMsg;
 
-    fn model(
-        _: &Relm<Self>,
-        project: (Relm<crate::main_window::MainWindow>, PathBuf, ProjectConfig),
-    ) -> Self::Model {
+    fn model(_: &Relm<Self>, project: Self::ModelParam) -> Self::Model {
+        let journal = RecoveryJournal::new(&project.1, &project.2);
+
         Model {
             parent_relm: project.0,
             project_path: project.1,
             config: project.2,
             control_channel: None,
+            history: History::new(),
+            journal,
+            stage_state: StageState::load(&project.1),
+            editor_state: EditorState::load(&project.1),
+            stage_preview_list: StagePreviewList::new_shared(),
+            output_window: OutputWindow::new_shared(),
+            capture_queue: Rc::new(RefCell::new(Vec::new())),
+            recording_folder: None,
+            paned_positions: project.3,
         }
     }
 
     fn update(&mut self, event: ConfigPanelMsg) {
+        match &event {
+            ConfigPanelMsg::Undo => {
+                if let Some(config) = self.model.history.undo(&self.model.config) {
+                    let previous_config = self.model.config.clone();
+                    self.restore_config(config);
+                    self.record_edit("undo", "Undo", &previous_config);
+                }
+                return;
+            }
+            ConfigPanelMsg::Redo => {
+                if let Some(config) = self.model.history.redo(&self.model.config) {
+                    let previous_config = self.model.config.clone();
+                    self.restore_config(config);
+                    self.record_edit("redo", "Redo", &previous_config);
+                }
+                return;
+            }
+            ConfigPanelMsg::EndHistoryRestore => {
+                self.model.history.resume();
+                return;
+            }
+            ConfigPanelMsg::Autosave => {
+                if let Err(e) = self.model.journal.autosave(&self.model.config) {
+                    eprintln!("Failed to autosave project: {:?}", e);
+                }
+                return;
+            }
+            ConfigPanelMsg::FlushJournal => {
+                match self.model.journal.flush_idle_edit(&self.model.config) {
+                    Ok(true) => (),
+                    Ok(false) => self.schedule_journal_flush(),
+                    Err(e) => eprintln!("Failed to write recovery journal: {:?}", e),
+                }
+                return;
+            }
+            ConfigPanelMsg::ShowValidationTarget(target) => {
+                self.show_validation_target(target);
+                return;
+            }
+            ConfigPanelMsg::DismissValidationFindings => {
+                self.update_validation_findings(&[]);
+                return;
+            }
+            ConfigPanelMsg::ShowExportDialog => {
+                let parent = self
+                    .root
+                    .get_toplevel()
+                    .and_then(|toplevel| toplevel.downcast::<gtk::Window>().ok());
+                export::dialog::show_export_dialog(
+                    parent.as_ref(),
+                    &self.model.project_path,
+                    &self
+                        .model
+                        .stage_state
+                        .apply(&self.model.project_path, &self.model.config),
+                );
+                return;
+            }
+            ConfigPanelMsg::CaptureFrame => {
+                self.capture_frame();
+                return;
+            }
+            ConfigPanelMsg::RefreshCaptureGallery => {
+                self.refresh_capture_gallery();
+                return;
+            }
+            ConfigPanelMsg::OpenCapture(capture_path) => {
+                let capture_uri = gio::File::new_for_path(capture_path).get_uri();
+                if let Err(e) = gio::AppInfo::launch_default_for_uri(
+                    &capture_uri,
+                    None::<&gio::AppLaunchContext>,
+                ) {
+                    self.show_warning_dialog("Could not open capture", &format!("{:?}", e));
+                }
+                return;
+            }
+            ConfigPanelMsg::DeleteCapture(capture_path) => {
+                if let Err(e) =
+                    gio::File::new_for_path(capture_path).trash(None::<&gio::Cancellable>)
+                {
+                    self.show_warning_dialog("Could not delete capture", &format!("{:?}", e));
+                }
+                self.refresh_capture_gallery();
+                return;
+            }
+            ConfigPanelMsg::SetCaptureAsThumbnail(capture_path) => {
+                if let Err(e) = crate::thumbnail::set_thumbnail_from_file(
+                    &self.model.project_path,
+                    capture_path,
+                ) {
+                    self.show_warning_dialog(
+                        "Could not set project thumbnail",
+                        &format!("{:?}", e),
+                    );
+                }
+                return;
+            }
+            ConfigPanelMsg::SetOutputWindowVisible(visible) => {
+                let output_window = self.model.output_window.borrow();
+                if *visible != output_window.is_visible() {
+                    if *visible {
+                        output_window.show();
+                    } else {
+                        output_window.hide();
+                    }
+                }
+                // Closing the window from the window manager leaves the toggle behind
+                self.output_button.set_active(*visible);
+                return;
+            }
+            ConfigPanelMsg::SetOutputMonitor(monitor) => {
+                self.model.output_window.borrow_mut().set_monitor(*monitor);
+                return;
+            }
+            ConfigPanelMsg::SetOutputBlackout(blackout) => {
+                self.model.output_window.borrow().set_blackout(*blackout);
+                return;
+            }
+            ConfigPanelMsg::StartProject => {
+                // Errors would make the renderer fail or render garbage, warnings are only listed
+                let finding_list = self.validate();
+                if finding_list
+                    .iter()
+                    .any(|finding| finding.severity == Severity::Error)
+                {
+                    self.start_button
+                        .set_label(emoji::symbols::av_symbol::PLAY_BUTTON);
+                    return;
+                }
+            }
+            ConfigPanelMsg::Save => {
+                self.validate();
+            }
+            ConfigPanelMsg::SortStages => {
+                self.sort_stages();
+                return;
+            }
+            ConfigPanelMsg::DuplicateRenderStage(stage_id) => {
+                if let Some(mut render_stage_config) = self.get_render_stage_config(*stage_id) {
+                    clipboard::rename_copied_stage(&self.model.config, &mut render_stage_config);
+                    self.relm
+                        .stream()
+                        .emit(ConfigPanelMsg::AddRenderStage(render_stage_config));
+                }
+                return;
+            }
+            ConfigPanelMsg::CopyRenderStage(stage_id) => {
+                if let Some(render_stage_config) = self.get_render_stage_config(*stage_id) {
+                    if let Err(e) =
+                        clipboard::copy_render_stage(&self.model.project_path, &render_stage_config)
+                    {
+                        eprintln!("Failed to copy render stage: {:?}", e);
+                    }
+                }
+                return;
+            }
+            ConfigPanelMsg::SetRenderStageBypass(stage_id, bypass) => {
+                if let Some(render_stage_config) = self.get_render_stage_config(Some(*stage_id)) {
+                    let mut stage_state = self.model.stage_state.clone();
+                    if *bypass {
+                        stage_state
+                            .bypassed_stage_list
+                            .insert(render_stage_config.name);
+                    } else {
+                        stage_state
+                            .bypassed_stage_list
+                            .remove(&render_stage_config.name);
+                    }
+                    self.set_stage_state(stage_state);
+                }
+                return;
+            }
+            ConfigPanelMsg::SetRenderStageSolo(stage_id, solo) => {
+                if let Some(render_stage_config) = self.get_render_stage_config(Some(*stage_id)) {
+                    let mut stage_state = self.model.stage_state.clone();
+                    if *solo {
+                        stage_state.solo_stage = Some(render_stage_config.name);
+                    } else if stage_state.is_solo(&render_stage_config.name) {
+                        // Releasing solo brings the final stage back to the chosen input
+                        stage_state.solo_stage = None;
+                    }
+                    self.set_stage_state(stage_state);
+                }
+                return;
+            }
+            ConfigPanelMsg::SetRenderStageColorEditor(
+                stage_id,
+                variable_name,
+                use_color_editor,
+            ) => {
+                // The stage view already shows the chosen editor, nothing is rendered differently
+                if let Some(render_stage_config) = self.get_render_stage_config(Some(*stage_id)) {
+                    self.model.editor_state.set_color_editor(
+                        &render_stage_config.name,
+                        variable_name,
+                        *use_color_editor,
+                    );
+                    self.save_editor_state();
+                }
+                return;
+            }
+            ConfigPanelMsg::ResetRenderStageVariables(stage_id) => {
+                // The stage view sends each reset back as an update, recorded in the history
+                if let Some((widget, _)) = self.render_stage_config_widget_list.get(stage_id) {
+                    widget.emit(RenderStageConfigViewMsg::ResetAllVariables);
+                }
+                return;
+            }
+            ConfigPanelMsg::PasteRenderStage => {
+                match clipboard::paste_render_stage(&self.model.project_path, &self.model.config) {
+                    Ok(pasted_render_stage) => {
+                        if !pasted_render_stage.warning_list.is_empty() {
+                            self.show_warning_dialog(
+                                "The pasted stage does not fully fit this project",
+                                &pasted_render_stage.warning_list.join("\n"),
+                            );
+                        }
+                        self.relm.stream().emit(ConfigPanelMsg::AddRenderStage(
+                            pasted_render_stage.render_stage,
+                        ));
+                    }
+                    Err(e) => eprintln!("Failed to paste render stage: {:?}", e),
+                }
+                return;
+            }
+            // Moving a page emits a reorder signal, which reports the move once it is done
+            ConfigPanelMsg::MoveStage(stage_id, target_index)
+                if self.get_render_stage_index(stage_id) == Some(*target_index) =>
+            {
+                return;
+            }
+            _ => (),
+        }
+
+        let history_entry = event
+            .get_history_merge_key()
+            .map(|merge_key| (merge_key, self.model.config.clone()));
+
         let mut render_stage_update_message_list = Vec::new();
 
         if let Some(message) = event.to_wvr_message(self) {
@@ -216,11 +1037,16 @@ impl Update for ConfigPanel {
         match &event {
             ConfigPanelMsg::StartProject => {
                 self.start_wvr().unwrap();
+                self.refresh_capture_gallery();
             }
             ConfigPanelMsg::PauseProject => (),
             ConfigPanelMsg::StopProject => (),
             ConfigPanelMsg::SetBpm(bpm) => {
                 self.model.config.bpm = *bpm as f32;
+                for (render_stage_config_widget, _) in self.render_stage_config_widget_list.values()
+                {
+                    render_stage_config_widget.emit(RenderStageConfigViewMsg::SetBpm(*bpm));
+                }
             }
             ConfigPanelMsg::SetWidth(width) => {
                 self.model.config.view.width = *width;
@@ -240,6 +1066,15 @@ impl Update for ConfigPanel {
             ConfigPanelMsg::SetScreenshot(screenshot) => {
                 self.model.config.view.screenshot = *screenshot;
             }
+            ConfigPanelMsg::SetScreenshotPath(screenshot_path) => {
+                self.model.config.view.screenshot_path = gallery::get_stored_capture_folder(
+                    &self.model.project_path,
+                    Path::new(screenshot_path),
+                );
+            }
+            ConfigPanelMsg::SetScreenshotFrameCount(frame_count) => {
+                self.model.config.view.screenshot_frame_count = *frame_count;
+            }
             ConfigPanelMsg::SetFullscreen(fullscreen) => {
                 self.model.config.view.fullscreen = *fullscreen;
             }
@@ -252,7 +1087,29 @@ impl Update for ConfigPanel {
             ConfigPanelMsg::SetServerEnabled(enable) => self.model.config.server.enable = *enable,
 
             ConfigPanelMsg::Save => {
-                self.save_config(&self.model.project_path.join("config.json"));
+                match self.save_config(&self.model.project_path.join("config.json")) {
+                    Ok(()) => {
+                        // The thumbnail is rendered along with the next frame of the preview
+                        if !self.model.config.server.enable && self.model.control_channel.is_some()
+                        {
+                            self.model
+                                .capture_queue
+                                .borrow_mut()
+                                .push(CaptureRequest::Thumbnail(self.model.project_path.clone()));
+                        }
+
+                        self.model.journal.clear(&self.model.config);
+                        self.model.parent_relm.stream().emit(
+                            crate::main_window::Msg::ProjectSaved(self.model.config.clone()),
+                        );
+                    }
+                    Err(error) => {
+                        eprintln!("Failed to save project: {:?}", error);
+                        self.model.parent_relm.stream().emit(
+                            crate::main_window::Msg::ProjectSaveFailed(format!("{:?}", error)),
+                        );
+                    }
+                }
             }
 
             ConfigPanelMsg::AddInput(input_name, input_config) => {
@@ -382,34 +1239,26 @@ impl Update for ConfigPanel {
                 let (id, wrapper, render_stage_config_view) =
                     stage_config::build_render_stage_config_row(
                         &self.relm,
+                        &self.model.project_path,
                         render_stage_config,
                         &input_choice_list,
                         &available_filter_list,
+                        &self.model.stage_preview_list,
+                        self.model
+                            .editor_state
+                            .get_color_editor_list(&render_stage_config.name),
+                        self.model.config.bpm as f64,
                     );
 
-                let page_label_container = gtk::Box::new(Horizontal, 4);
-
-                let page_label = Label::new(Some(&render_stage_config.name));
-                page_label.set_xalign(0.0);
-                page_label.set_hexpand(true);
-
-                let remove_button = Button::new();
-                remove_button.set_relief(ReliefStyle::None);
-                remove_button.set_label("x");
-                {
-                    connect!(
-                        self.relm,
-                        remove_button,
-                        connect_clicked(_),
-                        Some(ConfigPanelMsg::RemoveRenderStage(id))
-                    );
-                }
-
-                page_label_container.add(&remove_button);
-                page_label_container.add(&page_label);
+                let stage_tab_label = stage_config::build_page_label(
+                    &self.relm,
+                    id,
+                    &render_stage_config.name,
+                    &self.model.stage_preview_list,
+                );
 
                 self.render_stage_config_list_container
-                    .append_page(&wrapper, Some(&page_label_container));
+                    .append_page(&wrapper, Some(&stage_tab_label.container));
                 self.render_stage_config_list_container
                     .set_tab_reorderable(&wrapper, true);
                 self.render_stage_config_list_container
@@ -430,7 +1279,6 @@ impl Update for ConfigPanel {
                     );
                 }
 
-                page_label_container.show_all();
                 wrapper.show_all();
 
                 self.model
@@ -442,6 +1290,7 @@ impl Update for ConfigPanel {
 
                 self.render_stage_config_widget_list
                     .insert(id, (render_stage_config_view, wrapper));
+                self.stage_tab_label_list.insert(id, stage_tab_label);
 
                 self.render_stage_config_list_container
                     .set_current_page(Some(
@@ -454,13 +1303,30 @@ impl Update for ConfigPanel {
                     self.render_stage_config_widget_list.get(&id)
                 {
                     if let Some(render_stage_index) = self.get_render_stage_index(id) {
-                        self.model.config.render_chain.remove(render_stage_index);
+                        let render_stage_config =
+                            self.model.config.render_chain.remove(render_stage_index);
                         self.render_stage_config_list_container
                             .remove(render_stage_config_view_wrapper);
+
+                        self.model
+                            .stage_state
+                            .remove_stage(&render_stage_config.name);
+                        self.save_stage_state();
+                        self.model
+                            .editor_state
+                            .remove_stage(&render_stage_config.name);
+                        self.save_editor_state();
+                        if let Err(e) = preset::remove_stage_presets(
+                            &self.model.project_path,
+                            &render_stage_config.name,
+                        ) {
+                            eprintln!("Failed to remove stage presets: {:?}", e);
+                        }
                     }
                 }
 
                 self.render_stage_config_widget_list.remove(&id);
+                self.stage_tab_label_list.remove(&id);
                 self.render_stage_order.remove(
                     self.render_stage_order
                         .iter()
@@ -505,6 +1371,31 @@ impl Update for ConfigPanel {
                     }
                 }
             }
+            ConfigPanelMsg::ApplyRenderStagePreset(id, preset) => {
+                if let Some(render_stage_index) = self.get_render_stage_index(id) {
+                    if let Some(ref mut config) =
+                        self.model.config.render_chain.get_mut(render_stage_index)
+                    {
+                        let previous_config = config.clone();
+
+                        // The stage view only sends variables the filter declares, including
+                        // those the stage did not set yet
+                        for (variable_name, variable) in preset {
+                            config
+                                .variables
+                                .insert(variable_name.clone(), variable.clone());
+                        }
+
+                        // Only the changed variables are sent, all of them at once
+                        for update in
+                            history::build_render_stage_message_list(&previous_config, config)
+                        {
+                            render_stage_update_message_list
+                                .push(Message::UpdateRenderStage(render_stage_index, update));
+                        }
+                    }
+                }
+            }
             ConfigPanelMsg::UpdateRenderStageInput(id, input_name, new_input_value) => {
                 if let Some(render_stage_index) = self.get_render_stage_index(id) {
                     if let Some(ref mut config) =
@@ -515,6 +1406,16 @@ impl Update for ConfigPanel {
                             .insert(input_name.clone(), new_input_value.clone());
                     }
                 }
+
+                // Edits may come from the graph view, the stage view is kept in sync
+                if let Some((render_stage_config_view, _)) =
+                    self.render_stage_config_widget_list.get(id)
+                {
+                    render_stage_config_view.emit(RenderStageConfigViewMsg::SetInput(
+                        input_name.clone(),
+                        new_input_value.clone(),
+                    ));
+                }
             }
             ConfigPanelMsg::UpdateRenderStageFilterModeParams(id, new_filter_mode_params) => {
                 if let Some(render_stage_index) = self.get_render_stage_index(id) {
@@ -535,31 +1436,29 @@ impl Update for ConfigPanel {
                 }
             }
             ConfigPanelMsg::UpdateRenderStageName(id, new_name) => {
-                if let Some((_, render_stage_config_view_wrapper)) =
-                    self.render_stage_config_widget_list.get(&id)
-                {
-                    if let Ok(page_label_container) = self
-                        .render_stage_config_list_container
-                        .get_tab_label(render_stage_config_view_wrapper)
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                    {
-                        if let Some(page_label) = page_label_container.get_children().get(1) {
-                            if let Some(page_label) = page_label.downcast_ref::<Label>() {
-                                page_label.set_text(new_name.as_str());
-                            }
-                        }
-                    }
+                if let Some(stage_tab_label) = self.stage_tab_label_list.get(&id) {
+                    stage_tab_label.name_label.set_text(new_name.as_str());
 
                     if let Some(render_stage_index) = self.get_render_stage_index(id) {
                         if let Some(ref mut config) =
                             self.model.config.render_chain.get_mut(render_stage_index)
                         {
                             if &config.name != new_name {
+                                self.model.stage_state.rename_stage(&config.name, new_name);
+                                self.model.editor_state.rename_stage(&config.name, new_name);
+                                if let Err(e) = preset::rename_stage_presets(
+                                    &self.model.project_path,
+                                    &config.name,
+                                    new_name,
+                                ) {
+                                    eprintln!("Failed to rename stage presets: {:?}", e);
+                                }
                                 config.name = new_name.clone();
                                 input_list_changed = true;
                             }
                         }
+                        self.save_stage_state();
+                        self.save_editor_state();
                     }
                 }
             }
@@ -575,6 +1474,22 @@ impl Update for ConfigPanel {
             ConfigPanelMsg::MoveStage(stage_id, target_index) => {
                 let original_index = self.get_render_stage_index(stage_id).unwrap();
 
+                // Pages dragged by the user are already in place, sorted ones are moved here
+                if let Some((_, render_stage_config_view_wrapper)) =
+                    self.render_stage_config_widget_list.get(stage_id)
+                {
+                    if self
+                        .render_stage_config_list_container
+                        .page_num(render_stage_config_view_wrapper)
+                        != Some(*target_index as u32)
+                    {
+                        self.render_stage_config_list_container.reorder_child(
+                            render_stage_config_view_wrapper,
+                            Some(*target_index as u32),
+                        );
+                    }
+                }
+
                 let render_stage_config = self.model.config.render_chain.remove(original_index);
                 self.model
                     .config
@@ -591,7 +1506,38 @@ impl Update for ConfigPanel {
                     .final_stage
                     .inputs
                     .insert("iChannel0".to_string(), input.clone());
+
+                let input_name = validation::get_sampled_input_name(input);
+                if self.final_stage_name_chooser.get_active_id().as_deref() != Some(input_name) {
+                    self.final_stage_name_chooser
+                        .set_active_id(Some(input_name));
+                }
             }
+
+            ConfigPanelMsg::Undo
+            | ConfigPanelMsg::Redo
+            | ConfigPanelMsg::EndHistoryRestore
+            | ConfigPanelMsg::Autosave
+            | ConfigPanelMsg::FlushJournal
+            | ConfigPanelMsg::SetOutputWindowVisible(_)
+            | ConfigPanelMsg::SetOutputMonitor(_)
+            | ConfigPanelMsg::SetOutputBlackout(_)
+            | ConfigPanelMsg::ShowExportDialog
+            | ConfigPanelMsg::CaptureFrame
+            | ConfigPanelMsg::RefreshCaptureGallery
+            | ConfigPanelMsg::OpenCapture(_)
+            | ConfigPanelMsg::DeleteCapture(_)
+            | ConfigPanelMsg::SetCaptureAsThumbnail(_)
+            | ConfigPanelMsg::SortStages
+            | ConfigPanelMsg::DuplicateRenderStage(_)
+            | ConfigPanelMsg::CopyRenderStage(_)
+            | ConfigPanelMsg::PasteRenderStage
+            | ConfigPanelMsg::SetRenderStageBypass(_, _)
+            | ConfigPanelMsg::SetRenderStageSolo(_, _)
+            | ConfigPanelMsg::SetRenderStageColorEditor(_, _, _)
+            | ConfigPanelMsg::ResetRenderStageVariables(_)
+            | ConfigPanelMsg::ShowValidationTarget(_)
+            | ConfigPanelMsg::DismissValidationFindings => (),
         }
 
         if input_list_changed {
@@ -673,17 +1619,23 @@ impl Update for ConfigPanel {
             }
         }
 
-        let mut sender_disconnected = false;
-        if let Some(control_channel) = &mut self.model.control_channel {
-            for message in render_stage_update_message_list {
-                if control_channel.send(message).is_err() {
-                    sender_disconnected = true;
-                    break;
-                }
-            }
-        }
-        if sender_disconnected {
-            self.model.control_channel = None;
+        let render_stage_update_message_list = self
+            .model
+            .stage_state
+            .filter_message_list(&self.model.config, render_stage_update_message_list);
+        self.send_wvr_message_list(render_stage_update_message_list);
+
+        self.update_dependency_markers();
+        self.update_stage_state_toggles();
+        self.update_render_graph_view();
+        self.update_stage_preview_list();
+
+        if let Some((merge_key, previous_config)) = history_entry {
+            self.record_edit(&merge_key, &format!("{:?}", event), &previous_config);
+
+            self.model
+                .history
+                .record(merge_key, previous_config, &self.model.config);
         }
 
         self.model
@@ -705,23 +1657,61 @@ impl Widget for ConfigPanel {
     fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
         let mut input_config_widget_list = HashMap::new();
         let mut render_stage_config_widget_list = HashMap::new();
+        let mut stage_tab_label_list = HashMap::new();
 
         let model = model;
 
         let root = gtk::Box::new(Vertical, 2);
 
-        let (control_container, final_stage_name_chooser) =
+        let (control_container, start_button, final_stage_name_chooser, bpm_spin_button) =
             build_control_widget(relm, &model.config);
 
+        let (output_container, output_button) = build_output_widget(relm);
+        control_container.add(&Separator::new(Vertical));
+        control_container.add(&output_container);
+
+        {
+            let output_window = model.output_window.borrow();
+            connect!(
+                relm,
+                output_window.get_window(),
+                connect_delete_event(_, _),
+                return (
+                    Some(ConfigPanelMsg::SetOutputWindowVisible(false)),
+                    Inhibit(true)
+                )
+            );
+        }
+        {
+            // The output window would otherwise outlive the project it shows
+            let output_window = model.output_window.clone();
+            root.connect_destroy(move |_| output_window.borrow().destroy());
+        }
+
         let project_container = Paned::new(Horizontal);
         project_container.set_wide_handle(true);
 
         let left_container = Paned::new(Vertical);
         left_container.set_wide_handle(true);
 
-        let view_config_panel = view_config::build_view(relm, &model.config.view);
-
-        let server_config_panel = server_config::build_view(relm, &model.config.server);
+        if let Some(position) = model.paned_positions.project {
+            project_container.set_position(position);
+        }
+        if let Some(position) = model.paned_positions.render_stage_list {
+            left_container.set_position(position);
+        }
+        connect!(
+            model.parent_relm,
+            project_container,
+            connect_property_position_notify(paned),
+            crate::main_window::Msg::SetProjectPanedPosition(paned.get_position())
+        );
+        connect!(
+            model.parent_relm,
+            left_container,
+            connect_property_position_notify(paned),
+            crate::main_window::Msg::SetRenderStageListPanedPosition(paned.get_position())
+        );
 
         let (input_list_panel, input_list_container) = input_config::build_list_view(
             relm,
@@ -737,8 +1727,35 @@ impl Widget for ConfigPanel {
                 &model.config.render_chain,
                 &get_input_choice_list(&model.config),
                 &mut render_stage_config_widget_list,
+                &mut stage_tab_label_list,
+                &model.stage_preview_list,
+                &model.editor_state,
+                model.config.bpm as f64,
             );
 
+        let stage_list_wrapper = gtk::Box::new(Vertical, 0);
+        stage_list_wrapper.add(&render_stage_config_list_container);
+
+        let (graph_view_wrapper, render_graph_view) = graph_view::build_graph_view(
+            relm,
+            &model.project_path,
+            &model.config,
+            &render_stage_order,
+        );
+        let render_graph_topology = GraphTopology::new(&model.config, &render_stage_order);
+
+        let stage_view_stack = Stack::new();
+        stage_view_stack.add_titled(&stage_list_wrapper, "list", "Stages");
+        stage_view_stack.add_titled(&graph_view_wrapper, "graph", "Graph");
+
+        let stage_view_switcher = StackSwitcher::new();
+        stage_view_switcher.set_stack(Some(&stage_view_stack));
+        stage_view_switcher.set_halign(Align::Center);
+
+        let stage_view_container = gtk::Box::new(Vertical, 2);
+        stage_view_container.add(&stage_view_switcher);
+        stage_view_container.add(&stage_view_stack);
+
         let view_container = gtk::Box::new(Vertical, 0);
 
         let glarea_wrapper = AspectFrame::new(None, 0.5, 0.5, 16.0 / 9.0, true);
@@ -748,25 +1765,39 @@ impl Widget for ConfigPanel {
         glarea_wrapper.set_hexpand(true);
         glarea_wrapper.set_vexpand(true);
 
-        let view_config_wrapper = Expander::new(Some("View config"));
-        view_config_wrapper.add(&view_config_panel);
-        view_config_wrapper.activate();
-
-        let server_config_wrapper = Expander::new(Some("Server config"));
-        server_config_wrapper.add(&server_config_panel);
+        let settings_container = gtk::Box::new(Vertical, 0);
+        settings_container.add(&build_settings_panel(
+            relm,
+            &model.project_path,
+            &model.config,
+        ));
 
         let general_config_panel = gtk::Box::new(Vertical, 8);
         general_config_panel.set_property_margin(8);
 
+        let validation_container = gtk::Box::new(Vertical, 0);
+        validation_container.set_widget_name("validation-findings");
+        validation_container.set_no_show_all(true);
+
         general_config_panel.add(&control_container);
+        general_config_panel.add(&validation_container);
         general_config_panel.add(&Separator::new(Horizontal));
-        general_config_panel.add(&view_config_wrapper);
-        general_config_panel.add(&server_config_wrapper);
+        general_config_panel.add(&settings_container);
+
+        let capture_gallery_container = gtk::Box::new(Vertical, 0);
+        capture_gallery_container.add(&gallery::build_gallery_view(
+            relm,
+            &gallery::get_capture_folder(&model.project_path, &model.config.view),
+        ));
+
+        let capture_gallery_wrapper = Expander::new(Some("Captures"));
+        capture_gallery_wrapper.add(&capture_gallery_container);
+        general_config_panel.add(&capture_gallery_wrapper);
 
         view_container.add(&glarea_wrapper);
         view_container.add(&general_config_panel);
 
-        left_container.pack1(&render_stage_config_list_container, true, false);
+        left_container.pack1(&stage_view_container, true, false);
         left_container.pack2(&input_list_panel, false, false);
 
         project_container.pack1(&left_container, true, false);
@@ -775,9 +1806,25 @@ impl Widget for ConfigPanel {
         root.add(&project_container);
         root.show_all();
 
+        {
+            // The timer stops once the panel is removed from the main window
+            let stream = relm.stream().clone();
+            let root: WeakRef<gtk::Box> = root.downgrade();
+            glib::source::timeout_add_seconds_local(
+                recovery::AUTOSAVE_INTERVAL_SECONDS,
+                move || match root.upgrade() {
+                    Some(root) if root.get_parent().is_some() => {
+                        stream.emit(ConfigPanelMsg::Autosave);
+                        glib::source::Continue(true)
+                    }
+                    _ => glib::source::Continue(false),
+                },
+            );
+        }
+
         let created_render_stage_count = model.config.render_chain.len();
 
-        Self {
+        let config_panel = Self {
             model,
 
             root,
@@ -787,23 +1834,110 @@ impl Widget for ConfigPanel {
             input_config_widget_list,
 
             created_render_stage_count,
+            stage_list_wrapper,
             render_stage_config_list_container,
             render_stage_config_widget_list,
+            stage_tab_label_list,
             render_stage_order,
+            render_graph_view,
+            render_graph_topology,
 
+            start_button,
+            output_button,
             final_stage_name_chooser,
+            bpm_spin_button,
+            validation_container,
+            capture_gallery_container,
+
+            left_container,
+            settings_container,
 
             glarea_wrapper,
 
             relm: relm.clone(),
-        }
+        };
+        config_panel.update_dependency_markers();
+        config_panel.update_stage_state_toggles();
+        config_panel.update_stage_preview_list();
+
+        config_panel
     }
 }
 
+/// Builds the controls of the output window: visibility, monitor and blackout.
+fn build_output_widget(relm: &Relm<ConfigPanel>) -> (gtk::Box, ToggleButton) {
+    let output_container = gtk::Box::new(Horizontal, 4);
+
+    let output_button = ToggleButton::with_label("Output");
+    output_button.set_tooltip_text(Some(
+        "Show the rendered output fullscreen on the chosen monitor",
+    ));
+    connect!(
+        relm,
+        output_button,
+        connect_toggled(output_button),
+        ConfigPanelMsg::SetOutputWindowVisible(output_button.get_active())
+    );
+
+    let monitor_chooser = ComboBoxText::new();
+    for (monitor_index, monitor_name) in OutputWindow::get_monitor_name_list().iter().enumerate() {
+        monitor_chooser.append(Some(&monitor_index.to_string()), monitor_name);
+    }
+    monitor_chooser.set_active(Some(0));
+    monitor_chooser.set_tooltip_text(Some("Output monitor"));
+    connect!(
+        relm,
+        monitor_chooser,
+        connect_changed(chooser),
+        chooser
+            .get_active()
+            .map(|monitor_index| ConfigPanelMsg::SetOutputMonitor(monitor_index as i32))
+    );
+
+    let blackout_button = ToggleButton::with_label("Blackout");
+    blackout_button.set_tooltip_text(Some("Paint the output black at once"));
+    connect!(
+        relm,
+        blackout_button,
+        connect_toggled(blackout_button),
+        ConfigPanelMsg::SetOutputBlackout(blackout_button.get_active())
+    );
+
+    output_container.add(&output_button);
+    output_container.add(&monitor_chooser);
+    output_container.add(&blackout_button);
+
+    (output_container, output_button)
+}
+
+fn build_settings_panel(
+    relm: &Relm<ConfigPanel>,
+    project_path: &Path,
+    config: &ProjectConfig,
+) -> gtk::Box {
+    let settings_panel = gtk::Box::new(Vertical, 8);
+
+    let view_config_panel = view_config::build_view(relm, project_path, &config.view);
+
+    let server_config_panel = server_config::build_view(relm, &config.server);
+
+    let view_config_wrapper = Expander::new(Some("View config"));
+    view_config_wrapper.add(&view_config_panel);
+    view_config_wrapper.activate();
+
+    let server_config_wrapper = Expander::new(Some("Server config"));
+    server_config_wrapper.add(&server_config_panel);
+
+    settings_panel.add(&view_config_wrapper);
+    settings_panel.add(&server_config_wrapper);
+
+    settings_panel
+}
+
 fn build_control_widget(
     relm: &Relm<ConfigPanel>,
     config: &ProjectConfig,
-) -> (gtk::Box, ComboBoxText) {
+) -> (gtk::Box, Button, ComboBoxText, SpinButton) {
     let control_container = gtk::Box::new(Horizontal, 4);
     control_container.set_widget_name("control-bar");
     control_container.set_property_margin(2);
@@ -812,14 +1946,12 @@ fn build_control_widget(
     start_button.set_relief(ReliefStyle::None);
     start_button.set_label(emoji::symbols::av_symbol::PLAY_BUTTON);
 
-    let play_state = AtomicBool::new(false);
+    // The label holds the play state, so that a refused start can reset it
     connect!(relm, start_button, connect_clicked(start_button), {
-        if play_state.load(Ordering::Relaxed) {
-            play_state.store(false, Ordering::Relaxed);
+        if start_button.get_label().as_deref() == Some(emoji::symbols::av_symbol::PAUSE_BUTTON) {
             start_button.set_label(emoji::symbols::av_symbol::PLAY_BUTTON);
             ConfigPanelMsg::PauseProject
         } else {
-            play_state.store(true, Ordering::Relaxed);
             start_button.set_label(emoji::symbols::av_symbol::PAUSE_BUTTON);
             ConfigPanelMsg::StartProject
         }
@@ -906,5 +2038,10 @@ fn build_control_widget(
     control_container.add(&Separator::new(Vertical));
     control_container.add(&start_button);
 
-    (control_container, final_stage_name_chooser)
+    (
+        control_container,
+        start_button,
+        final_stage_name_chooser,
+        bpm_spin_button,
+    )
 }
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+
+pub const EDITOR_STATE_FILE_NAME: &str = "editor_state.json";
+
+/// How the stage editors of a project are shown, which never changes what is rendered.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct EditorState {
+    /// Per stage, variables switched to or from the color editor by hand.
+    pub color_editor_list: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+impl EditorState {
+    pub fn load(project_path: &Path) -> Self {
+        let editor_state_path = project_path.join(EDITOR_STATE_FILE_NAME);
+        if !editor_state_path.exists() {
+            return Self::default();
+        }
+
+        match File::open(&editor_state_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(serde_json::from_reader::<File, Self>(file)?))
+        {
+            Ok(editor_state) => editor_state,
+            Err(e) => {
+                eprintln!(
+                    "Failed to load editor state {:?}: {:?}",
+                    editor_state_path, e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<()> {
+        let editor_state_path = project_path.join(EDITOR_STATE_FILE_NAME);
+
+        if self == &Self::default() {
+            if editor_state_path.exists() {
+                std::fs::remove_file(&editor_state_path)?;
+            }
+            return Ok(());
+        }
+
+        let editor_state_as_bytes = serde_json::ser::to_string_pretty(self)?.into_bytes();
+
+        let mut editor_state_file = File::create(editor_state_path)?;
+        editor_state_file.write_all(&editor_state_as_bytes)?;
+
+        Ok(())
+    }
+
+    pub fn rename_stage(&mut self, old_name: &str, new_name: &str) {
+        if let Some(color_editor_list) = self.color_editor_list.remove(old_name) {
+            self.color_editor_list
+                .insert(new_name.to_owned(), color_editor_list);
+        }
+    }
+
+    pub fn remove_stage(&mut self, stage_name: &str) {
+        self.color_editor_list.remove(stage_name);
+    }
+
+    pub fn get_color_editor_list(&self, stage_name: &str) -> BTreeMap<String, bool> {
+        self.color_editor_list
+            .get(stage_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_color_editor(
+        &mut self,
+        stage_name: &str,
+        variable_name: &str,
+        use_color_editor: bool,
+    ) {
+        self.color_editor_list
+            .entry(stage_name.to_owned())
+            .or_default()
+            .insert(variable_name.to_owned(), use_color_editor);
+    }
+}
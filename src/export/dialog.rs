@@ -0,0 +1,391 @@
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use nfd2::Response;
+
+use gtk::{
+    Adjustment, Button, ButtonExt, ButtonsType, ComboBoxExt, ComboBoxText, ComboBoxTextExt,
+    ContainerExt, Dialog, DialogExt, DialogFlags, Entry, EntryExt, GLArea, GLAreaExt, Grid,
+    GridExt, GtkWindowExt, Label, LabelExt, MessageDialog, MessageType, ProgressBar,
+    ProgressBarExt, ResponseType, SpinButton, SpinButtonExt, WidgetExt, Window,
+};
+
+use wvr_data::config::project_config::ProjectConfig;
+
+use super::ffmpeg::{self, FfmpegSink, VideoPreset};
+use super::png::PngSequenceSink;
+use super::{ExportFormat, ExportJob, ExportLength, ExportSettings, FrameSink, OfflineRenderer};
+
+const PNG_SEQUENCE_ID: &str = "png";
+
+const DEFAULT_BEAT_COUNT: f64 = 16.0;
+
+// Kept apart from the captures of the screenshot folder
+const DEFAULT_EXPORT_FOLDER_NAME: &str = "export";
+
+struct ExportForm {
+    format_chooser: ComboBoxText,
+    width_spin_button: SpinButton,
+    height_spin_button: SpinButton,
+    fps_spin_button: SpinButton,
+    length_spin_button: SpinButton,
+    length_unit_chooser: ComboBoxText,
+    output_path_entry: Entry,
+}
+
+impl ExportForm {
+    fn get_settings(&self) -> ExportSettings {
+        let length = self.length_spin_button.get_value();
+
+        ExportSettings {
+            format: self.get_format(),
+            width: self.width_spin_button.get_value_as_int() as u32,
+            height: self.height_spin_button.get_value_as_int() as u32,
+            fps: self.fps_spin_button.get_value(),
+            length: if self.length_unit_chooser.get_active_id().as_deref() == Some("frames") {
+                ExportLength::Frames(length.round() as u64)
+            } else {
+                ExportLength::Beats(length)
+            },
+            output_path: PathBuf::from(self.output_path_entry.get_text().as_str()),
+        }
+    }
+
+    fn get_format(&self) -> ExportFormat {
+        self.format_chooser
+            .get_active_id()
+            .and_then(|format_id| VideoPreset::from_id(&format_id))
+            .map(ExportFormat::Video)
+            .unwrap_or(ExportFormat::PngSequence)
+    }
+
+    /// Adapts the output path to the chosen format, a folder for images and a file for videos.
+    fn update_output_path(&self) {
+        let output_path = PathBuf::from(self.output_path_entry.get_text().as_str());
+        let output_path = match self.get_format() {
+            ExportFormat::PngSequence => match output_path.extension() {
+                Some(_) => output_path
+                    .parent()
+                    .map(Path::to_owned)
+                    .unwrap_or(output_path),
+                None => output_path,
+            },
+            ExportFormat::Video(preset) => ffmpeg::get_video_output_path(&output_path, preset),
+        };
+
+        self.output_path_entry
+            .set_text(output_path.to_str().unwrap_or_default());
+    }
+
+    fn set_sensitive(&self, sensitive: bool) {
+        self.format_chooser.set_sensitive(sensitive);
+        self.width_spin_button.set_sensitive(sensitive);
+        self.height_spin_button.set_sensitive(sensitive);
+        self.fps_spin_button.set_sensitive(sensitive);
+        self.length_spin_button.set_sensitive(sensitive);
+        self.length_unit_chooser.set_sensitive(sensitive);
+        self.output_path_entry.set_sensitive(sensitive);
+    }
+}
+
+fn build_spin_button(value: f64, min: f64, max: f64, digits: u32) -> SpinButton {
+    let spin_button = SpinButton::new(
+        Some(&Adjustment::new(value, min, max, 1.0, 10.0, 0.0)),
+        1.0,
+        digits,
+    );
+    spin_button.set_hexpand(true);
+
+    spin_button
+}
+
+/// Describes what an export would overwrite, if anything.
+fn get_overwrite_warning(settings: &ExportSettings) -> Option<String> {
+    match settings.format {
+        ExportFormat::PngSequence => {
+            let is_empty = std::fs::read_dir(&settings.output_path)
+                .map(|mut entry_list| entry_list.next().is_none())
+                .unwrap_or(true);
+
+            if is_empty {
+                None
+            } else {
+                Some(format!(
+                    "The folder {:?} is not empty, frames already exported there will be overwritten",
+                    settings.output_path
+                ))
+            }
+        }
+        ExportFormat::Video(_) => {
+            if settings.output_path.exists() {
+                Some(format!(
+                    "{:?} already exists and will be replaced",
+                    settings.output_path
+                ))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn confirm_overwrite(dialog: &Dialog, warning: &str) -> bool {
+    let overwrite_dialog = MessageDialog::new(
+        Some(dialog),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+        MessageType::Warning,
+        ButtonsType::None,
+        warning,
+    );
+    overwrite_dialog.add_button("Cancel", ResponseType::Cancel);
+    overwrite_dialog.add_button("Overwrite", ResponseType::Accept);
+
+    let response = overwrite_dialog.run();
+    overwrite_dialog.close();
+
+    response == ResponseType::Accept
+}
+
+fn start_export(
+    glarea: &GLArea,
+    project_path: &Path,
+    config: &ProjectConfig,
+    settings: &ExportSettings,
+) -> Result<ExportJob> {
+    let renderer = OfflineRenderer::new(glarea, project_path, config, settings)?;
+    let sink: Box<dyn FrameSink> = match settings.format {
+        ExportFormat::PngSequence => Box::new(PngSequenceSink::new(settings.output_path.clone())?),
+        ExportFormat::Video(preset) => Box::new(FfmpegSink::new(preset, settings)?),
+    };
+
+    Ok(ExportJob::new(
+        Box::new(renderer),
+        sink,
+        settings.get_frame_count(config.bpm as f64),
+    ))
+}
+
+/// Shows the dialog rendering the project offline to images or a video, at a fixed resolution
+/// and frame rate.
+pub fn show_export_dialog(parent: Option<&Window>, project_path: &Path, config: &ProjectConfig) {
+    let dialog = Dialog::with_buttons(
+        Some("Export"),
+        parent,
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Export", ResponseType::Accept),
+        ],
+    );
+
+    let form_grid = Grid::new();
+    form_grid.set_row_spacing(4);
+    form_grid.set_column_spacing(8);
+    form_grid.set_property_margin(8);
+
+    let form = Rc::new(ExportForm {
+        format_chooser: ComboBoxText::new(),
+        width_spin_button: build_spin_button(config.view.width as f64, 1.0, 16384.0, 0),
+        height_spin_button: build_spin_button(config.view.height as f64, 1.0, 16384.0, 0),
+        fps_spin_button: build_spin_button(config.view.target_fps as f64, 1.0, 240.0, 2),
+        length_spin_button: build_spin_button(DEFAULT_BEAT_COUNT, 1.0, 1_000_000.0, 2),
+        length_unit_chooser: ComboBoxText::new(),
+        output_path_entry: Entry::new(),
+    });
+
+    form.format_chooser
+        .append(Some(PNG_SEQUENCE_ID), "PNG image sequence");
+    if FfmpegSink::is_available() {
+        for preset in VideoPreset::ALL.iter() {
+            form.format_chooser
+                .append(Some(preset.get_id()), preset.get_label());
+        }
+    } else {
+        form.format_chooser
+            .set_tooltip_text(Some("Install ffmpeg to export videos"));
+    }
+    form.format_chooser.set_active_id(Some(PNG_SEQUENCE_ID));
+
+    form.length_unit_chooser.append(Some("beats"), "beats");
+    form.length_unit_chooser.append(Some("frames"), "frames");
+    form.length_unit_chooser.set_active_id(Some("beats"));
+
+    form.output_path_entry.set_hexpand(true);
+    form.output_path_entry.set_text(
+        project_path
+            .join(DEFAULT_EXPORT_FOLDER_NAME)
+            .to_str()
+            .unwrap_or_default(),
+    );
+
+    let browse_button = Button::with_label("…");
+    {
+        let form = form.clone();
+        let project_path = project_path.to_owned();
+        browse_button.connect_clicked(move |_| {
+            let response = match form.get_format() {
+                ExportFormat::PngSequence => nfd2::open_pick_folder(Some(&project_path)),
+                ExportFormat::Video(preset) => {
+                    nfd2::open_save_dialog(Some(preset.get_extension()), Some(&project_path))
+                }
+            };
+
+            match response {
+                Ok(Response::Okay(output_path)) => {
+                    form.output_path_entry
+                        .set_text(output_path.to_str().unwrap_or_default());
+                    form.update_output_path();
+                }
+                Ok(_) => (),
+                Err(e) => eprintln!("Failed to open file dialog: {:?}", e),
+            }
+        });
+    }
+    {
+        let form_handle = form.clone();
+        form.format_chooser
+            .connect_changed(move |_| form_handle.update_output_path());
+    }
+
+    let output_path_container = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    output_path_container.add(&form.output_path_entry);
+    output_path_container.add(&browse_button);
+
+    form_grid.attach(&Label::new(Some("Format")), 0, 0, 1, 1);
+    form_grid.attach(&form.format_chooser, 1, 0, 2, 1);
+    form_grid.attach(&Label::new(Some("Width")), 0, 1, 1, 1);
+    form_grid.attach(&form.width_spin_button, 1, 1, 2, 1);
+    form_grid.attach(&Label::new(Some("Height")), 0, 2, 1, 1);
+    form_grid.attach(&form.height_spin_button, 1, 2, 2, 1);
+    form_grid.attach(&Label::new(Some("Frame rate")), 0, 3, 1, 1);
+    form_grid.attach(&form.fps_spin_button, 1, 3, 2, 1);
+    form_grid.attach(&Label::new(Some("Length")), 0, 4, 1, 1);
+    form_grid.attach(&form.length_spin_button, 1, 4, 1, 1);
+    form_grid.attach(&form.length_unit_chooser, 2, 4, 1, 1);
+    form_grid.attach(&Label::new(Some("Output")), 0, 5, 1, 1);
+    form_grid.attach(&output_path_container, 1, 5, 2, 1);
+
+    let progress_bar = ProgressBar::new();
+    progress_bar.set_show_text(true);
+    progress_bar.set_property_margin(8);
+
+    let status_label = Label::new(None);
+    status_label.set_line_wrap(true);
+    status_label.set_xalign(0.0);
+    status_label.set_property_margin(8);
+
+    // Never shown, it only provides the GL context frames are rendered with
+    let glarea = GLArea::new();
+    glarea.set_required_version(3, 2);
+    glarea.set_no_show_all(true);
+
+    let content_area = dialog.get_content_area();
+    content_area.add(&form_grid);
+    content_area.add(&progress_bar);
+    content_area.add(&status_label);
+    content_area.add(&glarea);
+
+    let export_job: Rc<RefCell<Option<ExportJob>>> = Rc::new(RefCell::new(None));
+    let cancel_requested = Rc::new(Cell::new(false));
+
+    {
+        let project_path = project_path.to_owned();
+        let config = config.clone();
+        dialog.connect_response(move |dialog, response| match response {
+            ResponseType::Accept => {
+                if export_job.borrow().is_some() {
+                    return;
+                }
+
+                let settings = form.get_settings();
+                if let Some(warning) = get_overwrite_warning(&settings) {
+                    if !confirm_overwrite(dialog, &warning) {
+                        return;
+                    }
+                }
+
+                glarea.realize();
+                let job = match start_export(&glarea, &project_path, &config, &settings) {
+                    Ok(job) => job,
+                    Err(e) => {
+                        status_label.set_text(&format!("Failed to start export: {:?}", e));
+                        return;
+                    }
+                };
+
+                let frame_count = job.get_frame_count();
+                export_job.replace(Some(job));
+                cancel_requested.set(false);
+                form.set_sensitive(false);
+                dialog.set_response_sensitive(ResponseType::Accept, false);
+                status_label.set_text(&format!(
+                    "Exporting {:} frames to {:?}",
+                    frame_count, settings.output_path
+                ));
+
+                let export_job = export_job.clone();
+                let cancel_requested = cancel_requested.clone();
+                let form = form.clone();
+                let dialog = dialog.clone();
+                let progress_bar = progress_bar.clone();
+                let status_label = status_label.clone();
+                glib::idle_add_local(move || {
+                    let result = if cancel_requested.get() {
+                        Err(None)
+                    } else {
+                        match export_job.borrow_mut().as_mut() {
+                            Some(job) => match job.step() {
+                                Ok(is_over) => {
+                                    progress_bar.set_fraction(
+                                        job.get_frame_index() as f64
+                                            / job.get_frame_count().max(1) as f64,
+                                    );
+                                    progress_bar.set_text(Some(&format!(
+                                        "{:} / {:}",
+                                        job.get_frame_index(),
+                                        job.get_frame_count()
+                                    )));
+
+                                    if is_over {
+                                        Ok(job.get_frame_count())
+                                    } else {
+                                        return glib::Continue(true);
+                                    }
+                                }
+                                Err(e) => Err(Some(e)),
+                            },
+                            None => Err(None),
+                        }
+                    };
+
+                    match result {
+                        Ok(frame_count) => {
+                            status_label.set_text(&format!("Exported {:} frames", frame_count))
+                        }
+                        Err(Some(e)) => status_label.set_text(&format!("Export failed: {:?}", e)),
+                        Err(None) => status_label.set_text("Export cancelled"),
+                    }
+
+                    export_job.replace(None);
+                    form.set_sensitive(true);
+                    dialog.set_response_sensitive(ResponseType::Accept, true);
+
+                    glib::Continue(false)
+                });
+            }
+            _ => {
+                // Cancelling a running export keeps the dialog open to show where it stopped
+                if export_job.borrow().is_some() {
+                    cancel_requested.set(true);
+                } else {
+                    dialog.close();
+                }
+            }
+        });
+    }
+
+    dialog.show_all();
+}
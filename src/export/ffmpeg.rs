@@ -0,0 +1,206 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use glium::texture::RawImage2d;
+
+use super::{ExportSettings, FrameSink};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VideoPreset {
+    H264,
+    ProRes,
+    PngMkv,
+}
+
+impl VideoPreset {
+    pub const ALL: [VideoPreset; 3] = [VideoPreset::H264, VideoPreset::ProRes, VideoPreset::PngMkv];
+
+    pub fn get_id(&self) -> &'static str {
+        match self {
+            VideoPreset::H264 => "h264",
+            VideoPreset::ProRes => "prores",
+            VideoPreset::PngMkv => "png_mkv",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.get_id() == id)
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            VideoPreset::H264 => "H.264 video (mp4)",
+            VideoPreset::ProRes => "ProRes 422 HQ video (mov)",
+            VideoPreset::PngMkv => "Lossless PNG video (mkv)",
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            VideoPreset::H264 => "mp4",
+            VideoPreset::ProRes => "mov",
+            VideoPreset::PngMkv => "mkv",
+        }
+    }
+
+    fn get_codec_args(&self) -> &'static [&'static str] {
+        match self {
+            // Chroma subsampling needs even dimensions, which the scale filter enforces
+            VideoPreset::H264 => &[
+                "-vf",
+                "vflip,scale=trunc(iw/2)*2:trunc(ih/2)*2",
+                "-c:v",
+                "libx264",
+                "-preset",
+                "medium",
+                "-crf",
+                "18",
+                "-pix_fmt",
+                "yuv420p",
+            ],
+            VideoPreset::ProRes => &[
+                "-vf",
+                "vflip",
+                "-c:v",
+                "prores_ks",
+                "-profile:v",
+                "3",
+                "-pix_fmt",
+                "yuv422p10le",
+            ],
+            VideoPreset::PngMkv => &["-vf", "vflip", "-c:v", "png"],
+        }
+    }
+}
+
+/// Encodes frames to a video file by piping them raw into a local `ffmpeg` process.
+pub struct FfmpegSink {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    // ffmpeg blocks once its error output is full, so it is drained as it comes
+    stderr_reader: Option<JoinHandle<String>>,
+}
+
+impl FfmpegSink {
+    pub fn new(preset: VideoPreset, settings: &ExportSettings) -> Result<Self> {
+        if let Some(parent_path) = settings.output_path.parent() {
+            std::fs::create_dir_all(parent_path)?;
+        }
+
+        let mut child = Command::new("ffmpeg")
+            .args(&["-y", "-hide_banner", "-nostats", "-loglevel", "error"])
+            .args(&["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .arg("-s")
+            .arg(format!("{:}x{:}", settings.width, settings.height))
+            .arg("-r")
+            .arg(settings.fps.to_string())
+            .args(&["-i", "-"])
+            .args(preset.get_codec_args())
+            .arg(&settings.output_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run ffmpeg, is it installed and in the PATH?")?;
+
+        let stdin = child.stdin.take();
+        let stderr_reader = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut error_output = String::new();
+                let _ = stderr.read_to_string(&mut error_output);
+                error_output
+            })
+        });
+
+        Ok(Self {
+            child: Some(child),
+            stdin,
+            stderr_reader,
+        })
+    }
+
+    pub fn is_available() -> bool {
+        Command::new("ffmpeg")
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    /// Closes the input of ffmpeg and waits for it to write the end of the file.
+    fn wait(&mut self) -> Result<()> {
+        self.stdin.take();
+
+        let status = match self.child.take() {
+            Some(mut child) => child.wait()?,
+            None => return Ok(()),
+        };
+        let error_output = self
+            .stderr_reader
+            .take()
+            .and_then(|stderr_reader| stderr_reader.join().ok())
+            .unwrap_or_default();
+
+        if !status.success() {
+            bail!("ffmpeg failed ({:}): {:}", status, error_output.trim());
+        }
+
+        Ok(())
+    }
+}
+
+impl FrameSink for FfmpegSink {
+    fn write_frame(&mut self, frame_index: u64, pixels: &RawImage2d<u8>) -> Result<()> {
+        let stdin = match &mut self.stdin {
+            Some(stdin) => stdin,
+            None => bail!("ffmpeg is not running anymore"),
+        };
+
+        if let Err(e) = stdin.write_all(&pixels.data) {
+            // A closed pipe means ffmpeg stopped, its own error tells why
+            self.wait()?;
+            return Err(anyhow!(
+                "Failed to send frame {:} to ffmpeg: {:?}",
+                frame_index,
+                e
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.wait()
+    }
+}
+
+impl Drop for FfmpegSink {
+    // Reached without `finish` when the export is cancelled, the partial file is left as is
+    fn drop(&mut self) {
+        self.stdin.take();
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Output path of a video export, based on the path used for image sequences.
+pub fn get_video_output_path(output_path: &Path, preset: VideoPreset) -> std::path::PathBuf {
+    if output_path.extension().is_some() {
+        output_path.with_extension(preset.get_extension())
+    } else {
+        output_path
+            .join("export")
+            .with_extension(preset.get_extension())
+    }
+}
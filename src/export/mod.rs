@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+
+use glium::texture::RawImage2d;
+
+use gtk::{GLArea, GLAreaExt};
+
+use wvr::Wvr;
+use wvr_com::data::{Message, SetInfo};
+use wvr_data::config::project_config::ProjectConfig;
+
+use crate::wvr_frame;
+
+pub mod dialog;
+pub mod ffmpeg;
+pub mod png;
+
+use ffmpeg::VideoPreset;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportLength {
+    Beats(f64),
+    Frames(u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    PngSequence,
+    Video(VideoPreset),
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportSettings {
+    pub format: ExportFormat,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub length: ExportLength,
+    /// Folder of an image sequence, or file of a video.
+    pub output_path: PathBuf,
+}
+
+impl ExportSettings {
+    pub fn get_frame_count(&self, bpm: f64) -> u64 {
+        match self.length {
+            ExportLength::Frames(frame_count) => frame_count,
+            ExportLength::Beats(beat_count) => {
+                if bpm <= 0.0 {
+                    0
+                } else {
+                    (beat_count * 60.0 / bpm * self.fps).round() as u64
+                }
+            }
+        }
+    }
+}
+
+/// Producer of the exported frames, in render order.
+pub trait FrameSource {
+    /// Advances the project by one frame and reads it back, RGBA with the bottom row first.
+    fn render_frame(&mut self) -> Result<RawImage2d<'static, u8>>;
+}
+
+/// Consumer of the exported frames, in render order.
+pub trait FrameSink {
+    /// Receives the pixels of a frame as read from the GPU, RGBA with the bottom row first.
+    fn write_frame(&mut self, frame_index: u64, pixels: &RawImage2d<u8>) -> Result<()>;
+
+    /// Called once all the frames are written, not when the export is cancelled.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Renders a project frame by frame in an offscreen framebuffer, at a fixed frame rate.
+pub struct OfflineRenderer {
+    glarea: GLArea,
+    context: Rc<glium::backend::Context>,
+    app: Wvr,
+    width: u32,
+    height: u32,
+}
+
+impl OfflineRenderer {
+    /// Builds a renderer on the GL context of `glarea`, which only has to be realized.
+    pub fn new(
+        glarea: &GLArea,
+        project_path: &Path,
+        config: &ProjectConfig,
+        settings: &ExportSettings,
+    ) -> Result<Self> {
+        if let Some(error) = glarea.get_error() {
+            return Err(anyhow::Error::from(error)).context("No GL context to render with");
+        }
+        glarea.make_current();
+
+        let context = wvr_frame::build_glium_context(glarea)?;
+
+        // Locked speed makes time advance by exactly one frame on each update
+        let mut config = config.clone();
+        config.view.width = settings.width as i64;
+        config.view.height = settings.height as i64;
+        config.view.target_fps = settings.fps as f32;
+        config.view.locked_speed = true;
+        config.view.dynamic = false;
+        config.view.vsync = false;
+        config.view.fullscreen = false;
+        config.view.screenshot = false;
+
+        let mut app =
+            Wvr::new(project_path, config, &context).context("Failed creating Wvr app")?;
+        app.handle_message(&context, &Message::Set(SetInfo::DynamicResolution(false)))?;
+        app.handle_message(&context, &Message::Start)?;
+
+        Ok(Self {
+            glarea: glarea.clone(),
+            context,
+            app,
+            width: settings.width,
+            height: settings.height,
+        })
+    }
+}
+
+impl FrameSource for OfflineRenderer {
+    fn render_frame(&mut self) -> Result<RawImage2d<'static, u8>> {
+        self.glarea.make_current();
+
+        self.app
+            .update(&self.context, (self.width as usize, self.height as usize))?;
+        self.app.render_stages(&self.context)?;
+
+        wvr_frame::render_final_stage_to_pixels(
+            &mut self.app,
+            &self.context,
+            self.width,
+            self.height,
+        )
+    }
+}
+
+/// Export in progress, advanced one frame at a time so that the interface stays responsive.
+pub struct ExportJob {
+    source: Box<dyn FrameSource>,
+    sink: Box<dyn FrameSink>,
+    frame_index: u64,
+    frame_count: u64,
+    is_finished: bool,
+}
+
+impl ExportJob {
+    pub fn new(source: Box<dyn FrameSource>, sink: Box<dyn FrameSink>, frame_count: u64) -> Self {
+        Self {
+            source,
+            sink,
+            frame_index: 0,
+            frame_count,
+            is_finished: false,
+        }
+    }
+
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Renders and writes the next frame, returns whether the export is over.
+    pub fn step(&mut self) -> Result<bool> {
+        if self.frame_index < self.frame_count {
+            let pixels = self.source.render_frame()?;
+            self.sink.write_frame(self.frame_index, &pixels)?;
+            self.frame_index += 1;
+        }
+
+        if self.frame_index >= self.frame_count {
+            if !self.is_finished {
+                self.is_finished = true;
+                self.sink.finish()?;
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    struct FakeSource;
+
+    impl FrameSource for FakeSource {
+        fn render_frame(&mut self) -> Result<RawImage2d<'static, u8>> {
+            Ok(RawImage2d::from_raw_rgba(vec![0; 4], (1, 1)))
+        }
+    }
+
+    #[derive(Default)]
+    struct SinkCount {
+        frame_index_list: Vec<u64>,
+        finish_count: usize,
+    }
+
+    struct CountingSink(Rc<RefCell<SinkCount>>);
+
+    impl FrameSink for CountingSink {
+        fn write_frame(&mut self, frame_index: u64, _pixels: &RawImage2d<u8>) -> Result<()> {
+            self.0.borrow_mut().frame_index_list.push(frame_index);
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            self.0.borrow_mut().finish_count += 1;
+            Ok(())
+        }
+    }
+
+    fn build_job(frame_count: u64) -> (ExportJob, Rc<RefCell<SinkCount>>) {
+        let sink_count = Rc::new(RefCell::new(SinkCount::default()));
+        let job = ExportJob::new(
+            Box::new(FakeSource),
+            Box::new(CountingSink(sink_count.clone())),
+            frame_count,
+        );
+
+        (job, sink_count)
+    }
+
+    fn build_settings(fps: f64, length: ExportLength) -> ExportSettings {
+        ExportSettings {
+            format: ExportFormat::PngSequence,
+            width: 640,
+            height: 360,
+            fps,
+            length,
+            output_path: PathBuf::from("export"),
+        }
+    }
+
+    #[test]
+    fn step_writes_every_frame_once() {
+        let (mut job, sink_count) = build_job(5);
+
+        let mut step_count = 0;
+        while !job.step().unwrap() {
+            step_count += 1;
+        }
+
+        assert_eq!(step_count, 4);
+        assert_eq!(job.get_frame_index(), job.get_frame_count());
+        assert_eq!(sink_count.borrow().frame_index_list, vec![0, 1, 2, 3, 4]);
+        assert_eq!(sink_count.borrow().finish_count, 1);
+    }
+
+    #[test]
+    fn finish_is_called_once() {
+        let (mut job, sink_count) = build_job(2);
+
+        while !job.step().unwrap() {}
+        assert!(job.step().unwrap());
+
+        assert_eq!(sink_count.borrow().frame_index_list.len(), 2);
+        assert_eq!(sink_count.borrow().finish_count, 1);
+    }
+
+    #[test]
+    fn empty_export_only_finishes() {
+        let (mut job, sink_count) = build_job(0);
+
+        assert!(job.step().unwrap());
+
+        assert!(sink_count.borrow().frame_index_list.is_empty());
+        assert_eq!(sink_count.borrow().finish_count, 1);
+    }
+
+    #[test]
+    fn dropped_job_does_not_finish() {
+        let (mut job, sink_count) = build_job(10);
+
+        assert!(!job.step().unwrap());
+        assert!(!job.step().unwrap());
+        drop(job);
+
+        assert_eq!(sink_count.borrow().frame_index_list, vec![0, 1]);
+        assert_eq!(sink_count.borrow().finish_count, 0);
+    }
+
+    #[test]
+    fn frame_count_of_frames() {
+        let settings = build_settings(30.0, ExportLength::Frames(42));
+
+        assert_eq!(settings.get_frame_count(120.0), 42);
+        assert_eq!(settings.get_frame_count(0.0), 42);
+    }
+
+    #[test]
+    fn frame_count_of_beats() {
+        assert_eq!(
+            build_settings(30.0, ExportLength::Beats(16.0)).get_frame_count(120.0),
+            240
+        );
+        // 60 / 90 * 25 = 16.67 frames per beat
+        assert_eq!(
+            build_settings(25.0, ExportLength::Beats(1.0)).get_frame_count(90.0),
+            17
+        );
+        assert_eq!(
+            build_settings(25.0, ExportLength::Beats(0.5)).get_frame_count(90.0),
+            8
+        );
+    }
+
+    #[test]
+    fn frame_count_without_tempo() {
+        let settings = build_settings(30.0, ExportLength::Beats(16.0));
+
+        assert_eq!(settings.get_frame_count(0.0), 0);
+        assert_eq!(settings.get_frame_count(-120.0), 0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, bail, Result};
+
+use glium::texture::RawImage2d;
+
+use crate::wvr_frame::preview::build_image_surface;
+
+use super::FrameSink;
+
+// Frames waiting for the encoder, rendering waits once this many are pending
+const PENDING_FRAME_LIMIT: usize = 8;
+
+type PendingFrame = (PathBuf, RawImage2d<'static, u8>);
+
+/// Writes each frame to a numbered PNG file of the output folder.
+///
+/// Frames are encoded on a worker thread, the first encoding error stops the export.
+pub struct PngSequenceSink {
+    output_path: PathBuf,
+    frame_sender: Option<SyncSender<PendingFrame>>,
+    encoder: Option<JoinHandle<Result<()>>>,
+}
+
+impl PngSequenceSink {
+    pub fn new(output_path: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&output_path)?;
+
+        let (frame_sender, frame_receiver) = sync_channel::<PendingFrame>(PENDING_FRAME_LIMIT);
+        let encoder = thread::spawn(move || {
+            for (frame_path, pixels) in frame_receiver {
+                write_png(&frame_path, &pixels)?;
+            }
+
+            Ok(())
+        });
+
+        Ok(Self {
+            output_path,
+            frame_sender: Some(frame_sender),
+            encoder: Some(encoder),
+        })
+    }
+
+    /// Waits for the pending frames to be written, returns the error which stopped the encoder.
+    fn join_encoder(&mut self) -> Result<()> {
+        self.frame_sender = None;
+
+        match self.encoder.take() {
+            Some(encoder) => encoder
+                .join()
+                .map_err(|_| anyhow!("The PNG encoder panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl FrameSink for PngSequenceSink {
+    fn write_frame(&mut self, frame_index: u64, pixels: &RawImage2d<u8>) -> Result<()> {
+        let frame_path = self
+            .output_path
+            .join(format!("frame_{:06}.png", frame_index));
+        let pixels = RawImage2d {
+            data: pixels.data.to_vec().into(),
+            width: pixels.width,
+            height: pixels.height,
+            format: pixels.format,
+        };
+
+        let is_sent = match &self.frame_sender {
+            Some(frame_sender) => frame_sender.send((frame_path, pixels)).is_ok(),
+            None => false,
+        };
+        if !is_sent {
+            self.join_encoder()?;
+            bail!("The PNG encoder stopped");
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.join_encoder()
+    }
+}
+
+/// Encodes pixels read back from a texture to a PNG file.
+pub fn write_png(path: &Path, pixels: &RawImage2d<u8>) -> Result<()> {
+    let surface = build_image_surface(&pixels.data, pixels.width, pixels.height)?;
+
+    let mut image_file = File::create(path)?;
+    surface
+        .write_to_png(&mut image_file)
+        .map_err(|e| anyhow!("Failed to encode {:?}: {:?}", path, e))?;
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gtk::Orientation::{Horizontal, Vertical};
+use gtk::{
+    Button, ButtonExt, ContainerExt, FlowBox, FlowBoxExt, IconSize, Image, ImageExt, Label,
+    LabelExt, ReliefStyle, SelectionMode, StyleContextExt, WidgetExt,
+};
+
+use path_calculate::Calculate;
+
+use relm::{connect, Relm};
+
+use wvr_data::config::project_config::ViewConfig;
+
+use crate::config_panel::msg::ConfigPanelMsg;
+use crate::config_panel::view::ConfigPanel;
+
+const CAPTURE_PREVIEW_WIDTH: i32 = 128;
+const CAPTURE_PREVIEW_HEIGHT: i32 = 72;
+
+// Decoding every capture of a long recording would stall the interface
+const MAX_GALLERY_CAPTURE_COUNT: usize = 48;
+
+/// Folder frames are recorded and captured to, relative paths starting at the project.
+pub fn get_capture_folder(project_path: &Path, view_config: &ViewConfig) -> PathBuf {
+    project_path.join(&view_config.screenshot_path)
+}
+
+/// Recording folder as stored in the config, relative to the project when possible.
+pub fn get_stored_capture_folder(project_path: &Path, capture_folder: &Path) -> PathBuf {
+    if capture_folder.is_absolute() {
+        capture_folder
+            .related_to(project_path)
+            .map(|capture_folder| capture_folder.to_path_buf())
+            .unwrap_or_else(|_| capture_folder.to_path_buf())
+    } else {
+        capture_folder.to_path_buf()
+    }
+}
+
+/// Path of a new capture, named after the current time so that captures sort chronologically.
+pub fn get_new_capture_path(capture_folder: &Path) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+
+    capture_folder.join(format!("capture_{:}.png", timestamp))
+}
+
+/// PNG images of the capture folder, most recent first.
+pub fn get_capture_list(capture_folder: &Path) -> Vec<PathBuf> {
+    let mut capture_list: Vec<(SystemTime, PathBuf)> = match std::fs::read_dir(capture_folder) {
+        Ok(entry_list) => entry_list
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .map(|extension| extension.eq_ignore_ascii_case("png"))
+                        .unwrap_or(false)
+            })
+            .map(|path| {
+                let modified = path
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .unwrap_or(UNIX_EPOCH);
+                (modified, path)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    capture_list.sort_by(|(modified_a, path_a), (modified_b, path_b)| {
+        modified_b.cmp(modified_a).then_with(|| path_b.cmp(path_a))
+    });
+
+    capture_list.into_iter().map(|(_, path)| path).collect()
+}
+
+fn build_capture_item(relm: &Relm<ConfigPanel>, capture_path: &Path) -> gtk::Box {
+    let capture_item = gtk::Box::new(Vertical, 2);
+
+    let preview = match crate::thumbnail::load_scaled_png(
+        capture_path,
+        CAPTURE_PREVIEW_WIDTH,
+        CAPTURE_PREVIEW_HEIGHT,
+    ) {
+        Ok(surface) => {
+            let preview = Image::new();
+            preview.set_from_surface(Some(&*surface));
+            preview
+        }
+        Err(e) => {
+            eprintln!("Failed to load capture {:?}: {:?}", capture_path, e);
+            let placeholder = Image::from_icon_name(Some("image-missing"), IconSize::Dialog);
+            placeholder.get_style_context().add_class("dim-label");
+            placeholder
+        }
+    };
+    preview.set_size_request(CAPTURE_PREVIEW_WIDTH, CAPTURE_PREVIEW_HEIGHT);
+
+    let file_name = capture_path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    preview.set_tooltip_text(Some(&file_name));
+
+    let action_container = gtk::Box::new(Horizontal, 0);
+    action_container.set_halign(gtk::Align::Center);
+
+    let open_button = Button::from_icon_name(Some("document-open-symbolic"), IconSize::Button);
+    open_button.set_relief(ReliefStyle::None);
+    open_button.set_tooltip_text(Some("Open"));
+    {
+        let capture_path = capture_path.to_owned();
+        connect!(
+            relm,
+            open_button,
+            connect_clicked(_),
+            ConfigPanelMsg::OpenCapture(capture_path.clone())
+        );
+    }
+
+    let thumbnail_button =
+        Button::from_icon_name(Some("image-x-generic-symbolic"), IconSize::Button);
+    thumbnail_button.set_relief(ReliefStyle::None);
+    thumbnail_button.set_tooltip_text(Some("Set as project thumbnail"));
+    {
+        let capture_path = capture_path.to_owned();
+        connect!(
+            relm,
+            thumbnail_button,
+            connect_clicked(_),
+            ConfigPanelMsg::SetCaptureAsThumbnail(capture_path.clone())
+        );
+    }
+
+    let delete_button = Button::from_icon_name(Some("user-trash-symbolic"), IconSize::Button);
+    delete_button.set_relief(ReliefStyle::None);
+    delete_button.set_tooltip_text(Some("Move to the trash"));
+    {
+        let capture_path = capture_path.to_owned();
+        connect!(
+            relm,
+            delete_button,
+            connect_clicked(_),
+            ConfigPanelMsg::DeleteCapture(capture_path.clone())
+        );
+    }
+
+    action_container.add(&open_button);
+    action_container.add(&thumbnail_button);
+    action_container.add(&delete_button);
+
+    capture_item.add(&preview);
+    capture_item.add(&action_container);
+
+    capture_item
+}
+
+/// Builds the list of the images captured to the capture folder, with a capture button.
+pub fn build_gallery_view(relm: &Relm<ConfigPanel>, capture_folder: &Path) -> gtk::Box {
+    let gallery_container = gtk::Box::new(Vertical, 4);
+    gallery_container.set_property_margin(8);
+
+    let capture_list = get_capture_list(capture_folder);
+
+    let header_container = gtk::Box::new(Horizontal, 4);
+
+    let capture_button = Button::with_label("Capture now");
+    capture_button.set_tooltip_text(Some(
+        "Save the current output to the recording folder (F12)",
+    ));
+    connect!(
+        relm,
+        capture_button,
+        connect_clicked(_),
+        ConfigPanelMsg::CaptureFrame
+    );
+
+    let summary_label = Label::new(Some(&match capture_list.len() {
+        0 => "No captures yet".to_owned(),
+        1 => "1 capture".to_owned(),
+        capture_count => format!("{:} captures", capture_count),
+    }));
+    summary_label.set_hexpand(true);
+    summary_label.set_xalign(0.0);
+    summary_label.set_tooltip_text(capture_folder.to_str());
+
+    let refresh_button = Button::from_icon_name(Some("view-refresh-symbolic"), IconSize::Button);
+    refresh_button.set_relief(ReliefStyle::None);
+    refresh_button.set_tooltip_text(Some("Refresh"));
+    connect!(
+        relm,
+        refresh_button,
+        connect_clicked(_),
+        ConfigPanelMsg::RefreshCaptureGallery
+    );
+
+    header_container.add(&summary_label);
+    header_container.add(&refresh_button);
+    header_container.add(&capture_button);
+
+    let capture_flow_box = FlowBox::new();
+    capture_flow_box.set_selection_mode(SelectionMode::None);
+    capture_flow_box.set_column_spacing(4);
+    capture_flow_box.set_row_spacing(4);
+    capture_flow_box.set_homogeneous(true);
+
+    for capture_path in capture_list.iter().take(MAX_GALLERY_CAPTURE_COUNT) {
+        capture_flow_box.add(&build_capture_item(relm, capture_path));
+    }
+
+    gallery_container.add(&header_container);
+    gallery_container.add(&capture_flow_box);
+
+    if capture_list.len() > MAX_GALLERY_CAPTURE_COUNT {
+        let overflow_label = Label::new(Some(&format!(
+            "{:} older captures are not shown",
+            capture_list.len() - MAX_GALLERY_CAPTURE_COUNT
+        )));
+        overflow_label.get_style_context().add_class("dim-label");
+        gallery_container.add(&overflow_label);
+    }
+
+    gallery_container
+}
@@ -5,10 +5,19 @@ use anyhow::Result;
 use relm::Widget;
 
 mod config_panel;
+mod editor_state;
+mod export;
+mod gallery;
 mod input_config;
 mod main_window;
+mod migration;
+mod preferences;
+mod recovery;
 mod server_config;
 mod stage_config;
+mod stage_state;
+mod templates;
+mod thumbnail;
 mod utils;
 mod view_config;
 mod welcome_panel;
@@ -1,16 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use relm::connect;
 use relm_derive::Msg;
 
+use gio::{AppInfoExt, FileExt};
 use glib::object::ObjectExt;
 use gtk::prelude::*;
 
 use gtk::Orientation;
 use gtk::{
-    AccelFlags, AccelGroup, ButtonsType, ContainerExt, DialogBuilder, DialogExt, Entry, EntryExt,
-    GtkWindowExt, Inhibit, Menu, MenuBar, MenuItem, MessageDialogBuilder, MessageType,
-    ResponseType, Settings, WidgetExt, Window, WindowPosition, WindowType,
+    AccelFlags, AccelGroup, Adjustment, ButtonsType, ContainerExt, DialogBuilder, DialogExt, Entry,
+    EntryExt, FileChooserAction, FileChooserDialog, FileChooserExt, GtkWindowExt, Inhibit, Label,
+    LabelExt, ListBox, ListBoxExt, ListBoxRowExt, Menu, MenuBar, MenuItem, MessageDialogBuilder,
+    MessageType, PolicyType, ResponseType, ScrolledWindow, ScrolledWindowExt, SelectionMode,
+    SeparatorMenuItem, Settings, WidgetExt, Window, WindowPosition, WindowType,
 };
 
 use relm::{Component, ContainerWidget, Relm, Update, Widget};
@@ -19,21 +22,44 @@ use wvr_data::config::project_config::ProjectConfig;
 
 use crate::config_panel::msg::ConfigPanelMsg;
 use crate::config_panel::view::ConfigPanel;
-use crate::welcome_panel;
+use crate::preferences::{Preferences, WindowGeometry};
+use crate::utils::ConfigLoadError;
+use crate::welcome_panel::{WelcomePanel, WelcomePanelMsg};
 
 #[derive(Msg, Debug)]
 pub enum Msg {
     UpdateConfig(ProjectConfig),
+    ProjectSaved(ProjectConfig),
+    ProjectSaveFailed(String),
     NewProject,
-    OpenProject(PathBuf, ProjectConfig),
+    OpenProject(PathBuf),
     SaveProject,
+    SaveProjectAs,
+    SaveProjectAsTemplate,
+    ExportProject,
+    DuplicateProject(PathBuf),
+    RenameProject(PathBuf),
+    TrashProject(PathBuf),
+    RevealProject(PathBuf),
+    ClearRecentProjects,
+    SetProjectPanedPosition(i32),
+    SetRenderStageListPanedPosition(i32),
+    Undo,
+    Redo,
+    DuplicateStage,
+    CopyStage,
+    PasteStage,
+    CaptureFrame,
     ToggleDarkMode,
     Quit,
 }
 pub struct Model {
     project_path: Option<PathBuf>,
     project_config: Option<ProjectConfig>,
-    dark_mode: bool,
+    saved_project_config: Option<ProjectConfig>,
+    preferences: Preferences,
+    /// Action interrupted to save the project, run again once the project is saved.
+    pending_action: Option<Msg>,
 }
 
 pub struct MainWindow {
@@ -42,85 +68,637 @@ pub struct MainWindow {
 
     config_panel_container: gtk::Box,
     config_panel: Option<Component<ConfigPanel>>,
+    welcome_panel: Option<Component<WelcomePanel>>,
+
+    recent_menu: Menu,
 
     relm: Relm<Self>,
 }
 
 impl MainWindow {
+    fn is_project_modified(&self) -> bool {
+        match (&self.model.project_config, &self.model.saved_project_config) {
+            (Some(project_config), Some(saved_project_config)) => {
+                serde_json::to_value(project_config).ok()
+                    != serde_json::to_value(saved_project_config).ok()
+            }
+            _ => false,
+        }
+    }
+
+    fn update_title(&self) {
+        if let Some(project_path) = &self.model.project_path {
+            self.window.set_title(&format!(
+                "wvr://{:}{:}",
+                project_path
+                    .ancestors()
+                    .next()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                if self.is_project_modified() { "*" } else { "" }
+            ));
+        } else {
+            self.window.set_title("wvr");
+        }
+    }
+
+    fn show_error_dialog(&self, title: &str, text: &str) {
+        let error_message = MessageDialogBuilder::new()
+            .title(title)
+            .text(text)
+            .message_type(MessageType::Error)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::Ok)
+            .attached_to(&self.window)
+            .modal(true)
+            .build();
+        error_message.run();
+        error_message.close();
+    }
+
+    /// Lists the changes made to load a project written for an older launcher, offering to
+    /// upgrade its config file right away.
+    fn upgrade_project_config(&self, project_path: &Path, project_config: &ProjectConfig) {
+        let config_path = project_path.join("config.json");
+        let report = match crate::migration::get_migration_report(&config_path) {
+            Ok(Some(report)) => report,
+            Ok(None) => return,
+            // Unreadable configs are reported when loading the project
+            Err(_) => return,
+        };
+
+        let mut details = "The config file is upgraded when the project is saved, the original \
+            is kept as a backup.\n\nApplied changes:"
+            .to_owned();
+        for step in &report.step_list {
+            details.push_str(&format!("\n  {:}", step));
+        }
+
+        let migration_dialog = MessageDialogBuilder::new()
+            .title("Project upgraded")
+            .text(&format!(
+                "The project config was upgraded from version {:} to version {:}",
+                report.from_version, report.to_version
+            ))
+            .secondary_text(&details)
+            .message_type(MessageType::Info)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::None)
+            .attached_to(&self.window)
+            .modal(true)
+            .build();
+        migration_dialog.add_button("Upgrade file now", ResponseType::Accept);
+        migration_dialog.add_button("Later", ResponseType::Cancel);
+
+        let response = migration_dialog.run();
+        migration_dialog.close();
+
+        if response == ResponseType::Accept {
+            if let Err(e) = crate::utils::save_config(&config_path, project_config) {
+                self.show_error_dialog("Error: could not upgrade project", &format!("{:?}", e));
+            }
+        }
+    }
+
+    /// Reads the config of a project, reporting errors with an option to fix the file by hand.
+    fn read_project_config(&self, project_path: &Path) -> Option<ProjectConfig> {
+        let config_path = project_path.join("config.json");
+
+        let error = match crate::utils::load_project_config(&config_path) {
+            Ok(project_config) => return Some(project_config),
+            Err(error) => error,
+        };
+
+        let (text, details) = match error.downcast_ref::<ConfigLoadError>() {
+            Some(load_error) => {
+                let mut details = if load_error.line > 0 {
+                    format!(
+                        "{:}\n\nLine {:}, column {:}: {:}",
+                        config_path.to_string_lossy(),
+                        load_error.line,
+                        load_error.column,
+                        load_error.message
+                    )
+                } else {
+                    format!(
+                        "{:}\n\n{:}",
+                        config_path.to_string_lossy(),
+                        load_error.message
+                    )
+                };
+                if let Some(line_content) = &load_error.line_content {
+                    details.push_str(&format!("\n\n{:}", line_content.trim()));
+                }
+
+                (
+                    format!("{:} in the project config", load_error.kind),
+                    details,
+                )
+            }
+            None => (
+                "The project config could not be read".to_owned(),
+                format!("{:}", error),
+            ),
+        };
+
+        let error_dialog = MessageDialogBuilder::new()
+            .title("Error: could not load project")
+            .text(&text)
+            .secondary_text(&details)
+            .message_type(MessageType::Error)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::None)
+            .attached_to(&self.window)
+            .modal(true)
+            .build();
+        if config_path.is_file() {
+            error_dialog.add_button("Open in text editor", ResponseType::Accept);
+        }
+        error_dialog.add_button("Close", ResponseType::Close);
+
+        let response = error_dialog.run();
+        error_dialog.close();
+
+        if response == ResponseType::Accept {
+            if let Err(e) = open_in_text_editor(&config_path) {
+                self.show_error_dialog("Error: could not open text editor", &format!("{:?}", e));
+            }
+        }
+
+        None
+    }
+
+    /// Asks the user what to do with unsaved changes, returns false if `action` cannot run now.
+    /// When the user chooses to save, `action` is emitted again once the config panel saved.
+    fn confirm_discard_changes(&mut self, action: Msg) -> bool {
+        if !self.is_project_modified() {
+            return true;
+        }
+
+        let unsaved_changes_dialog = MessageDialogBuilder::new()
+            .title("Unsaved changes")
+            .text("The current project has unsaved changes")
+            .secondary_text("Do you want to save them before continuing?")
+            .message_type(MessageType::Question)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::None)
+            .attached_to(&self.window)
+            .modal(true)
+            .build();
+        unsaved_changes_dialog.add_button("Save", ResponseType::Accept);
+        unsaved_changes_dialog.add_button("Discard", ResponseType::Reject);
+        unsaved_changes_dialog.add_button("Cancel", ResponseType::Cancel);
+
+        let response = unsaved_changes_dialog.run();
+        unsaved_changes_dialog.close();
+
+        match response {
+            ResponseType::Accept => match &self.config_panel {
+                Some(panel) => {
+                    self.model.pending_action = Some(action);
+                    panel.emit(ConfigPanelMsg::Save);
+                    false
+                }
+                None => true,
+            },
+            ResponseType::Reject => {
+                if let Some(project_path) = &self.model.project_path {
+                    crate::recovery::clear_recovery_files(project_path);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Offers to restore the autosaved state of a project if it is newer than its config file.
+    fn open_recovery_dialog(&self, project_path: &Path) -> Option<ProjectConfig> {
+        let recovery_state = crate::recovery::load_recovery_state(project_path)?;
+
+        let elapsed_minutes = recovery_state
+            .modified
+            .elapsed()
+            .map(|elapsed| elapsed.as_secs() / 60)
+            .unwrap_or(0);
+
+        let mut details = format!(
+            "Unsaved changes from {:} minute(s) ago were found for this project.",
+            elapsed_minutes
+        );
+        if !recovery_state.edit_list.is_empty() {
+            details.push_str("\n\nLast recorded edits:");
+            for edit in recovery_state.edit_list.iter().rev().take(8) {
+                let edit: String = edit.chars().take(80).collect();
+                details.push_str(&format!("\n  {:}", edit));
+            }
+        }
+
+        let recovery_dialog = MessageDialogBuilder::new()
+            .title("Recover unsaved changes")
+            .text("Restore the unsaved changes of this project?")
+            .secondary_text(&details)
+            .message_type(MessageType::Question)
+            .window_position(WindowPosition::Center)
+            .buttons(ButtonsType::None)
+            .attached_to(&self.window)
+            .modal(true)
+            .build();
+        recovery_dialog.add_button("Restore", ResponseType::Accept);
+        recovery_dialog.add_button("Discard", ResponseType::Reject);
+
+        let response = recovery_dialog.run();
+        recovery_dialog.close();
+
+        if response == ResponseType::Accept {
+            Some(recovery_state.config)
+        } else {
+            crate::recovery::clear_recovery_files(project_path);
+            None
+        }
+    }
+
+    fn load_project(&mut self, project_path: PathBuf, project_config: ProjectConfig) {
+        self.model.saved_project_config = Some(project_config.clone());
+
+        let project_config = self
+            .open_recovery_dialog(&project_path)
+            .unwrap_or(project_config);
+
+        self.model.project_path = Some(project_path.clone());
+        self.model.project_config = Some(project_config.clone());
+
+        self.model.preferences.add_recent_project(&project_path);
+        self.save_preferences();
+        self.update_recent_menu();
+
+        for children in &self.config_panel_container.get_children() {
+            self.config_panel_container.remove(children);
+        }
+        self.welcome_panel = None;
+
+        self.update_title();
+
+        self.config_panel = Some(self.config_panel_container.add_widget::<ConfigPanel>((
+            self.relm.clone(),
+            project_path,
+            project_config,
+            self.model.preferences.paned_positions,
+        )));
+
+        self.root().show_all();
+    }
+
+    fn refresh_welcome_panel(&self) {
+        if let Some(welcome_panel) = &self.welcome_panel {
+            welcome_panel.emit(WelcomePanelMsg::SetRecentProjectList(
+                self.model.preferences.get_recent_project_list(),
+            ));
+            welcome_panel.emit(WelcomePanelMsg::Refresh);
+        }
+    }
+
+    fn save_preferences(&self) {
+        if let Err(e) = self.model.preferences.save() {
+            eprintln!("Failed to save preferences: {:?}", e);
+        }
+    }
+
+    fn update_recent_menu(&self) {
+        for child in &self.recent_menu.get_children() {
+            self.recent_menu.remove(child);
+        }
+
+        let recent_project_list = self.model.preferences.get_recent_project_list();
+        for project_path in &recent_project_list {
+            let recent_project_item = MenuItem::with_label(&project_path.to_string_lossy());
+
+            let project_path = project_path.clone();
+            connect!(
+                self.relm,
+                recent_project_item,
+                connect_activate(_),
+                Msg::OpenProject(project_path.clone())
+            );
+
+            self.recent_menu.append(&recent_project_item);
+        }
+
+        if recent_project_list.is_empty() {
+            let empty_item = MenuItem::with_label("No recent project");
+            empty_item.set_sensitive(false);
+            self.recent_menu.append(&empty_item);
+        }
+
+        let clear_item = MenuItem::with_label("Clear recent projects");
+        clear_item.set_sensitive(!recent_project_list.is_empty());
+        connect!(
+            self.relm,
+            clear_item,
+            connect_activate(_),
+            Msg::ClearRecentProjects
+        );
+        self.recent_menu.append(&SeparatorMenuItem::new());
+        self.recent_menu.append(&clear_item);
+
+        self.recent_menu.show_all();
+    }
+
+    fn store_window_geometry(&mut self) {
+        let (x, y) = self.window.get_position();
+        let (width, height) = self.window.get_size();
+        let maximized = self.window.is_maximized();
+
+        // A maximized window keeps the geometry it had before being maximized
+        let geometry = match (maximized, self.model.preferences.window_geometry) {
+            (true, Some(geometry)) => WindowGeometry {
+                maximized,
+                ..geometry
+            },
+            _ => WindowGeometry {
+                x,
+                y,
+                width,
+                height,
+                maximized,
+            },
+        };
+
+        self.model.preferences.window_geometry = Some(geometry);
+    }
+
+    /// Asks for the name of a project to create in `parent_path`, which must not exist yet.
+    ///
+    /// `extra_content` is displayed below the name entry, for additional project options.
+    fn open_project_name_dialog(
+        &self,
+        title: &str,
+        parent_path: &Path,
+        default_name: &str,
+        extra_content: Option<&gtk::Box>,
+    ) -> Option<PathBuf> {
+        let project_name_dialog = DialogBuilder::new()
+            .title(title)
+            .attached_to(&self.window)
+            .window_position(WindowPosition::Center)
+            .modal(true)
+            .build();
+
+        let project_name_entry = Entry::new();
+        project_name_entry.set_text(default_name);
+        {
+            let project_name_dialog = project_name_dialog.clone();
+            connect!(self.relm, project_name_entry, connect_changed(val), {
+                project_name_dialog.set_response_sensitive(
+                    ResponseType::Ok,
+                    !val.get_text().to_string().trim().is_empty(),
+                );
+            });
+        }
+
+        project_name_dialog
+            .get_content_area()
+            .add(&project_name_entry);
+        if let Some(extra_content) = extra_content {
+            project_name_dialog.get_content_area().add(extra_content);
+        }
+        project_name_dialog
+            .add_button("Ok", ResponseType::Ok)
+            .set_hexpand(true);
+        project_name_dialog.add_button("Cancel", ResponseType::Cancel);
+        project_name_dialog.set_response_sensitive(ResponseType::Ok, !default_name.is_empty());
+
+        project_name_dialog.get_content_area().show_all();
+
+        let mut project_path = None;
+        while project_path.is_none() {
+            match project_name_dialog.run() {
+                ResponseType::Ok => {
+                    let project_name_candidate =
+                        project_name_entry.get_text().to_string().trim().to_string();
+
+                    if !crate::utils::is_valid_folder_name(&project_name_candidate) {
+                        self.show_error_dialog(
+                            "Error: invalid project name",
+                            "A project name cannot contain path separators or be \".\" or \"..\"",
+                        );
+                    } else {
+                        let project_path_candidate = parent_path.join(&project_name_candidate);
+                        if project_path_candidate.exists() {
+                            self.show_error_dialog(
+                                "Error: project exists",
+                                "A project using the same name already exists",
+                            );
+                        } else {
+                            project_path = Some(project_path_candidate);
+                        }
+                    }
+                }
+                _ => {
+                    break;
+                }
+            }
+        }
+        project_name_dialog.close();
+
+        project_path
+    }
+
     pub fn open_new_project_dialog(&self) -> Option<(PathBuf, ProjectConfig)> {
-        let new_project_dialog = DialogBuilder::new()
-            .title("New wvr project")
+        let template_list = crate::templates::list_templates();
+
+        let template_list_box = ListBox::new();
+        template_list_box.set_selection_mode(SelectionMode::Browse);
+        for template in &template_list {
+            let template_row = gtk::Box::new(Orientation::Vertical, 2);
+            template_row.set_property_margin(4);
+
+            let template_name_label = Label::new(None);
+            template_name_label.set_markup(&format!(
+                "<b>{:}</b>",
+                glib::markup_escape_text(&template.name)
+            ));
+            template_name_label.set_xalign(0.0);
+
+            let template_description_label = Label::new(Some(&template.description));
+            template_description_label.set_xalign(0.0);
+            template_description_label.set_line_wrap(true);
+
+            template_row.add(&template_name_label);
+            template_row.add(&template_description_label);
+
+            template_list_box.add(&template_row);
+        }
+        let default_template_index = self
+            .model
+            .preferences
+            .new_project
+            .template
+            .as_ref()
+            .and_then(|template_name| {
+                template_list
+                    .iter()
+                    .position(|template| &template.name == template_name)
+            })
+            .unwrap_or(0);
+        template_list_box.select_row(
+            template_list_box
+                .get_row_at_index(default_template_index as i32)
+                .as_ref(),
+        );
+
+        let template_list_wrapper = ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
+        template_list_wrapper.set_policy(PolicyType::Never, PolicyType::Automatic);
+        template_list_wrapper.set_size_request(360, 240);
+        template_list_wrapper.add(&template_list_box);
+
+        let template_panel = gtk::Box::new(Orientation::Vertical, 4);
+        template_panel.set_margin_top(8);
+        let template_label = Label::new(Some("Template"));
+        template_label.set_xalign(0.0);
+        template_panel.add(&template_label);
+        template_panel.add(&template_list_wrapper);
+
+        let new_project_path = self.open_project_name_dialog(
+            "New wvr project",
+            &wvr_data::get_data_path().join("projects"),
+            "",
+            Some(&template_panel),
+        )?;
+
+        let template_index = template_list_box
+            .get_selected_row()
+            .map(|row| row.get_index())
+            .unwrap_or(0) as usize;
+
+        match crate::templates::create_project_from_template(
+            &template_list[template_index],
+            &new_project_path,
+            &self.model.preferences.new_project,
+        ) {
+            Ok(project_config) => Some((new_project_path, project_config)),
+            Err(e) => {
+                self.show_error_dialog("Error: could not create project", &format!("{:?}", e));
+                None
+            }
+        }
+    }
+
+    /// Asks for the name and description of a new template, which must not exist yet.
+    fn open_save_as_template_dialog(&self) -> Option<(String, String)> {
+        let template_dialog = DialogBuilder::new()
+            .title("Save project as template")
             .attached_to(&self.window)
             .window_position(WindowPosition::Center)
             .modal(true)
             .build();
-        new_project_dialog.set_response_sensitive(ResponseType::Ok, false);
 
-        let new_project_name_entry = Entry::new();
+        let template_name_entry = Entry::new();
+        template_name_entry.set_placeholder_text(Some("Name"));
         {
-            let new_project_dialog = new_project_dialog.clone();
-            connect!(self.relm, new_project_name_entry, connect_changed(val), {
-                new_project_dialog.set_response_sensitive(
+            let template_dialog = template_dialog.clone();
+            connect!(self.relm, template_name_entry, connect_changed(val), {
+                template_dialog.set_response_sensitive(
                     ResponseType::Ok,
                     !val.get_text().to_string().trim().is_empty(),
                 );
             });
         }
 
-        new_project_dialog
+        let template_description_entry = Entry::new();
+        template_description_entry.set_placeholder_text(Some("Description"));
+
+        template_dialog.get_content_area().add(&template_name_entry);
+        template_dialog
             .get_content_area()
-            .add(&new_project_name_entry);
-        new_project_dialog
+            .add(&template_description_entry);
+        template_dialog
             .add_button("Ok", ResponseType::Ok)
             .set_hexpand(true);
-        new_project_dialog.add_button("Cancel", ResponseType::Cancel);
+        template_dialog.add_button("Cancel", ResponseType::Cancel);
+        template_dialog.set_response_sensitive(ResponseType::Ok, false);
 
-        new_project_dialog.get_content_area().show_all();
+        template_dialog.get_content_area().show_all();
 
-        let mut created_project = None;
-        while created_project.is_none() {
-            match new_project_dialog.run() {
+        let mut template_info = None;
+        while template_info.is_none() {
+            match template_dialog.run() {
                 ResponseType::Ok => {
-                    let new_project_name_candidate = new_project_name_entry
+                    let template_name = template_name_entry
                         .get_text()
                         .to_string()
                         .trim()
                         .to_string();
 
-                    if !new_project_name_candidate.is_empty() {
-                        let new_project_path = wvr_data::get_data_path()
-                            .join("projects")
-                            .join(&new_project_name_candidate);
-                        if new_project_path.exists() {
-                            let error_message = MessageDialogBuilder::new()
-                                .title("Error: project exists")
-                                .text("A project using the same name already exists")
-                                .message_type(MessageType::Error)
-                                .window_position(WindowPosition::Center)
-                                .buttons(ButtonsType::Ok)
-                                .attached_to(&self.window)
-                                .modal(true)
-                                .build();
-                            error_message.run();
-                            error_message.close();
-                        } else if let Some(project_config) =
-                            crate::utils::create_project(&new_project_path)
+                    if !template_name.is_empty() {
+                        if crate::templates::get_templates_path()
+                            .join(&template_name)
+                            .exists()
                         {
-                            created_project = Some((new_project_path, project_config));
+                            self.show_error_dialog(
+                                "Error: template exists",
+                                "A template using the same name already exists",
+                            );
+                        } else {
+                            template_info = Some((
+                                template_name,
+                                template_description_entry.get_text().to_string(),
+                            ));
                         }
                     }
                 }
-                ResponseType::Cancel => {
+                _ => {
                     break;
                 }
+            }
+        }
+        template_dialog.close();
+
+        template_info
+    }
+
+    /// Asks for the directory a copy of the project should be written to.
+    fn open_save_as_dialog(&self, project_path: &Path) -> Option<PathBuf> {
+        let save_as_dialog = FileChooserDialog::with_buttons(
+            Some("Save project as"),
+            Some(&self.window),
+            FileChooserAction::Save,
+            &[
+                ("Cancel", ResponseType::Cancel),
+                ("Save", ResponseType::Accept),
+            ],
+        );
+        save_as_dialog.set_create_folders(true);
+        save_as_dialog.set_current_folder(wvr_data::get_data_path().join("projects"));
+        if let Some(project_name) = project_path.file_name().and_then(|name| name.to_str()) {
+            save_as_dialog.set_current_name(&format!("{:} copy", project_name));
+        }
+
+        let mut target_path = None;
+        while target_path.is_none() {
+            match save_as_dialog.run() {
+                ResponseType::Accept => {
+                    if let Some(target_path_candidate) = save_as_dialog.get_filename() {
+                        if target_path_candidate.exists() {
+                            self.show_error_dialog(
+                                "Error: destination exists",
+                                "A file or directory using the same name already exists",
+                            );
+                        } else {
+                            target_path = Some(target_path_candidate);
+                        }
+                    }
+                }
                 _ => {
                     break;
                 }
             }
         }
-        new_project_dialog.close();
+        save_as_dialog.close();
 
-        created_project
+        target_path
     }
 }
 
@@ -133,71 +711,287 @@ impl Update for MainWindow {
         Model {
             project_path: None,
             project_config: None,
-            dark_mode: true,
+            saved_project_config: None,
+            preferences: Preferences::load(),
+            pending_action: None,
         }
     }
 
     fn update(&mut self, event: Msg) {
         match event {
             Msg::ToggleDarkMode => {
-                self.model.dark_mode = !self.model.dark_mode;
+                self.model.preferences.dark_mode = !self.model.preferences.dark_mode;
+                self.save_preferences();
 
                 let settings = Settings::get_default().unwrap();
                 settings
-                    .set_property("gtk-application-prefer-dark-theme", &self.model.dark_mode)
+                    .set_property(
+                        "gtk-application-prefer-dark-theme",
+                        &self.model.preferences.dark_mode,
+                    )
                     .unwrap();
             }
             Msg::UpdateConfig(project_config) => {
                 self.model.project_config = Some(project_config);
+                self.update_title();
             }
-            Msg::OpenProject(project_path, project_config) => {
-                self.model.project_path = Some(project_path.clone());
+            Msg::ProjectSaved(project_config) => {
+                // The saved config is the current one of the config panel
                 self.model.project_config = Some(project_config.clone());
+                self.model.saved_project_config = Some(project_config);
+                self.update_title();
+
+                if let Some(pending_action) = self.model.pending_action.take() {
+                    self.relm.stream().emit(pending_action);
+                }
+            }
+            Msg::ProjectSaveFailed(error) => {
+                self.model.pending_action = None;
+                self.show_error_dialog("Error: could not save project", &error);
+            }
+            Msg::OpenProject(project_path) => {
+                // Older configs are migrated in memory, the file is only written once confirmed
+                if let Some(project_config) = self.read_project_config(&project_path) {
+                    if self.confirm_discard_changes(Msg::OpenProject(project_path.clone())) {
+                        self.upgrade_project_config(&project_path, &project_config);
+                        self.load_project(project_path, project_config);
+                    }
+                }
+            }
+            Msg::SaveProject => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::Save);
+                }
+            }
+            Msg::SaveProjectAs => {
+                let (project_path, project_config) =
+                    match (&self.model.project_path, &self.model.project_config) {
+                        (Some(project_path), Some(project_config)) => {
+                            (project_path.clone(), project_config.clone())
+                        }
+                        _ => return,
+                    };
+
+                if let Some(target_path) = self.open_save_as_dialog(&project_path) {
+                    match crate::utils::copy_project(&project_path, &target_path, &project_config) {
+                        Ok(target_config) => {
+                            // Unsaved changes now live in the copy, the original is left as saved
+                            crate::recovery::clear_recovery_files(&project_path);
+                            self.load_project(target_path, target_config);
+                        }
+                        Err(e) => self.show_error_dialog(
+                            "Error: could not save project",
+                            &format!("{:?}", e),
+                        ),
+                    }
+                }
+            }
+            Msg::SaveProjectAsTemplate => {
+                let (project_path, project_config) =
+                    match (&self.model.project_path, &self.model.project_config) {
+                        (Some(project_path), Some(project_config)) => {
+                            (project_path.clone(), project_config.clone())
+                        }
+                        _ => return,
+                    };
 
-                for children in &self.config_panel_container.get_children() {
-                    self.config_panel_container.remove(children);
+                if let Some((template_name, description)) = self.open_save_as_template_dialog() {
+                    if let Err(e) = crate::templates::save_as_template(
+                        &project_path,
+                        &project_config,
+                        &template_name,
+                        &description,
+                    ) {
+                        self.show_error_dialog(
+                            "Error: could not save template",
+                            &format!("{:?}", e),
+                        );
+                    }
                 }
+            }
+            Msg::DuplicateProject(project_path) => {
+                let project_config = match self.read_project_config(&project_path) {
+                    Some(project_config) => project_config,
+                    None => return,
+                };
 
-                self.window.set_title(&format!(
-                    "wvr://{:}",
+                let default_name = format!(
+                    "{:} copy",
                     project_path
-                        .ancestors()
-                        .next()
-                        .unwrap()
                         .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                ));
+                        .and_then(|name| name.to_str())
+                        .unwrap_or("project")
+                );
+                if let Some(target_path) = self.open_project_name_dialog(
+                    "Duplicate wvr project",
+                    &wvr_data::get_data_path().join("projects"),
+                    &default_name,
+                    None,
+                ) {
+                    match crate::utils::copy_project(&project_path, &target_path, &project_config) {
+                        Ok(_) => self.refresh_welcome_panel(),
+                        Err(e) => self.show_error_dialog(
+                            "Error: could not duplicate project",
+                            &format!("{:?}", e),
+                        ),
+                    }
+                }
+            }
+            Msg::RenameProject(project_path) => {
+                let (parent_path, project_name) =
+                    match (project_path.parent(), project_path.file_name()) {
+                        (Some(parent_path), Some(project_name)) => {
+                            (parent_path, project_name.to_string_lossy())
+                        }
+                        _ => return,
+                    };
 
-                self.config_panel = Some(self.config_panel_container.add_widget::<ConfigPanel>((
-                    self.relm.clone(),
-                    project_path,
-                    project_config,
-                )));
+                if let Some(target_path) = self.open_project_name_dialog(
+                    "Rename wvr project",
+                    parent_path,
+                    &project_name,
+                    None,
+                ) {
+                    if let Err(e) = std::fs::rename(&project_path, &target_path) {
+                        self.show_error_dialog(
+                            "Error: could not rename project",
+                            &format!("{:?}", e),
+                        );
+                    } else {
+                        self.model
+                            .preferences
+                            .rename_recent_project(&project_path, &target_path);
+                        self.save_preferences();
+                        self.update_recent_menu();
+                    }
+                    self.refresh_welcome_panel();
+                }
+            }
+            Msg::TrashProject(project_path) => {
+                let confirmation_dialog = MessageDialogBuilder::new()
+                    .title("Delete project")
+                    .text(&format!(
+                        "Move {:} to the trash?",
+                        project_path.to_string_lossy()
+                    ))
+                    .message_type(MessageType::Warning)
+                    .window_position(WindowPosition::Center)
+                    .buttons(ButtonsType::OkCancel)
+                    .attached_to(&self.window)
+                    .modal(true)
+                    .build();
+                let response = confirmation_dialog.run();
+                confirmation_dialog.close();
 
-                self.root().show_all();
+                if response != ResponseType::Ok {
+                    return;
+                }
+
+                if let Err(e) =
+                    gio::File::new_for_path(&project_path).trash(None::<&gio::Cancellable>)
+                {
+                    self.show_error_dialog("Error: could not delete project", &format!("{:?}", e));
+                } else {
+                    self.model.preferences.remove_recent_project(&project_path);
+                    self.save_preferences();
+                    self.update_recent_menu();
+                }
+                self.refresh_welcome_panel();
             }
-            Msg::SaveProject => {
+            Msg::RevealProject(project_path) => {
+                let project_uri = gio::File::new_for_path(&project_path).get_uri();
+                if let Err(e) = gio::AppInfo::launch_default_for_uri(
+                    &project_uri,
+                    None::<&gio::AppLaunchContext>,
+                ) {
+                    self.show_error_dialog("Error: could not open folder", &format!("{:?}", e));
+                }
+            }
+            Msg::ClearRecentProjects => {
+                self.model.preferences.recent_project_list.clear();
+                self.save_preferences();
+                self.update_recent_menu();
+                self.refresh_welcome_panel();
+            }
+            Msg::SetProjectPanedPosition(position) => {
+                self.model.preferences.paned_positions.project = Some(position);
+            }
+            Msg::SetRenderStageListPanedPosition(position) => {
+                self.model.preferences.paned_positions.render_stage_list = Some(position);
+            }
+            Msg::Undo => {
                 if let Some(panel) = &self.config_panel {
-                    panel.emit(ConfigPanelMsg::Save);
+                    panel.emit(ConfigPanelMsg::Undo);
+                }
+            }
+            Msg::Redo => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::Redo);
+                }
+            }
+            Msg::ExportProject => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::ShowExportDialog);
+                }
+            }
+            Msg::CaptureFrame => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::CaptureFrame);
+                }
+            }
+            Msg::DuplicateStage => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::DuplicateRenderStage(None));
+                }
+            }
+            Msg::CopyStage => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::CopyRenderStage(None));
+                }
+            }
+            Msg::PasteStage => {
+                if let Some(panel) = &self.config_panel {
+                    panel.emit(ConfigPanelMsg::PasteRenderStage);
                 }
             }
 
             Msg::NewProject => {
+                // The current project is only left once the new one exists, so cancelling the
+                // dialog keeps it and its recovery files untouched
                 if let Some((new_project_path, new_project_config)) = self.open_new_project_dialog()
                 {
-                    self.relm
-                        .stream()
-                        .emit(Msg::OpenProject(new_project_path, new_project_config));
+                    // Saving first opens the new project from its folder once the save is done
+                    if self.confirm_discard_changes(Msg::OpenProject(new_project_path.clone())) {
+                        self.load_project(new_project_path, new_project_config);
+                    } else {
+                        self.refresh_welcome_panel();
+                    }
+                }
+            }
+            Msg::Quit => {
+                if self.confirm_discard_changes(Msg::Quit) {
+                    self.store_window_geometry();
+                    self.save_preferences();
+
+                    gtk::main_quit();
                 }
             }
-            Msg::Quit => gtk::main_quit(),
         }
     }
 }
 
-fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar {
+fn open_in_text_editor(file_path: &Path) -> Result<(), glib::Error> {
+    let file = gio::File::new_for_path(file_path);
+
+    match gio::AppInfo::get_default_for_type("text/plain", false) {
+        Some(text_editor) => text_editor.launch(&[file], None::<&gio::AppLaunchContext>),
+        None => {
+            gio::AppInfo::launch_default_for_uri(&file.get_uri(), None::<&gio::AppLaunchContext>)
+        }
+    }
+}
+
+fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> (MenuBar, Menu) {
     let menu_bar = MenuBar::new();
 
     let file_button = MenuItem::with_label("File");
@@ -211,10 +1005,24 @@ fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar
     let (key, modifier) = gtk::accelerator_parse("<Primary>O");
     open_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
 
+    let recent_menu_item = MenuItem::with_label("Recent");
+    let recent_menu = Menu::new();
+    recent_menu_item.set_submenu(Some(&recent_menu));
+
     let save_menu_item = MenuItem::with_label("Save");
     let (key, modifier) = gtk::accelerator_parse("<Primary>S");
     save_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
 
+    let save_as_menu_item = MenuItem::with_label("Save As");
+    let (key, modifier) = gtk::accelerator_parse("<Primary><Shift>S");
+    save_as_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
+
+    let save_as_template_menu_item = MenuItem::with_label("Save as template");
+
+    let export_menu_item = MenuItem::with_label("Export");
+    let (key, modifier) = gtk::accelerator_parse("<Primary>E");
+    export_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
+
     let quit = MenuItem::with_label("Quit");
     let (key, modifier) = gtk::accelerator_parse("<Primary>Q");
     quit.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
@@ -222,9 +1030,63 @@ fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar
     file_button.set_submenu(Some(&file_menu));
     file_menu.append(&new_menu_item);
     file_menu.append(&open_menu_item);
+    file_menu.append(&recent_menu_item);
     file_menu.append(&save_menu_item);
+    file_menu.append(&save_as_menu_item);
+    file_menu.append(&save_as_template_menu_item);
+    file_menu.append(&export_menu_item);
     file_menu.append(&quit);
 
+    let edit_button = MenuItem::with_label("Edit");
+    let edit_menu = Menu::new();
+
+    let undo_menu_item = MenuItem::with_label("Undo");
+    let (key, modifier) = gtk::accelerator_parse("<Primary>Z");
+    undo_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
+
+    let redo_menu_item = MenuItem::with_label("Redo");
+    let (key, modifier) = gtk::accelerator_parse("<Primary><Shift>Z");
+    redo_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
+
+    // Plain copy and paste shortcuts are left to text entries
+    let duplicate_stage_menu_item = MenuItem::with_label("Duplicate stage");
+    let (key, modifier) = gtk::accelerator_parse("<Primary><Shift>D");
+    duplicate_stage_menu_item.add_accelerator(
+        "activate",
+        accel_group,
+        key,
+        modifier,
+        AccelFlags::VISIBLE,
+    );
+
+    let copy_stage_menu_item = MenuItem::with_label("Copy stage");
+    let (key, modifier) = gtk::accelerator_parse("<Primary><Shift>C");
+    copy_stage_menu_item.add_accelerator(
+        "activate",
+        accel_group,
+        key,
+        modifier,
+        AccelFlags::VISIBLE,
+    );
+
+    let paste_stage_menu_item = MenuItem::with_label("Paste stage");
+    let (key, modifier) = gtk::accelerator_parse("<Primary><Shift>V");
+    paste_stage_menu_item.add_accelerator(
+        "activate",
+        accel_group,
+        key,
+        modifier,
+        AccelFlags::VISIBLE,
+    );
+
+    edit_button.set_submenu(Some(&edit_menu));
+    edit_menu.append(&undo_menu_item);
+    edit_menu.append(&redo_menu_item);
+    edit_menu.append(&SeparatorMenuItem::new());
+    edit_menu.append(&duplicate_stage_menu_item);
+    edit_menu.append(&copy_stage_menu_item);
+    edit_menu.append(&paste_stage_menu_item);
+
     let view_button = MenuItem::with_label("View");
     let view_menu = Menu::new();
 
@@ -232,10 +1094,16 @@ fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar
     let (key, modifier) = gtk::accelerator_parse("<Primary>D");
     dark_mode_button.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
 
+    let capture_menu_item = MenuItem::with_label("Capture frame");
+    let (key, modifier) = gtk::accelerator_parse("F12");
+    capture_menu_item.add_accelerator("activate", accel_group, key, modifier, AccelFlags::VISIBLE);
+
     view_button.set_submenu(Some(&view_menu));
     view_menu.append(&dark_mode_button);
+    view_menu.append(&capture_menu_item);
 
     menu_bar.append(&file_button);
+    menu_bar.append(&edit_button);
     menu_bar.append(&view_button);
 
     connect!(relm, new_menu_item, connect_activate(_), Msg::NewProject);
@@ -244,14 +1112,57 @@ fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar
         relm,
         open_menu_item,
         connect_activate(_),
-        if let Some(project) = crate::utils::get_config() {
-            Some(Msg::OpenProject(project.0, project.1))
-        } else {
-            None
-        }
+        crate::utils::get_project_path().map(Msg::OpenProject)
     );
 
     connect!(relm, save_menu_item, connect_activate(_), Msg::SaveProject);
+    connect!(
+        relm,
+        save_as_menu_item,
+        connect_activate(_),
+        Msg::SaveProjectAs
+    );
+    connect!(
+        relm,
+        save_as_template_menu_item,
+        connect_activate(_),
+        Msg::SaveProjectAsTemplate
+    );
+
+    connect!(
+        relm,
+        export_menu_item,
+        connect_activate(_),
+        Msg::ExportProject
+    );
+
+    connect!(relm, undo_menu_item, connect_activate(_), Msg::Undo);
+    connect!(relm, redo_menu_item, connect_activate(_), Msg::Redo);
+    connect!(
+        relm,
+        duplicate_stage_menu_item,
+        connect_activate(_),
+        Msg::DuplicateStage
+    );
+    connect!(
+        relm,
+        copy_stage_menu_item,
+        connect_activate(_),
+        Msg::CopyStage
+    );
+    connect!(
+        relm,
+        paste_stage_menu_item,
+        connect_activate(_),
+        Msg::PasteStage
+    );
+
+    connect!(
+        relm,
+        capture_menu_item,
+        connect_activate(_),
+        Msg::CaptureFrame
+    );
 
     connect!(
         relm,
@@ -262,7 +1173,7 @@ fn build_menu_bar(relm: &Relm<MainWindow>, accel_group: &AccelGroup) -> MenuBar
 
     connect!(relm, quit, connect_activate(_), Msg::Quit);
 
-    menu_bar
+    (menu_bar, recent_menu)
 }
 
 impl Widget for MainWindow {
@@ -275,7 +1186,10 @@ impl Widget for MainWindow {
     fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
         let settings = Settings::get_default().unwrap();
         settings
-            .set_property("gtk-application-prefer-dark-theme", &model.dark_mode)
+            .set_property(
+                "gtk-application-prefer-dark-theme",
+                &model.preferences.dark_mode,
+            )
             .unwrap();
 
         let provider = gtk::CssProvider::new();
@@ -294,7 +1208,16 @@ impl Widget for MainWindow {
         window.hide();
         window.set_title("wvr");
         window.set_position(gtk::WindowPosition::Center);
-        window.maximize();
+        match model.preferences.window_geometry {
+            Some(geometry) => {
+                window.move_(geometry.x, geometry.y);
+                window.resize(geometry.width, geometry.height);
+                if geometry.maximized {
+                    window.maximize();
+                }
+            }
+            None => window.maximize(),
+        }
 
         if let Err(e) = wvr::utils::init_wvr_data_directory() {
             let error_message = MessageDialogBuilder::new()
@@ -315,11 +1238,15 @@ impl Widget for MainWindow {
 
         let v_box = gtk::Box::new(Orientation::Vertical, 0);
 
-        v_box.pack_start(&build_menu_bar(relm, &accel_group), false, false, 0);
+        let (menu_bar, recent_menu) = build_menu_bar(relm, &accel_group);
+        v_box.pack_start(&menu_bar, false, false, 0);
 
         let config_panel_container = gtk::Box::new(Orientation::Vertical, 0);
 
-        config_panel_container.add(&welcome_panel::build_welcome_panel(relm));
+        let welcome_panel = config_panel_container.add_widget::<WelcomePanel>((
+            relm.clone(),
+            model.preferences.get_recent_project_list(),
+        ));
 
         v_box.pack_start(&config_panel_container, true, true, 0);
 
@@ -327,21 +1254,27 @@ impl Widget for MainWindow {
             relm,
             window,
             connect_delete_event(_, _),
-            return (Some(Msg::Quit), Inhibit(false))
+            return (Some(Msg::Quit), Inhibit(true))
         );
 
         window.add(&v_box);
 
-        window.show_all();
-
-        MainWindow {
+        let main_window = MainWindow {
             model,
             window,
 
             config_panel_container,
             config_panel: None,
+            welcome_panel: Some(welcome_panel),
+
+            recent_menu,
 
             relm: relm.clone(),
-        }
+        };
+        main_window.update_recent_menu();
+
+        main_window.window.show_all();
+
+        main_window
     }
 }
@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use serde_json::{json, Map, Value};
+
+use wvr_data::config::project_config::ProjectConfig;
+
+pub const SCHEMA_VERSION_KEY: &str = "launcher_schema_version";
+
+/// Version written in saved configs, configs without version are considered to be version 0.
+pub const CONFIG_SCHEMA_VERSION: u64 = 1;
+
+/// Applies a migration in place, returning a description of each performed change.
+type Migration = fn(&mut Value) -> Vec<String>;
+
+// The migration at index `n` upgrades a config from version `n` to version `n + 1`
+const MIGRATION_LIST: &[Migration] = &[migrate_unversioned_config];
+
+pub struct MigrationReport {
+    pub from_version: u64,
+    pub to_version: u64,
+    pub step_list: Vec<String>,
+}
+
+fn insert_missing_field(
+    object: &mut Map<String, Value>,
+    field_name: &str,
+    value: Value,
+    location: &str,
+    step_list: &mut Vec<String>,
+) {
+    if !object.contains_key(field_name) {
+        step_list.push(format!(
+            "Added missing {:}.{:} with value {:}",
+            location, field_name, value
+        ));
+        object.insert(field_name.to_owned(), value);
+    }
+}
+
+fn migrate_render_stage(stage: &mut Value, location: &str, step_list: &mut Vec<String>) {
+    if let Some(stage) = stage.as_object_mut() {
+        insert_missing_field(stage, "precision", json!("F32"), location, step_list);
+        insert_missing_field(
+            stage,
+            "filter_mode_params",
+            json!({ "Rectangle": [0.0, 0.0, 1.0, 1.0] }),
+            location,
+            step_list,
+        );
+    }
+}
+
+fn migrate_unversioned_config(config: &mut Value) -> Vec<String> {
+    let mut step_list = Vec::new();
+
+    if let Some(view) = config.get_mut("view").and_then(Value::as_object_mut) {
+        insert_missing_field(view, "locked_speed", json!(false), "view", &mut step_list);
+        insert_missing_field(
+            view,
+            "screenshot_frame_count",
+            json!(-1),
+            "view",
+            &mut step_list,
+        );
+        insert_missing_field(
+            view,
+            "screenshot_path",
+            json!("output/"),
+            "view",
+            &mut step_list,
+        );
+    }
+
+    if let Some(render_chain) = config.get_mut("render_chain").and_then(Value::as_array_mut) {
+        for (stage_index, stage) in render_chain.iter_mut().enumerate() {
+            let location = format!("render_chain[{:}]", stage_index);
+            migrate_render_stage(stage, &location, &mut step_list);
+        }
+    }
+
+    if let Some(final_stage) = config.get_mut("final_stage") {
+        migrate_render_stage(final_stage, "final_stage", &mut step_list);
+    }
+
+    step_list
+}
+
+pub fn get_schema_version(config: &Value) -> u64 {
+    config
+        .get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+pub fn set_schema_version(config: &mut Value) {
+    if let Some(config) = config.as_object_mut() {
+        config.insert(SCHEMA_VERSION_KEY.to_owned(), json!(CONFIG_SCHEMA_VERSION));
+    }
+}
+
+/// Upgrades a raw config to the current schema version, returning the list of applied changes.
+pub fn migrate(config: &mut Value) -> Vec<String> {
+    let mut step_list = Vec::new();
+
+    let version = get_schema_version(config);
+    if version >= CONFIG_SCHEMA_VERSION {
+        return step_list;
+    }
+
+    for (migration_version, migration) in MIGRATION_LIST.iter().enumerate().skip(version as usize) {
+        for step in migration(config) {
+            step_list.push(format!(
+                "v{:} to v{:}: {:}",
+                migration_version,
+                migration_version + 1,
+                step
+            ));
+        }
+    }
+    set_schema_version(config);
+
+    step_list
+}
+
+/// Lists the changes needed to load a config file written with an older schema, leaving the
+/// file untouched.
+pub fn get_migration_report(config_path: &Path) -> Result<Option<MigrationReport>> {
+    let config_content = std::fs::read_to_string(config_path)?;
+    let mut config: Value = serde_json::from_str(&config_content)?;
+
+    let from_version = get_schema_version(&config);
+    if from_version >= CONFIG_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    // Configs which only lack the version field are upgraded on their next save
+    let step_list = migrate(&mut config);
+    if step_list.is_empty() {
+        return Ok(None);
+    }
+
+    // Only configs which load once migrated are reported
+    serde_json::from_value::<ProjectConfig>(config)?;
+
+    Ok(Some(MigrationReport {
+        from_version,
+        to_version: CONFIG_SCHEMA_VERSION,
+        step_list,
+    }))
+}
+
+// Backups of a config are never overwritten, later ones get a number
+fn get_backup_path(config_path: &Path, from_version: u64) -> PathBuf {
+    let mut backup_path = config_path.with_extension(format!("v{:}.backup.json", from_version));
+
+    let mut backup_index = 1;
+    while backup_path.exists() {
+        backup_path =
+            config_path.with_extension(format!("v{:}.backup.{:}.json", from_version, backup_index));
+        backup_index += 1;
+    }
+
+    backup_path
+}
+
+/// Copies a config file written with an older schema aside before it is overwritten, returning
+/// the path of the copy.
+pub fn backup_outdated_config(config_path: &Path) -> Result<Option<PathBuf>> {
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+
+    let mut config: Value = match std::fs::read_to_string(config_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|config_content| Ok(serde_json::from_str(&config_content)?))
+    {
+        Ok(config) => config,
+        Err(_) => return Ok(None),
+    };
+
+    let from_version = get_schema_version(&config);
+    if from_version >= CONFIG_SCHEMA_VERSION || migrate(&mut config).is_empty() {
+        return Ok(None);
+    }
+
+    let backup_path = get_backup_path(config_path, from_version);
+    std::fs::copy(config_path, &backup_path)?;
+
+    Ok(Some(backup_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_v0_config() -> Value {
+        json!({
+            "bpm": 89.0,
+            "view": {
+                "width": 640,
+                "height": 480,
+                "fullscreen": false,
+                "dynamic": true,
+                "vsync": true,
+                "screenshot": false,
+                "target_fps": 60.0
+            },
+            "server": {
+                "ip": "127.0.0.1",
+                "port": 3000,
+                "enable": false
+            },
+            "inputs": {},
+            "render_chain": [
+                {
+                    "name": "patterns",
+                    "filter": "dot_pattern",
+                    "inputs": {},
+                    "variables": {}
+                }
+            ],
+            "final_stage": {
+                "name": "final_stage",
+                "filter": "copy",
+                "inputs": {},
+                "variables": {}
+            }
+        })
+    }
+
+    #[test]
+    fn migrate_v0_config() {
+        let mut config = build_v0_config();
+
+        let step_list = migrate(&mut config);
+
+        assert_eq!(step_list.len(), 7);
+        assert!(step_list.iter().all(|step| step.starts_with("v0 to v1: ")));
+        assert_eq!(get_schema_version(&config), CONFIG_SCHEMA_VERSION);
+        assert_eq!(config["view"]["locked_speed"], json!(false));
+        assert_eq!(config["view"]["screenshot_frame_count"], json!(-1));
+        assert_eq!(config["view"]["screenshot_path"], json!("output/"));
+        assert_eq!(config["render_chain"][0]["precision"], json!("F32"));
+        assert_eq!(
+            config["final_stage"]["filter_mode_params"],
+            json!({ "Rectangle": [0.0, 0.0, 1.0, 1.0] })
+        );
+        assert!(serde_json::from_value::<ProjectConfig>(config).is_ok());
+    }
+
+    #[test]
+    fn migrate_current_config() {
+        let mut config = build_v0_config();
+        migrate(&mut config);
+        let current_config = config.clone();
+
+        let step_list = migrate(&mut config);
+
+        assert!(step_list.is_empty());
+        assert_eq!(config, current_config);
+    }
+
+    #[test]
+    fn migrate_keeps_existing_fields() {
+        let mut config = build_v0_config();
+        config["view"]["screenshot_path"] = json!("captures/");
+        config["render_chain"][0]["precision"] = json!("U8");
+
+        let step_list = migrate(&mut config);
+
+        assert_eq!(step_list.len(), 5);
+        assert_eq!(config["view"]["screenshot_path"], json!("captures/"));
+        assert_eq!(config["render_chain"][0]["precision"], json!("U8"));
+    }
+}
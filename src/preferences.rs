@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+
+const PREFERENCES_FILE_NAME: &str = "launcher_preferences.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct PanedPositions {
+    /// Position of the separator between the stage/input lists and the preview.
+    pub project: Option<i32>,
+    /// Position of the separator between the stage list and the input list.
+    pub render_stage_list: Option<i32>,
+}
+
+/// Settings of the built-in default template, other templates carry their own.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NewProjectDefaults {
+    pub bpm: f32,
+    pub width: i64,
+    pub height: i64,
+    pub target_fps: f32,
+    /// Name of the template preselected in the New Project dialog.
+    pub template: Option<String>,
+}
+
+impl Default for NewProjectDefaults {
+    fn default() -> Self {
+        Self {
+            bpm: 89.0,
+            width: 640,
+            height: 480,
+            target_fps: 60.0,
+            template: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    pub dark_mode: bool,
+    pub window_geometry: Option<WindowGeometry>,
+    pub paned_positions: PanedPositions,
+    pub recent_project_count: usize,
+    pub recent_project_list: Vec<PathBuf>,
+    pub new_project: NewProjectDefaults,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            window_geometry: None,
+            paned_positions: PanedPositions::default(),
+            recent_project_count: 10,
+            recent_project_list: Vec::new(),
+            new_project: NewProjectDefaults::default(),
+        }
+    }
+}
+
+fn get_preferences_path() -> PathBuf {
+    wvr_data::get_data_path().join(PREFERENCES_FILE_NAME)
+}
+
+impl Preferences {
+    /// Loads the launcher preferences, falling back to the defaults if they are missing or invalid.
+    pub fn load() -> Self {
+        let preferences_path = get_preferences_path();
+        if !preferences_path.exists() {
+            return Self::default();
+        }
+
+        match File::open(&preferences_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(serde_json::from_reader::<File, Self>(file)?))
+        {
+            Ok(preferences) => preferences,
+            Err(e) => {
+                eprintln!("Failed to load preferences {:?}: {:?}", preferences_path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let preferences_as_bytes = serde_json::ser::to_string_pretty(self)?.into_bytes();
+
+        let mut preferences_file = File::create(get_preferences_path())?;
+        preferences_file.write_all(&preferences_as_bytes)?;
+
+        Ok(())
+    }
+
+    pub fn add_recent_project(&mut self, project_path: &Path) {
+        self.recent_project_list
+            .retain(|recent_project_path| recent_project_path != project_path);
+        self.recent_project_list.insert(0, project_path.to_owned());
+        self.recent_project_list.truncate(self.recent_project_count);
+    }
+
+    pub fn remove_recent_project(&mut self, project_path: &Path) {
+        self.recent_project_list
+            .retain(|recent_project_path| recent_project_path != project_path);
+    }
+
+    /// Keeps a renamed project at its place in the recent projects.
+    pub fn rename_recent_project(&mut self, old_project_path: &Path, new_project_path: &Path) {
+        for recent_project_path in &mut self.recent_project_list {
+            if recent_project_path == old_project_path {
+                *recent_project_path = new_project_path.to_owned();
+            }
+        }
+    }
+
+    /// Recent projects which still exist on disk.
+    pub fn get_recent_project_list(&self) -> Vec<PathBuf> {
+        self.recent_project_list
+            .iter()
+            .filter(|project_path| project_path.join("config.json").is_file())
+            .cloned()
+            .collect()
+    }
+}
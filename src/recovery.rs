@@ -0,0 +1,426 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use wvr_data::config::project_config::ProjectConfig;
+
+use crate::config_panel::history::MERGE_DELAY;
+use crate::migration;
+
+pub const AUTOSAVE_FILE_NAME: &str = "config.autosave.json";
+pub const JOURNAL_FILE_NAME: &str = "config.journal";
+
+pub const AUTOSAVE_INTERVAL_SECONDS: u32 = 30;
+
+/// A single applied edit, storing the new value of the top-level config section it touched.
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    timestamp: u64,
+    edit: String,
+    section: String,
+    index: Option<usize>,
+    value: Value,
+}
+
+pub struct RecoveryState {
+    pub config: ProjectConfig,
+    pub modified: SystemTime,
+    pub edit_list: Vec<String>,
+}
+
+/// Edits merged the same way as in the history, written once no other edit is merged into them.
+struct PendingEdit {
+    merge_key: String,
+    edit: String,
+    previous_config: ProjectConfig,
+    last_edit_time: Instant,
+}
+
+pub struct RecoveryJournal {
+    project_path: PathBuf,
+    last_autosave: Option<Value>,
+    pending_edit: Option<PendingEdit>,
+}
+
+fn get_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn get_modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Snapshots carry the schema version, so that recovered configs are migrated like saved ones
+fn build_snapshot(project_config: &ProjectConfig) -> Option<Value> {
+    let mut snapshot = serde_json::to_value(project_config).ok()?;
+    migration::set_schema_version(&mut snapshot);
+
+    Some(snapshot)
+}
+
+fn build_journal_entry_list(edit: &str, previous: &Value, current: &Value) -> Vec<JournalEntry> {
+    let mut entry_list = Vec::new();
+
+    let (previous, current) = match (previous.as_object(), current.as_object()) {
+        (Some(previous), Some(current)) => (previous, current),
+        _ => return entry_list,
+    };
+
+    let timestamp = get_timestamp();
+    for (section, value) in current {
+        let previous_value = previous.get(section);
+        if previous_value == Some(value) {
+            continue;
+        }
+
+        // Edits of a single stage only store that stage
+        if let (Some(Value::Array(previous_stage_list)), Value::Array(stage_list)) =
+            (previous_value, value)
+        {
+            if previous_stage_list.len() == stage_list.len() {
+                for (index, (previous_stage, stage)) in previous_stage_list
+                    .iter()
+                    .zip(stage_list.iter())
+                    .enumerate()
+                {
+                    if previous_stage != stage {
+                        entry_list.push(JournalEntry {
+                            timestamp,
+                            edit: edit.to_string(),
+                            section: section.clone(),
+                            index: Some(index),
+                            value: stage.clone(),
+                        });
+                    }
+                }
+                continue;
+            }
+        }
+
+        entry_list.push(JournalEntry {
+            timestamp,
+            edit: edit.to_string(),
+            section: section.clone(),
+            index: None,
+            value: value.clone(),
+        });
+    }
+
+    entry_list
+}
+
+fn apply_journal_entry(config: &mut Value, entry: JournalEntry) {
+    match entry.index {
+        Some(index) => {
+            if let Some(stage) = config
+                .get_mut(&entry.section)
+                .and_then(|section| section.get_mut(index))
+            {
+                *stage = entry.value;
+            }
+        }
+        None => {
+            if let Some(config) = config.as_object_mut() {
+                config.insert(entry.section, entry.value);
+            }
+        }
+    }
+}
+
+// Applies the entries of a journal in order, returns the edits they were recorded for
+fn replay_journal<R: BufRead>(config: &mut Value, journal: R) -> Vec<String> {
+    let mut edit_list = Vec::new();
+
+    for line in journal.lines() {
+        // A partially written last line is expected after a crash
+        if let Some(entry) = line
+            .ok()
+            .and_then(|line| serde_json::from_str::<JournalEntry>(&line).ok())
+        {
+            if edit_list.last() != Some(&entry.edit) {
+                edit_list.push(entry.edit.clone());
+            }
+            apply_journal_entry(config, entry);
+        }
+    }
+
+    edit_list
+}
+
+impl RecoveryJournal {
+    pub fn new(project_path: &Path, project_config: &ProjectConfig) -> Self {
+        Self {
+            project_path: project_path.to_owned(),
+            last_autosave: build_snapshot(project_config),
+            pending_edit: None,
+        }
+    }
+
+    /// Records an edit applied on top of `previous_config`. Edits of the same value in a quick
+    /// succession are written as one once they stop, returns true if a new edit was started.
+    pub fn record_edit(
+        &mut self,
+        merge_key: &str,
+        edit: &str,
+        previous_config: &ProjectConfig,
+    ) -> Result<bool> {
+        if let Some(pending_edit) = &mut self.pending_edit {
+            if pending_edit.merge_key == merge_key
+                && pending_edit.last_edit_time.elapsed() < MERGE_DELAY
+            {
+                pending_edit.edit = edit.to_string();
+                pending_edit.last_edit_time = Instant::now();
+                return Ok(false);
+            }
+        }
+
+        // The pending edit ended with the state this one started from
+        let result = self.write_pending_edit(previous_config);
+        self.pending_edit = Some(PendingEdit {
+            merge_key: merge_key.to_string(),
+            edit: edit.to_string(),
+            previous_config: previous_config.clone(),
+            last_edit_time: Instant::now(),
+        });
+
+        result.map(|_| true)
+    }
+
+    /// Writes the pending edit if nothing was merged into it for a while, returns false while it
+    /// is still being merged into.
+    pub fn flush_idle_edit(&mut self, current_config: &ProjectConfig) -> Result<bool> {
+        match &self.pending_edit {
+            Some(pending_edit) if pending_edit.last_edit_time.elapsed() < MERGE_DELAY => Ok(false),
+            _ => self.write_pending_edit(current_config).map(|_| true),
+        }
+    }
+
+    /// Appends the sections changed by the pending edit, up to `current_config`, to the journal.
+    fn write_pending_edit(&mut self, current_config: &ProjectConfig) -> Result<()> {
+        let pending_edit = match self.pending_edit.take() {
+            Some(pending_edit) => pending_edit,
+            None => return Ok(()),
+        };
+
+        let previous = serde_json::to_value(&pending_edit.previous_config)?;
+        let current = serde_json::to_value(current_config)?;
+
+        let entry_list = build_journal_entry_list(&pending_edit.edit, &previous, &current);
+        if entry_list.is_empty() {
+            return Ok(());
+        }
+
+        let mut journal_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.project_path.join(JOURNAL_FILE_NAME))?;
+
+        for entry in entry_list {
+            journal_file.write_all(serde_json::to_string(&entry)?.as_bytes())?;
+            journal_file.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a full snapshot of the project if it changed since the last one, then truncates the journal.
+    pub fn autosave(&mut self, project_config: &ProjectConfig) -> Result<()> {
+        let snapshot = match build_snapshot(project_config) {
+            Some(snapshot) => snapshot,
+            None => return Ok(()),
+        };
+        if self.last_autosave.as_ref() == Some(&snapshot) {
+            return Ok(());
+        }
+
+        // The snapshot holds the result of the pending edit
+        self.pending_edit = None;
+
+        let autosave_path = self.project_path.join(AUTOSAVE_FILE_NAME);
+        let autosave_tmp_path = self
+            .project_path
+            .join(format!("{:}.tmp", AUTOSAVE_FILE_NAME));
+
+        let mut autosave_file = File::create(&autosave_tmp_path)?;
+        autosave_file.write_all(&serde_json::ser::to_string_pretty(&snapshot)?.into_bytes())?;
+        autosave_file.sync_all()?;
+        std::fs::rename(&autosave_tmp_path, &autosave_path)?;
+
+        File::create(self.project_path.join(JOURNAL_FILE_NAME))?;
+
+        self.last_autosave = Some(snapshot);
+
+        Ok(())
+    }
+
+    /// Forgets the recovery state once the project has been saved.
+    pub fn clear(&mut self, project_config: &ProjectConfig) {
+        clear_recovery_files(&self.project_path);
+        self.last_autosave = build_snapshot(project_config);
+        self.pending_edit = None;
+    }
+}
+
+pub fn clear_recovery_files(project_path: &Path) {
+    for file_name in &[AUTOSAVE_FILE_NAME, JOURNAL_FILE_NAME] {
+        let file_path = project_path.join(file_name);
+        if file_path.exists() {
+            if let Err(e) = std::fs::remove_file(&file_path) {
+                eprintln!("Failed to remove {:?}: {:?}", file_path, e);
+            }
+        }
+    }
+}
+
+/// Rebuilds the last known state of a project if the autosave or journal are newer than its config.
+pub fn load_recovery_state(project_path: &Path) -> Option<RecoveryState> {
+    let config_path = project_path.join("config.json");
+    let autosave_path = project_path.join(AUTOSAVE_FILE_NAME);
+    let journal_path = project_path.join(JOURNAL_FILE_NAME);
+
+    let config_modified = get_modification_time(&config_path)?;
+
+    let modified = [&autosave_path, &journal_path]
+        .iter()
+        .filter_map(|path| get_modification_time(path))
+        .max()?;
+
+    if modified <= config_modified {
+        return None;
+    }
+
+    let base_config_path = if autosave_path.exists() {
+        autosave_path
+    } else {
+        config_path
+    };
+    let mut config: Value = serde_json::from_reader(File::open(&base_config_path).ok()?).ok()?;
+
+    let edit_list = match File::open(&journal_path) {
+        Ok(journal_file) => replay_journal(&mut config, BufReader::new(journal_file)),
+        Err(_) => Vec::new(),
+    };
+
+    // Base configs may predate the current schema, which the journal entries follow
+    migration::migrate(&mut config);
+    let config = serde_json::from_value::<ProjectConfig>(config).ok()?;
+
+    Some(RecoveryState {
+        config,
+        modified,
+        edit_list,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn build_config(stage_list: Value, bpm: f64) -> Value {
+        json!({
+            "bpm": bpm,
+            "view": { "width": 640, "height": 360 },
+            "render_chain": stage_list,
+        })
+    }
+
+    fn build_journal(entry_list: &[JournalEntry]) -> String {
+        entry_list
+            .iter()
+            .map(|entry| format!("{:}\n", serde_json::to_string(entry).unwrap()))
+            .collect()
+    }
+
+    fn replay(base: &Value, journal: &str) -> (Value, Vec<String>) {
+        let mut config = base.clone();
+        let edit_list = replay_journal(&mut config, journal.as_bytes());
+
+        (config, edit_list)
+    }
+
+    #[test]
+    fn unchanged_config_has_no_entry() {
+        let config = build_config(json!([{ "name": "a" }]), 120.0);
+
+        assert!(build_journal_entry_list("Nothing", &config, &config).is_empty());
+    }
+
+    #[test]
+    fn stage_edit_only_stores_that_stage() {
+        let previous = build_config(json!([{ "name": "a" }, { "name": "b", "value": 1 }]), 120.0);
+        let current = build_config(json!([{ "name": "a" }, { "name": "b", "value": 2 }]), 120.0);
+
+        let entry_list = build_journal_entry_list("Edit b", &previous, &current);
+        assert_eq!(entry_list.len(), 1);
+        assert_eq!(entry_list[0].section, "render_chain");
+        assert_eq!(entry_list[0].index, Some(1));
+
+        let (config, edit_list) = replay(&previous, &build_journal(&entry_list));
+        assert_eq!(config, current);
+        assert_eq!(edit_list, vec!["Edit b".to_owned()]);
+    }
+
+    #[test]
+    fn stage_count_change_replaces_section() {
+        let previous = build_config(json!([{ "name": "a" }]), 120.0);
+        let current = build_config(json!([{ "name": "a" }, { "name": "b" }]), 120.0);
+
+        let entry_list = build_journal_entry_list("Add b", &previous, &current);
+        assert_eq!(entry_list.len(), 1);
+        assert_eq!(entry_list[0].index, None);
+
+        let (config, _) = replay(&previous, &build_journal(&entry_list));
+        assert_eq!(config, current);
+
+        // Removing the stage again goes back to the base
+        let entry_list = build_journal_entry_list("Remove b", &current, &previous);
+        let (config, _) = replay(&current, &build_journal(&entry_list));
+        assert_eq!(config, previous);
+    }
+
+    #[test]
+    fn successive_edits_replay_in_order() {
+        let base = build_config(json!([{ "name": "a" }, { "name": "b" }]), 120.0);
+        let first = build_config(json!([{ "name": "a", "value": 1 }, { "name": "b" }]), 90.0);
+        let second = build_config(json!([{ "name": "a", "value": 1 }, { "name": "c" }]), 90.0);
+
+        let mut entry_list = build_journal_entry_list("First", &base, &first);
+        entry_list.extend(build_journal_entry_list("Second", &first, &second));
+
+        let (config, edit_list) = replay(&base, &build_journal(&entry_list));
+        assert_eq!(config, second);
+        assert_eq!(edit_list, vec!["First".to_owned(), "Second".to_owned()]);
+    }
+
+    #[test]
+    fn truncated_last_line_is_skipped() {
+        let base = build_config(json!([{ "name": "a" }]), 120.0);
+        let first = build_config(json!([{ "name": "a" }]), 90.0);
+        let second = build_config(json!([{ "name": "b" }]), 90.0);
+
+        let first_journal = build_journal(&build_journal_entry_list("First", &base, &first));
+        let second_journal = build_journal(&build_journal_entry_list("Second", &first, &second));
+        let journal = format!(
+            "{:}{:}",
+            first_journal,
+            &second_journal[..second_journal.len() / 2]
+        );
+
+        let (config, edit_list) = replay(&base, &journal);
+        assert_eq!(config, first);
+        assert_eq!(edit_list, vec!["First".to_owned()]);
+    }
+}
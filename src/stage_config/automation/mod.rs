@@ -1,3 +1,8 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
 use gtk::{
     prelude::{GtkListStoreExtManual, TreeSortableExtManual},
     Button, ButtonExt, ComboBoxExt, ContainerExt, Grid, GridExt, Label, LabelExt, MenuButton,
@@ -20,6 +25,64 @@ use super::{
     view::{RenderStageConfigView, RenderStageConfigViewMsg},
 };
 
+/// Automation of the variables of a stage and tempo of the project, for editors to preview LFOs.
+pub struct AutomationPreview {
+    pub bpm: Cell<f64>,
+    pub automation_list: RefCell<HashMap<String, Automation>>,
+}
+
+pub type SharedAutomationPreview = Rc<AutomationPreview>;
+
+impl AutomationPreview {
+    pub fn new_shared(
+        bpm: f64,
+        automation_list: HashMap<String, Automation>,
+    ) -> SharedAutomationPreview {
+        Rc::new(Self {
+            bpm: Cell::new(bpm),
+            automation_list: RefCell::new(automation_list),
+        })
+    }
+
+    pub fn get_automation(&self, variable_name: &str) -> Automation {
+        self.automation_list
+            .borrow()
+            .get(variable_name)
+            .copied()
+            .unwrap_or(Automation::None)
+    }
+
+    /// Beat reached after `time` seconds at the tempo of the project.
+    pub fn get_beat(&self, time: f64) -> f64 {
+        time * self.bpm.get() / 60.0
+    }
+}
+
+/// Offset an LFO adds to its variable at `beat`, mirroring the shapes of the renderer closely
+/// enough to preview them.
+pub fn evaluate_lfo(lfo: &Lfo, beat: f64) -> f64 {
+    let progress = (beat * lfo.numerator / lfo.denominator.max(1.0) + lfo.phase).rem_euclid(1.0);
+
+    let wave = match lfo.lfo_type {
+        LfoType::Saw => progress,
+        LfoType::Sine => 0.5 - 0.5 * (progress * 2.0 * PI).cos(),
+        LfoType::Square => {
+            if progress < 0.5 {
+                0.0
+            } else {
+                1.0
+            }
+        }
+        LfoType::Triangle => 1.0 - (progress * 2.0 - 1.0).abs(),
+    };
+
+    if lfo.signed {
+        lfo.amplitude * (wave * 2.0 - 1.0)
+    } else {
+        lfo.amplitude * wave
+    }
+}
+
 pub fn build_automation_selector(
     parent_relm: Relm<RenderStageConfigView>,
     variable_name: String,
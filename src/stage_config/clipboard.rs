@@ -0,0 +1,326 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use serde::{Deserialize, Serialize};
+
+use strsim::levenshtein;
+
+use wvr_data::config::project_config::{ProjectConfig, RenderStageConfig};
+use wvr_data::DataHolder;
+
+use crate::config_panel::get_input_choice_list;
+use crate::config_panel::validation::{get_sampled_input_name, load_filter_list};
+
+use super::input::resample;
+use super::variable::{format_variable_value, parse_variable_value};
+
+// Distinguishes copied stages from any other JSON found in the clipboard
+const CLIPBOARD_CONTENT_KIND: &str = "wvr-launcher/render-stage";
+
+#[derive(Serialize, Deserialize)]
+struct ClipboardContent {
+    kind: String,
+    project_path: PathBuf,
+    render_stage: RenderStageConfig,
+}
+
+pub struct PastedRenderStage {
+    pub render_stage: RenderStageConfig,
+    /// Filters and sources missing from the project the stage is pasted in.
+    pub warning_list: Vec<String>,
+}
+
+fn get_clipboard() -> gtk::Clipboard {
+    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD)
+}
+
+/// Returns `name`, or a numbered copy of it if a stage or an input already uses it.
+pub fn get_available_stage_name(config: &ProjectConfig, name: &str) -> String {
+    let is_taken = |candidate: &str| {
+        config.inputs.contains_key(candidate)
+            || config
+                .render_chain
+                .iter()
+                .any(|stage| stage.name == candidate)
+    };
+
+    if !is_taken(name) {
+        return name.to_owned();
+    }
+
+    (1..)
+        .map(|copy_index| {
+            if copy_index == 1 {
+                format!("{:} copy", name)
+            } else {
+                format!("{:} copy {:}", name, copy_index)
+            }
+        })
+        .find(|candidate| !is_taken(candidate))
+        .unwrap()
+}
+
+/// Gives a copy of a stage a name available in `config`, its feedback inputs sample the copy.
+pub fn rename_copied_stage(config: &ProjectConfig, render_stage: &mut RenderStageConfig) {
+    let original_name = render_stage.name.clone();
+    render_stage.name = get_available_stage_name(config, &original_name);
+
+    for sampled_input in render_stage.inputs.values_mut() {
+        if get_sampled_input_name(sampled_input) == original_name {
+            *sampled_input = resample(Some(&*sampled_input), &render_stage.name);
+        }
+    }
+}
+
+pub fn copy_render_stage(project_path: &Path, render_stage: &RenderStageConfig) -> Result<()> {
+    let content = ClipboardContent {
+        kind: CLIPBOARD_CONTENT_KIND.to_owned(),
+        project_path: project_path.to_owned(),
+        render_stage: render_stage.clone(),
+    };
+
+    get_clipboard().set_text(&serde_json::ser::to_string_pretty(&content)?);
+
+    Ok(())
+}
+
+/// Reads a stage from the clipboard, renamed and with its inputs remapped to fit `config`.
+pub fn paste_render_stage(
+    project_path: &Path,
+    config: &ProjectConfig,
+) -> Result<PastedRenderStage> {
+    let content = match get_clipboard().wait_for_text() {
+        Some(content) => content,
+        None => bail!("The clipboard does not hold any text"),
+    };
+
+    let content: ClipboardContent = serde_json::from_str(&content)?;
+    if content.kind != CLIPBOARD_CONTENT_KIND {
+        bail!("The clipboard does not hold a render stage");
+    }
+
+    let mut render_stage = content.render_stage;
+    let mut warning_list = Vec::new();
+
+    rename_copied_stage(config, &mut render_stage);
+
+    let available_filter_list = load_filter_list(project_path);
+    if !available_filter_list.contains_key(&render_stage.filter) {
+        if content
+            .project_path
+            .join("filters")
+            .join(&render_stage.filter)
+            .exists()
+        {
+            warning_list.push(format!(
+                "Filter \"{:}\" is specific to {:?}, copy it to the filters folder of this project",
+                render_stage.filter, content.project_path
+            ));
+        } else {
+            warning_list.push(format!(
+                "Filter \"{:}\" is not available",
+                render_stage.filter
+            ));
+        }
+    }
+
+    // Sources missing from this project are replaced by the closest name, as when renaming one
+    let input_choice_list = get_input_choice_list(config);
+    let mut uniform_name_list: Vec<String> = render_stage.inputs.keys().cloned().collect();
+    uniform_name_list.sort();
+    for uniform_name in uniform_name_list {
+        let sampled_input = &render_stage.inputs[&uniform_name];
+        let input_name = get_sampled_input_name(sampled_input);
+
+        let new_input_name = if input_name.is_empty()
+            || input_name == render_stage.name
+            || input_choice_list.iter().any(|name| name == input_name)
+        {
+            continue;
+        } else {
+            let new_input_name = input_choice_list
+                .iter()
+                .min_by_key(|candidate| levenshtein(input_name, candidate))
+                .cloned()
+                .unwrap_or_default();
+
+            warning_list.push(if new_input_name.is_empty() {
+                format!(
+                    "Input {:} sampled \"{:}\", which does not exist in this project",
+                    uniform_name, input_name
+                )
+            } else {
+                format!(
+                    "Input {:} sampled \"{:}\", which does not exist in this project, it now samples \"{:}\"",
+                    uniform_name, input_name, new_input_name
+                )
+            });
+
+            new_input_name
+        };
+
+        let new_sampled_input = resample(Some(sampled_input), &new_input_name);
+        render_stage.inputs.insert(uniform_name, new_sampled_input);
+    }
+
+    Ok(PastedRenderStage {
+        render_stage,
+        warning_list,
+    })
+}
+
+/// Copies a variable value as text, so that it can be pasted to any variable of the same kind.
+pub fn copy_variable_value(value: &DataHolder) -> Result<()> {
+    match format_variable_value(value) {
+        Some(value) => get_clipboard().set_text(&value),
+        None => bail!("This variable type can not be copied"),
+    }
+
+    Ok(())
+}
+
+/// Reads from the clipboard a value of the same kind as `template`.
+pub fn paste_variable_value(template: &DataHolder) -> Result<DataHolder> {
+    match get_clipboard().wait_for_text() {
+        Some(content) => parse_variable_value(&content, template),
+        None => bail!("The clipboard does not hold any text"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wvr_data::config::project_config::{
+        BufferPrecision, FilterMode, InputConfig, SampledInput, ViewConfig,
+    };
+    use wvr_data::config::server_config::ServerConfig;
+
+    use super::*;
+
+    fn build_stage(name: &str, input_list: &[(&str, SampledInput)]) -> RenderStageConfig {
+        RenderStageConfig {
+            name: name.to_owned(),
+            filter: "generic/copy".to_owned(),
+            filter_mode_params: FilterMode::Rectangle(0.0, 0.0, 1.0, 1.0),
+            inputs: input_list
+                .iter()
+                .map(|(uniform_name, sampled_input)| {
+                    ((*uniform_name).to_owned(), sampled_input.clone())
+                })
+                .collect(),
+            variables: HashMap::new(),
+            precision: BufferPrecision::U8,
+        }
+    }
+
+    fn build_config(stage_name_list: &[&str], input_name_list: &[&str]) -> ProjectConfig {
+        ProjectConfig {
+            bpm: 120.0,
+            view: ViewConfig {
+                width: 640,
+                height: 360,
+                fullscreen: false,
+                dynamic: true,
+                vsync: true,
+                screenshot: false,
+                screenshot_path: PathBuf::from("output/"),
+                screenshot_frame_count: -1,
+                target_fps: 60.0,
+                locked_speed: false,
+            },
+            server: ServerConfig {
+                ip: "localhost".to_owned(),
+                port: 3000,
+                enable: false,
+            },
+            inputs: input_name_list
+                .iter()
+                .map(|input_name| {
+                    (
+                        (*input_name).to_owned(),
+                        InputConfig::Midi {
+                            name: (*input_name).to_owned(),
+                        },
+                    )
+                })
+                .collect(),
+            render_chain: stage_name_list
+                .iter()
+                .map(|stage_name| build_stage(stage_name, &[]))
+                .collect(),
+            final_stage: build_stage("FinalStage", &[]),
+        }
+    }
+
+    #[test]
+    fn free_name_is_kept() {
+        let config = build_config(&["a"], &["midi"]);
+
+        assert_eq!(get_available_stage_name(&config, "b"), "b");
+    }
+
+    #[test]
+    fn taken_names_are_numbered() {
+        let config = build_config(&["a"], &[]);
+        assert_eq!(get_available_stage_name(&config, "a"), "a copy");
+
+        let config = build_config(&["a", "a copy"], &[]);
+        assert_eq!(get_available_stage_name(&config, "a"), "a copy 2");
+
+        let config = build_config(&["a", "a copy", "a copy 2"], &[]);
+        assert_eq!(get_available_stage_name(&config, "a"), "a copy 3");
+    }
+
+    #[test]
+    fn input_names_are_taken() {
+        let config = build_config(&[], &["midi", "midi copy"]);
+
+        assert_eq!(get_available_stage_name(&config, "midi"), "midi copy 2");
+    }
+
+    #[test]
+    fn feedback_inputs_sample_the_copy() {
+        let config = build_config(&["a", "b"], &[]);
+        let mut render_stage = build_stage(
+            "a",
+            &[
+                ("iChannel0", SampledInput::Nearest("a".to_owned())),
+                ("iChannel1", SampledInput::Mipmaps("b".to_owned())),
+                ("iChannel2", SampledInput::Linear(String::new())),
+            ],
+        );
+
+        rename_copied_stage(&config, &mut render_stage);
+
+        assert_eq!(render_stage.name, "a copy");
+        assert!(matches!(
+            &render_stage.inputs["iChannel0"],
+            SampledInput::Nearest(input_name) if input_name == "a copy"
+        ));
+        assert!(matches!(
+            &render_stage.inputs["iChannel1"],
+            SampledInput::Mipmaps(input_name) if input_name == "b"
+        ));
+        assert!(matches!(
+            &render_stage.inputs["iChannel2"],
+            SampledInput::Linear(input_name) if input_name.is_empty()
+        ));
+    }
+
+    #[test]
+    fn stage_with_free_name_keeps_its_inputs() {
+        let config = build_config(&["b"], &[]);
+        let mut render_stage =
+            build_stage("a", &[("iChannel0", SampledInput::Linear("a".to_owned()))]);
+
+        rename_copied_stage(&config, &mut render_stage);
+
+        assert_eq!(render_stage.name, "a");
+        assert!(matches!(
+            &render_stage.inputs["iChannel0"],
+            SampledInput::Linear(input_name) if input_name == "a"
+        ));
+    }
+}
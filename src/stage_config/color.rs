@@ -0,0 +1,348 @@
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+
+use gtk::Orientation::{Horizontal, Vertical};
+use gtk::{
+    ColorButton, ColorButtonExt, ColorChooserExt, ContainerExt, EditableSignals, Entry, EntryExt,
+    Label, Orientation, PositionType, RangeExt, Scale, ScaleExt, WidgetExt,
+};
+
+use relm::{connect, Relm};
+
+use serde_json::Value;
+
+use wvr_data::{DataHolder, DataRange};
+
+use super::view::{RenderStageConfigView, RenderStageConfigViewMsg};
+
+/// Key of the filter config listing the variables to edit as colors, unknown to the renderer.
+const COLOR_HINT_KEY: &str = "color_variables";
+
+/// Names of the variables the filter config declares as colors.
+pub fn load_color_hint_list(filter_path: &Path) -> HashSet<String> {
+    let filter_config_path = if filter_path.is_dir() {
+        filter_path.join("config.json")
+    } else {
+        filter_path.to_owned()
+    };
+
+    File::open(&filter_config_path)
+        .ok()
+        .and_then(|file| serde_json::from_reader::<File, Value>(file).ok())
+        .and_then(|filter_config| match filter_config.get(COLOR_HINT_KEY) {
+            Some(Value::Array(variable_name_list)) => Some(
+                variable_name_list
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_owned)
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Naming convention of the variables holding a color, such as `tint_color` or `fog_rgb`.
+pub fn is_color_name(variable_name: &str) -> bool {
+    let variable_name = variable_name.to_lowercase();
+
+    variable_name.contains("color")
+        || variable_name.contains("colour")
+        || variable_name.ends_with("_rgb")
+        || variable_name.ends_with("_rgba")
+}
+
+/// Only three and four component float variables can be edited as colors.
+pub fn can_be_color(variable_value: &DataHolder) -> bool {
+    matches!(
+        variable_value,
+        DataHolder::Float3(_) | DataHolder::Float4(_)
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColorSource {
+    Button,
+    Hsv,
+    Alpha,
+    Hex,
+}
+
+struct ColorEditor {
+    has_alpha: bool,
+    // Color components are mapped from 0..1 to the range of the variable
+    min_value: f64,
+    max_value: f64,
+
+    color_button: ColorButton,
+    hex_entry: Entry,
+    hue_scale: Scale,
+    saturation_scale: Scale,
+    value_scale: Scale,
+    alpha_scale: Scale,
+
+    // Set while the widgets are synced, so that their change signals are ignored
+    is_syncing: Cell<bool>,
+}
+
+impl ColorEditor {
+    fn get_unit_value(&self, value: f32) -> f64 {
+        if self.max_value > self.min_value {
+            ((value as f64 - self.min_value) / (self.max_value - self.min_value))
+                .max(0.0)
+                .min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn get_variable_value(&self, value: f64) -> f32 {
+        (self.min_value + value * (self.max_value - self.min_value)) as f32
+    }
+
+    fn get_hsv_rgba(&self) -> gdk::RGBA {
+        let (red, green, blue) = gtk::hsv_to_rgb(
+            self.hue_scale.get_value() / 360.0,
+            self.saturation_scale.get_value(),
+            self.value_scale.get_value(),
+        );
+
+        gdk::RGBA {
+            red,
+            green,
+            blue,
+            alpha: self.alpha_scale.get_value(),
+        }
+    }
+
+    fn to_data_holder(&self, rgba: &gdk::RGBA) -> DataHolder {
+        let red = self.get_variable_value(rgba.red);
+        let green = self.get_variable_value(rgba.green);
+        let blue = self.get_variable_value(rgba.blue);
+
+        if self.has_alpha {
+            DataHolder::Float4([red, green, blue, self.get_variable_value(rgba.alpha)])
+        } else {
+            DataHolder::Float3([red, green, blue])
+        }
+    }
+
+    /// Shows `rgba` in every widget but the one it was edited from.
+    fn sync(&self, rgba: &gdk::RGBA, source: Option<ColorSource>) {
+        self.is_syncing.set(true);
+
+        if source != Some(ColorSource::Button) {
+            self.color_button.set_rgba(rgba);
+        }
+        if source != Some(ColorSource::Hex) {
+            self.hex_entry.set_text(&format_hex(rgba, self.has_alpha));
+        }
+        // Hue and saturation are lost on grays, so the sliders are left where they were
+        if source != Some(ColorSource::Hsv) && source != Some(ColorSource::Alpha) {
+            let (hue, saturation, value) = gtk::rgb_to_hsv(rgba.red, rgba.green, rgba.blue);
+            self.hue_scale.set_value(hue * 360.0);
+            self.saturation_scale.set_value(saturation);
+            self.value_scale.set_value(value);
+        }
+        if source != Some(ColorSource::Alpha) {
+            self.alpha_scale.set_value(rgba.alpha);
+        }
+
+        self.is_syncing.set(false);
+    }
+}
+
+fn format_hex(rgba: &gdk::RGBA, has_alpha: bool) -> String {
+    let to_byte = |component: f64| (component.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    let hex = format!(
+        "#{:02x}{:02x}{:02x}",
+        to_byte(rgba.red),
+        to_byte(rgba.green),
+        to_byte(rgba.blue)
+    );
+    if has_alpha {
+        format!("{:}{:02x}", hex, to_byte(rgba.alpha))
+    } else {
+        hex
+    }
+}
+
+/// Parses `#rrggbb` or `#rrggbbaa`, the leading `#` being optional.
+fn parse_hex(hex: &str) -> Option<gdk::RGBA> {
+    let hex = hex.trim().trim_start_matches('#');
+    if !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+        return None;
+    }
+
+    let mut component_list = Vec::new();
+    for component_index in 0..hex.len() / 2 {
+        let component = &hex[component_index * 2..component_index * 2 + 2];
+        component_list.push(u8::from_str_radix(component, 16).ok()? as f64 / 255.0);
+    }
+
+    Some(gdk::RGBA {
+        red: component_list[0],
+        green: component_list[1],
+        blue: component_list[2],
+        alpha: component_list.get(3).copied().unwrap_or(1.0),
+    })
+}
+
+fn build_component_scale(max_value: f64, step: f64) -> Scale {
+    let scale = Scale::with_range(Orientation::Horizontal, 0.0, max_value, step);
+    scale.set_has_origin(false);
+    scale.set_hexpand(true);
+    scale.set_value_pos(PositionType::Right);
+
+    scale
+}
+
+/// Builds a color chooser button, HSV sliders and a hex entry editing a Float3 or Float4
+/// variable, alpha being the fourth component.
+pub fn create_color_editor(
+    relm: &Relm<RenderStageConfigView>,
+    name: &str,
+    value: &[f32],
+    value_range: &DataRange,
+) -> gtk::Box {
+    let (min_value, max_value) = if let DataRange::FloatRange(min_value, max_value, _) = value_range
+    {
+        (*min_value, *max_value)
+    } else {
+        (0.0, 1.0)
+    };
+
+    let editor = Rc::new(ColorEditor {
+        has_alpha: value.len() > 3,
+        min_value,
+        max_value,
+
+        color_button: ColorButton::new(),
+        hex_entry: Entry::new(),
+        hue_scale: build_component_scale(360.0, 1.0),
+        saturation_scale: build_component_scale(1.0, 0.001),
+        value_scale: build_component_scale(1.0, 0.001),
+        alpha_scale: build_component_scale(1.0, 0.001),
+
+        is_syncing: Cell::new(false),
+    });
+
+    let rgba = gdk::RGBA {
+        red: editor.get_unit_value(value.first().copied().unwrap_or_default()),
+        green: editor.get_unit_value(value.get(1).copied().unwrap_or_default()),
+        blue: editor.get_unit_value(value.get(2).copied().unwrap_or_default()),
+        alpha: value
+            .get(3)
+            .map(|alpha| editor.get_unit_value(*alpha))
+            .unwrap_or(1.0),
+    };
+
+    editor.color_button.set_use_alpha(editor.has_alpha);
+    editor.hex_entry.set_width_chars(10);
+    editor
+        .hex_entry
+        .set_tooltip_text(Some("Hexadecimal color, #rrggbb or #rrggbbaa"));
+    editor.sync(&rgba, None);
+
+    let name = name.to_string();
+    {
+        let name = name.clone();
+        let editor = editor.clone();
+        let color_button = editor.color_button.clone();
+        connect!(relm, color_button, connect_color_set(val), {
+            let rgba = val.get_rgba();
+            editor.sync(&rgba, Some(ColorSource::Button));
+
+            Some(RenderStageConfigViewMsg::UpdateVariable(
+                name.clone(),
+                editor.to_data_holder(&rgba),
+            ))
+        });
+    }
+    {
+        let name = name.clone();
+        let editor = editor.clone();
+        let hex_entry = editor.hex_entry.clone();
+        connect!(relm, hex_entry, connect_changed(val), {
+            match parse_hex(&val.get_text()) {
+                Some(rgba) if !editor.is_syncing.get() => {
+                    editor.sync(&rgba, Some(ColorSource::Hex));
+
+                    Some(RenderStageConfigViewMsg::UpdateVariable(
+                        name.clone(),
+                        editor.to_data_holder(&rgba),
+                    ))
+                }
+                _ => None,
+            }
+        });
+    }
+    for hsv_scale in &[
+        &editor.hue_scale,
+        &editor.saturation_scale,
+        &editor.value_scale,
+    ] {
+        let name = name.clone();
+        let editor = editor.clone();
+        connect!(relm, hsv_scale, connect_value_changed(_), {
+            if editor.is_syncing.get() {
+                None
+            } else {
+                let rgba = editor.get_hsv_rgba();
+                editor.sync(&rgba, Some(ColorSource::Hsv));
+
+                Some(RenderStageConfigViewMsg::UpdateVariable(
+                    name.clone(),
+                    editor.to_data_holder(&rgba),
+                ))
+            }
+        });
+    }
+    {
+        let name = name;
+        let editor = editor.clone();
+        let alpha_scale = editor.alpha_scale.clone();
+        connect!(relm, alpha_scale, connect_value_changed(val), {
+            if editor.is_syncing.get() {
+                None
+            } else {
+                let mut rgba = editor.color_button.get_rgba();
+                rgba.alpha = val.get_value();
+                editor.sync(&rgba, Some(ColorSource::Alpha));
+
+                Some(RenderStageConfigViewMsg::UpdateVariable(
+                    name.clone(),
+                    editor.to_data_holder(&rgba),
+                ))
+            }
+        });
+    }
+
+    let components_wrapper = gtk::Box::new(Vertical, 0);
+
+    let chooser_wrapper = gtk::Box::new(Horizontal, 2);
+    chooser_wrapper.add(&editor.color_button);
+    chooser_wrapper.add(&editor.hex_entry);
+    components_wrapper.add(&chooser_wrapper);
+
+    let mut scale_list = vec![
+        ("h", &editor.hue_scale),
+        ("s", &editor.saturation_scale),
+        ("v", &editor.value_scale),
+    ];
+    if editor.has_alpha {
+        scale_list.push(("a", &editor.alpha_scale));
+    }
+    for (scale_label, scale) in scale_list {
+        let scale_wrapper = gtk::Box::new(Horizontal, 2);
+        scale_wrapper.add(&Label::new(Some(scale_label)));
+        scale_wrapper.add(scale);
+        components_wrapper.add(&scale_wrapper);
+    }
+
+    components_wrapper
+}
@@ -0,0 +1,760 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use uuid::Uuid;
+
+use gdk::EventMask;
+use gtk::{
+    Adjustment, ContainerExt, DrawingArea, Inhibit, Orientation::Vertical, PolicyType,
+    ScrolledWindow, ScrolledWindowExt, WidgetExt,
+};
+
+use relm::{connect, Component, ContainerWidget, Relm, Update, Widget};
+use relm_derive::Msg;
+
+use wvr_data::config::project_config::{FilterConfig, ProjectConfig, SampledInput};
+
+use crate::config_panel::msg::ConfigPanelMsg;
+use crate::config_panel::validation::{get_sampled_input_name, load_filter_list};
+use crate::config_panel::view::ConfigPanel;
+
+use super::input::resample;
+
+const MARGIN: f64 = 16.0;
+const NODE_WIDTH: f64 = 160.0;
+const HEADER_HEIGHT: f64 = 24.0;
+const SLOT_HEIGHT: f64 = 20.0;
+const PORT_RADIUS: f64 = 5.0;
+const COLUMN_SPACING: f64 = 220.0;
+const ROW_SPACING: f64 = 24.0;
+
+#[derive(Clone, PartialEq)]
+enum NodeKind {
+    Input,
+    RenderStage(Uuid),
+    FinalStage,
+}
+
+struct Node {
+    kind: NodeKind,
+    name: String,
+    slot_list: Vec<String>,
+    x: f64,
+    y: f64,
+}
+
+impl Node {
+    fn get_key(&self) -> String {
+        match &self.kind {
+            NodeKind::Input => format!("input:{:}", self.name),
+            NodeKind::RenderStage(stage_id) => format!("stage:{:}", stage_id),
+            NodeKind::FinalStage => "final_stage".to_string(),
+        }
+    }
+
+    fn get_height(&self) -> f64 {
+        HEADER_HEIGHT + self.slot_list.len() as f64 * SLOT_HEIGHT + PORT_RADIUS
+    }
+
+    fn get_output_port(&self) -> Option<(f64, f64)> {
+        match self.kind {
+            NodeKind::FinalStage => None,
+            _ => Some((self.x + NODE_WIDTH, self.y + HEADER_HEIGHT / 2.0)),
+        }
+    }
+
+    fn get_slot_port(&self, slot_index: usize) -> (f64, f64) {
+        (
+            self.x,
+            self.y + HEADER_HEIGHT + (slot_index as f64 + 0.5) * SLOT_HEIGHT,
+        )
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + NODE_WIDTH && y >= self.y && y <= self.y + self.get_height()
+    }
+}
+
+struct Edge {
+    source_node: usize,
+    target_node: usize,
+    slot_index: usize,
+    /// Set when the source is rendered after the target, which then samples its previous frame.
+    feedback: bool,
+}
+
+enum Drag {
+    Node {
+        node_index: usize,
+        offset_x: f64,
+        offset_y: f64,
+    },
+    Edge {
+        source_node: usize,
+        /// Slot the edge was pulled from, disconnected unless the edge is dropped back on it.
+        detached_slot: Option<(usize, usize)>,
+        x: f64,
+        y: f64,
+    },
+}
+
+#[derive(Default)]
+struct Graph {
+    node_list: Vec<Node>,
+    edge_list: Vec<Edge>,
+    drag: Option<Drag>,
+}
+
+impl Graph {
+    fn build(
+        config: &ProjectConfig,
+        render_stage_order: &[Uuid],
+        available_filter_list: &HashMap<String, (PathBuf, FilterConfig, bool)>,
+        node_position_list: &HashMap<String, (f64, f64)>,
+    ) -> Self {
+        let mut node_list = Vec::new();
+        let mut node_depth_list = Vec::new();
+
+        let mut input_name_list: Vec<&String> = config.inputs.keys().collect();
+        input_name_list.sort();
+        for input_name in input_name_list {
+            node_list.push(Node {
+                kind: NodeKind::Input,
+                name: input_name.clone(),
+                slot_list: Vec::new(),
+                x: 0.0,
+                y: 0.0,
+            });
+            node_depth_list.push(0);
+        }
+
+        let mut node_index_list = HashMap::new();
+        for (node_index, node) in node_list.iter().enumerate() {
+            node_index_list.insert(node.name.clone(), node_index);
+        }
+
+        let stage_list = config
+            .render_chain
+            .iter()
+            .zip(render_stage_order)
+            .map(|(stage, stage_id)| (NodeKind::RenderStage(*stage_id), stage))
+            .chain(std::iter::once((NodeKind::FinalStage, &config.final_stage)));
+
+        let mut pending_edge_list = Vec::new();
+        for (kind, stage) in stage_list {
+            let mut slot_list = available_filter_list
+                .get(&stage.filter)
+                .map(|(_, filter_config, _)| filter_config.inputs.clone())
+                .unwrap_or_default();
+
+            let mut extra_slot_list: Vec<&String> = stage
+                .inputs
+                .keys()
+                .filter(|uniform_name| !slot_list.contains(uniform_name))
+                .collect();
+            extra_slot_list.sort();
+            slot_list.extend(extra_slot_list.into_iter().cloned());
+
+            let node_index = node_list.len();
+
+            // Columns follow the render order, forward references do not push a stage right
+            let mut depth = 1;
+            for (slot_index, uniform_name) in slot_list.iter().enumerate() {
+                if let Some(sampled_input) = stage.inputs.get(uniform_name) {
+                    pending_edge_list.push((
+                        get_sampled_input_name(sampled_input).to_owned(),
+                        node_index,
+                        slot_index,
+                    ));
+
+                    if let Some(source_node) =
+                        node_index_list.get(get_sampled_input_name(sampled_input))
+                    {
+                        depth = depth.max(node_depth_list[*source_node] + 1);
+                    }
+                }
+            }
+
+            if kind != NodeKind::FinalStage {
+                node_index_list
+                    .entry(stage.name.clone())
+                    .or_insert(node_index);
+            }
+
+            node_list.push(Node {
+                kind,
+                name: stage.name.clone(),
+                slot_list,
+                x: 0.0,
+                y: 0.0,
+            });
+            node_depth_list.push(depth);
+        }
+
+        // The final stage always sits in the last column
+        let max_depth = node_depth_list[..node_depth_list.len() - 1]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        *node_depth_list.last_mut().unwrap() = max_depth + 1;
+
+        let mut column_height_list = vec![MARGIN; max_depth + 2];
+        for (node, depth) in node_list.iter_mut().zip(node_depth_list) {
+            let (x, y) = (
+                MARGIN + depth as f64 * COLUMN_SPACING,
+                column_height_list[depth],
+            );
+            column_height_list[depth] += node.get_height() + ROW_SPACING;
+
+            let (x, y) = node_position_list
+                .get(&node.get_key())
+                .copied()
+                .unwrap_or((x, y));
+            node.x = x;
+            node.y = y;
+        }
+
+        let edge_list = pending_edge_list
+            .into_iter()
+            .filter_map(|(source_name, target_node, slot_index)| {
+                node_index_list.get(&source_name).map(|source_node| Edge {
+                    source_node: *source_node,
+                    target_node,
+                    slot_index,
+                    feedback: node_list[target_node].kind != NodeKind::FinalStage
+                        && *source_node >= target_node,
+                })
+            })
+            .collect();
+
+        Self {
+            node_list,
+            edge_list,
+            drag: None,
+        }
+    }
+
+    fn get_size(&self) -> (i32, i32) {
+        let (width, height) =
+            self.node_list
+                .iter()
+                .fold((0.0, 0.0), |(width, height): (f64, f64), node| {
+                    (
+                        width.max(node.x + NODE_WIDTH),
+                        height.max(node.y + node.get_height()),
+                    )
+                });
+
+        ((width + MARGIN) as i32, (height + MARGIN) as i32)
+    }
+
+    fn find_node(&self, x: f64, y: f64) -> Option<usize> {
+        self.node_list.iter().rposition(|node| node.contains(x, y))
+    }
+
+    fn find_output_port(&self, x: f64, y: f64) -> Option<usize> {
+        self.node_list.iter().rposition(|node| {
+            node.get_output_port()
+                .map(|(port_x, port_y)| (port_x - x).hypot(port_y - y) <= PORT_RADIUS * 2.0)
+                .unwrap_or(false)
+        })
+    }
+
+    // Slots can be hit from their port or from the left half of their row
+    fn find_slot(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        for (node_index, node) in self.node_list.iter().enumerate().rev() {
+            for slot_index in 0..node.slot_list.len() {
+                let (port_x, port_y) = node.get_slot_port(slot_index);
+                if x >= port_x - PORT_RADIUS * 2.0
+                    && x <= port_x + NODE_WIDTH / 2.0
+                    && (y - port_y).abs() <= SLOT_HEIGHT / 2.0
+                {
+                    return Some((node_index, slot_index));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn draw_edge(cairo_context: &cairo::Context, from: (f64, f64), to: (f64, f64)) {
+        let handle_length = ((to.0 - from.0).abs() / 2.0).max(40.0);
+
+        cairo_context.move_to(from.0, from.1);
+        cairo_context.curve_to(
+            from.0 + handle_length,
+            from.1,
+            to.0 - handle_length,
+            to.1,
+            to.0,
+            to.1,
+        );
+        cairo_context.stroke();
+    }
+
+    fn draw_label(cairo_context: &cairo::Context, text: &str, x: f64, y: f64, width: f64) {
+        cairo_context.save();
+        cairo_context.rectangle(x, y - SLOT_HEIGHT, width, SLOT_HEIGHT * 2.0);
+        cairo_context.clip();
+        cairo_context.move_to(x, y);
+        cairo_context.show_text(text);
+        cairo_context.restore();
+    }
+
+    fn draw(&self, cairo_context: &cairo::Context) {
+        cairo_context.set_font_size(12.0);
+        cairo_context.set_line_width(2.0);
+
+        for edge in &self.edge_list {
+            let source_node = &self.node_list[edge.source_node];
+            let target_node = &self.node_list[edge.target_node];
+
+            if let Some(from) = source_node.get_output_port() {
+                if edge.feedback {
+                    cairo_context.set_source_rgb(0.9, 0.6, 0.2);
+                    cairo_context.set_dash(&[6.0, 4.0], 0.0);
+                } else {
+                    cairo_context.set_source_rgb(0.6, 0.7, 0.8);
+                    cairo_context.set_dash(&[], 0.0);
+                }
+                Self::draw_edge(
+                    cairo_context,
+                    from,
+                    target_node.get_slot_port(edge.slot_index),
+                );
+            }
+        }
+        cairo_context.set_dash(&[], 0.0);
+
+        for node in &self.node_list {
+            cairo_context.set_source_rgb(0.22, 0.22, 0.24);
+            cairo_context.rectangle(node.x, node.y, NODE_WIDTH, node.get_height());
+            cairo_context.fill();
+
+            match node.kind {
+                NodeKind::Input => cairo_context.set_source_rgb(0.3, 0.5, 0.3),
+                NodeKind::RenderStage(_) => cairo_context.set_source_rgb(0.3, 0.4, 0.6),
+                NodeKind::FinalStage => cairo_context.set_source_rgb(0.6, 0.35, 0.3),
+            }
+            cairo_context.rectangle(node.x, node.y, NODE_WIDTH, HEADER_HEIGHT);
+            cairo_context.fill();
+
+            cairo_context.set_source_rgb(1.0, 1.0, 1.0);
+            let title = match node.kind {
+                NodeKind::FinalStage => "Final stage",
+                _ => &node.name,
+            };
+            Self::draw_label(
+                cairo_context,
+                title,
+                node.x + 6.0,
+                node.y + HEADER_HEIGHT - 8.0,
+                NODE_WIDTH - 12.0 - PORT_RADIUS,
+            );
+
+            for (slot_index, uniform_name) in node.slot_list.iter().enumerate() {
+                let (port_x, port_y) = node.get_slot_port(slot_index);
+
+                cairo_context.set_source_rgb(0.85, 0.85, 0.85);
+                Self::draw_label(
+                    cairo_context,
+                    uniform_name,
+                    port_x + PORT_RADIUS * 2.0,
+                    port_y + 4.0,
+                    NODE_WIDTH - PORT_RADIUS * 3.0,
+                );
+
+                cairo_context.set_source_rgb(0.6, 0.7, 0.8);
+                cairo_context.arc(port_x, port_y, PORT_RADIUS, 0.0, 2.0 * PI);
+                cairo_context.fill();
+            }
+
+            if let Some((port_x, port_y)) = node.get_output_port() {
+                cairo_context.set_source_rgb(0.6, 0.7, 0.8);
+                cairo_context.arc(port_x, port_y, PORT_RADIUS, 0.0, 2.0 * PI);
+                cairo_context.fill();
+            }
+        }
+
+        if let Some(Drag::Edge {
+            source_node, x, y, ..
+        }) = &self.drag
+        {
+            if let Some(from) = self.node_list[*source_node].get_output_port() {
+                cairo_context.set_source_rgb(1.0, 1.0, 1.0);
+                Self::draw_edge(cairo_context, from, (*x, *y));
+            }
+        }
+    }
+}
+
+/// Parts of a project drawn by the graph view, other edits leave the view untouched.
+#[derive(PartialEq)]
+pub struct GraphTopology {
+    input_name_list: Vec<String>,
+    // Name, filter and sorted inputs of each stage, the final stage last
+    stage_list: Vec<(String, String, Vec<(String, String)>)>,
+    render_stage_order: Vec<Uuid>,
+}
+
+impl GraphTopology {
+    pub fn new(config: &ProjectConfig, render_stage_order: &[Uuid]) -> Self {
+        let mut input_name_list: Vec<String> = config.inputs.keys().cloned().collect();
+        input_name_list.sort();
+
+        let stage_list = config
+            .render_chain
+            .iter()
+            .chain(std::iter::once(&config.final_stage))
+            .map(|stage| {
+                let mut stage_input_list: Vec<(String, String)> = stage
+                    .inputs
+                    .iter()
+                    .map(|(uniform_name, sampled_input)| {
+                        (uniform_name.clone(), format!("{:?}", sampled_input))
+                    })
+                    .collect();
+                stage_input_list.sort();
+
+                (stage.name.clone(), stage.filter.clone(), stage_input_list)
+            })
+            .collect();
+
+        Self {
+            input_name_list,
+            stage_list,
+            render_stage_order: render_stage_order.to_vec(),
+        }
+    }
+}
+
+#[derive(Msg)]
+pub enum RenderGraphViewMsg {
+    SetConfig(ProjectConfig, Vec<Uuid>),
+    Press(f64, f64),
+    Motion(f64, f64),
+    Release(f64, f64),
+}
+
+pub struct RenderGraphViewModel {
+    parent_relm: Relm<ConfigPanel>,
+    config: ProjectConfig,
+    render_stage_order: Vec<Uuid>,
+    available_filter_list: HashMap<String, (PathBuf, FilterConfig, bool)>,
+    /// Positions of the nodes moved by the user, by node key.
+    node_position_list: HashMap<String, (f64, f64)>,
+    graph: Rc<RefCell<Graph>>,
+}
+
+pub struct RenderGraphView {
+    model: RenderGraphViewModel,
+    root: ScrolledWindow,
+    drawing_area: DrawingArea,
+}
+
+impl RenderGraphView {
+    fn rebuild_graph(&mut self) {
+        let graph = Graph::build(
+            &self.model.config,
+            &self.model.render_stage_order,
+            &self.model.available_filter_list,
+            &self.model.node_position_list,
+        );
+
+        let (width, height) = graph.get_size();
+        self.drawing_area.set_size_request(width, height);
+
+        *self.model.graph.borrow_mut() = graph;
+        self.drawing_area.queue_draw();
+    }
+
+    fn get_stage_input(&self, node_kind: &NodeKind, uniform_name: &str) -> Option<&SampledInput> {
+        match node_kind {
+            NodeKind::Input => None,
+            NodeKind::RenderStage(stage_id) => self
+                .model
+                .render_stage_order
+                .iter()
+                .position(|candidate| candidate == stage_id)
+                .and_then(|stage_index| self.model.config.render_chain.get(stage_index))
+                .and_then(|stage| stage.inputs.get(uniform_name)),
+            NodeKind::FinalStage => self.model.config.final_stage.inputs.get(uniform_name),
+        }
+    }
+
+    fn connect_slot(&self, node_kind: &NodeKind, uniform_name: &str, source_name: &str) {
+        let sampled_input = resample(self.get_stage_input(node_kind, uniform_name), source_name);
+
+        match node_kind {
+            NodeKind::Input => (),
+            NodeKind::RenderStage(stage_id) => {
+                self.model
+                    .parent_relm
+                    .stream()
+                    .emit(ConfigPanelMsg::UpdateRenderStageInput(
+                        *stage_id,
+                        uniform_name.to_owned(),
+                        sampled_input,
+                    ))
+            }
+            // The final stage only samples a single texture, which cannot be unset
+            NodeKind::FinalStage => {
+                if uniform_name == "iChannel0" && !source_name.is_empty() {
+                    self.model
+                        .parent_relm
+                        .stream()
+                        .emit(ConfigPanelMsg::UpdateRenderedTextureName(sampled_input))
+                }
+            }
+        }
+    }
+
+    fn release(&mut self, x: f64, y: f64) {
+        let drag = self.model.graph.borrow_mut().drag.take();
+
+        match drag {
+            Some(Drag::Node {
+                node_index,
+                offset_x,
+                offset_y,
+            }) => {
+                let node_key = self.model.graph.borrow().node_list[node_index].get_key();
+                self.model
+                    .node_position_list
+                    .insert(node_key, ((x - offset_x).max(0.0), (y - offset_y).max(0.0)));
+            }
+            Some(Drag::Edge {
+                source_node,
+                detached_slot,
+                ..
+            }) => {
+                let target_slot = self.model.graph.borrow().find_slot(x, y);
+                if target_slot == detached_slot {
+                    self.drawing_area.queue_draw();
+                    return;
+                }
+
+                let (source_name, detached_slot, target_slot) = {
+                    let graph = self.model.graph.borrow();
+                    let get_slot = |(node_index, slot_index): (usize, usize)| {
+                        let node = &graph.node_list[node_index];
+                        (node.kind.clone(), node.slot_list[slot_index].clone())
+                    };
+
+                    (
+                        graph.node_list[source_node].name.clone(),
+                        detached_slot.map(get_slot),
+                        target_slot.map(get_slot),
+                    )
+                };
+
+                // Unconnected slots sample an empty name, like unset choosers of the stage view
+                if let Some((node_kind, uniform_name)) = detached_slot {
+                    self.connect_slot(&node_kind, &uniform_name, "");
+                }
+                if let Some((node_kind, uniform_name)) = target_slot {
+                    self.connect_slot(&node_kind, &uniform_name, &source_name);
+                }
+            }
+            None => (),
+        }
+
+        self.rebuild_graph();
+    }
+}
+
+impl Update for RenderGraphView {
+    type Model = RenderGraphViewModel;
+    type ModelParam = (Relm<ConfigPanel>, PathBuf, ProjectConfig, Vec<Uuid>);
+    type Msg = RenderGraphViewMsg;
+
+    fn model(
+        _: &Relm<Self>,
+        (parent_relm, project_path, config, render_stage_order): Self::ModelParam,
+    ) -> Self::Model {
+        RenderGraphViewModel {
+            parent_relm,
+            available_filter_list: load_filter_list(&project_path),
+            config,
+            render_stage_order,
+            node_position_list: HashMap::new(),
+            graph: Rc::new(RefCell::new(Graph::default())),
+        }
+    }
+
+    fn update(&mut self, event: RenderGraphViewMsg) {
+        match event {
+            RenderGraphViewMsg::SetConfig(config, render_stage_order) => {
+                self.model.config = config;
+                self.model.render_stage_order = render_stage_order;
+
+                // Rebuilding while dragging would invalidate the dragged node index
+                if self.model.graph.borrow().drag.is_none() {
+                    self.rebuild_graph();
+                }
+            }
+            RenderGraphViewMsg::Press(x, y) => {
+                let mut graph = self.model.graph.borrow_mut();
+
+                let drag = if let Some(source_node) = graph.find_output_port(x, y) {
+                    Some(Drag::Edge {
+                        source_node,
+                        detached_slot: None,
+                        x,
+                        y,
+                    })
+                } else if let Some((node_index, slot_index)) = graph.find_slot(x, y) {
+                    graph
+                        .edge_list
+                        .iter()
+                        .find(|edge| {
+                            edge.target_node == node_index && edge.slot_index == slot_index
+                        })
+                        .map(|edge| Drag::Edge {
+                            source_node: edge.source_node,
+                            detached_slot: Some((node_index, slot_index)),
+                            x,
+                            y,
+                        })
+                } else if let Some(node_index) = graph.find_node(x, y) {
+                    let node = &graph.node_list[node_index];
+                    Some(Drag::Node {
+                        node_index,
+                        offset_x: x - node.x,
+                        offset_y: y - node.y,
+                    })
+                } else {
+                    None
+                };
+                graph.drag = drag;
+            }
+            RenderGraphViewMsg::Motion(new_x, new_y) => {
+                let mut graph = self.model.graph.borrow_mut();
+                let graph = &mut *graph;
+                match &mut graph.drag {
+                    Some(Drag::Node {
+                        node_index,
+                        offset_x,
+                        offset_y,
+                    }) => {
+                        let (node_index, x, y) = (
+                            *node_index,
+                            (new_x - *offset_x).max(0.0),
+                            (new_y - *offset_y).max(0.0),
+                        );
+                        graph.node_list[node_index].x = x;
+                        graph.node_list[node_index].y = y;
+                    }
+                    Some(Drag::Edge { x, y, .. }) => {
+                        *x = new_x;
+                        *y = new_y;
+                    }
+                    None => return,
+                }
+                self.drawing_area.queue_draw();
+            }
+            RenderGraphViewMsg::Release(x, y) => self.release(x, y),
+        }
+    }
+}
+
+impl Widget for RenderGraphView {
+    type Root = ScrolledWindow;
+
+    fn root(&self) -> Self::Root {
+        self.root.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
+        let root = ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
+        root.set_policy(PolicyType::Automatic, PolicyType::Automatic);
+        root.set_hexpand(true);
+        root.set_vexpand(true);
+
+        let drawing_area = DrawingArea::new();
+        drawing_area.add_events(
+            EventMask::BUTTON_PRESS_MASK
+                | EventMask::BUTTON_RELEASE_MASK
+                | EventMask::BUTTON1_MOTION_MASK,
+        );
+
+        {
+            let graph = model.graph.clone();
+            drawing_area.connect_draw(move |_, cairo_context| {
+                graph.borrow().draw(cairo_context);
+                Inhibit(false)
+            });
+        }
+
+        connect!(
+            relm,
+            drawing_area,
+            connect_button_press_event(_, event),
+            return (
+                {
+                    let (x, y) = event.get_position();
+                    Some(RenderGraphViewMsg::Press(x, y))
+                },
+                Inhibit(false)
+            )
+        );
+        connect!(
+            relm,
+            drawing_area,
+            connect_motion_notify_event(_, event),
+            return (
+                {
+                    let (x, y) = event.get_position();
+                    Some(RenderGraphViewMsg::Motion(x, y))
+                },
+                Inhibit(false)
+            )
+        );
+        connect!(
+            relm,
+            drawing_area,
+            connect_button_release_event(_, event),
+            return (
+                {
+                    let (x, y) = event.get_position();
+                    Some(RenderGraphViewMsg::Release(x, y))
+                },
+                Inhibit(false)
+            )
+        );
+
+        root.add(&drawing_area);
+        root.show_all();
+
+        let mut render_graph_view = Self {
+            model,
+            root,
+            drawing_area,
+        };
+        render_graph_view.rebuild_graph();
+
+        render_graph_view
+    }
+}
+
+/// Wraps a graph view of the render chain, to be shown next to the stage tabs.
+pub fn build_graph_view(
+    relm: &Relm<ConfigPanel>,
+    project_path: &Path,
+    config: &ProjectConfig,
+    render_stage_order: &[Uuid],
+) -> (gtk::Box, Component<RenderGraphView>) {
+    let wrapper = gtk::Box::new(Vertical, 0);
+    let render_graph_view = wrapper.add_widget::<RenderGraphView>((
+        relm.clone(),
+        project_path.to_owned(),
+        config.clone(),
+        render_stage_order.to_vec(),
+    ));
+
+    (wrapper, render_graph_view)
+}
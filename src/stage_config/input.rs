@@ -8,6 +8,16 @@ use wvr_data::config::project_config::SampledInput;
 
 use super::view::{RenderStageConfigView, RenderStageConfigViewMsg};
 
+/// Points `sampled_input` at another source, keeping its sampling method.
+pub fn resample(sampled_input: Option<&SampledInput>, input_name: &str) -> SampledInput {
+    let input_name = input_name.to_owned();
+    match sampled_input {
+        Some(SampledInput::Nearest(_)) => SampledInput::Nearest(input_name),
+        Some(SampledInput::Mipmaps(_)) => SampledInput::Mipmaps(input_name),
+        Some(SampledInput::Linear(_)) | None => SampledInput::Linear(input_name),
+    }
+}
+
 pub fn build_input_row(
     relm: &Relm<RenderStageConfigView>,
     input_choice_list: &[String],
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
@@ -7,8 +7,9 @@ use uuid::Uuid;
 
 use gtk::{prelude::NotebookExtManual, NotebookExt};
 use gtk::{
-    Button, ButtonExt, ContainerExt, Label, LabelExt, Notebook, PackType, TreeIter, TreeModel,
-    TreeModelExt, WidgetExt,
+    Button, ButtonExt, ContainerExt, GtkMenuItemExt, Label, LabelExt, Menu, MenuButton,
+    MenuButtonExt, MenuItem, MenuShellExt, Notebook, PackType, SeparatorMenuItem, ToggleButton,
+    ToggleButtonExt, TreeIter, TreeModel, TreeModelExt, WidgetExt,
 };
 use gtk::{Orientation::Horizontal, ReliefStyle};
 
@@ -20,12 +21,21 @@ use wvr_data::config::project_config::{
 };
 
 pub mod automation;
+pub mod clipboard;
+pub mod color;
+pub mod graph_view;
 pub mod input;
+pub mod preset;
 pub mod variable;
 pub mod view;
+pub mod xy_pad;
 
 use crate::config_panel::msg::ConfigPanelMsg;
 use crate::config_panel::view::ConfigPanel;
+use crate::editor_state::EditorState;
+use crate::wvr_frame::preview::{
+    SharedStagePreviewList, StagePreviewList, TAB_PREVIEW_HEIGHT, TAB_PREVIEW_WIDTH,
+};
 use view::RenderStageConfigView;
 
 pub fn build_list_view(
@@ -37,6 +47,10 @@ pub fn build_list_view(
         Uuid,
         (Component<RenderStageConfigView>, gtk::Box),
     >,
+    stage_tab_label_list: &mut HashMap<Uuid, StageTabLabel>,
+    stage_preview_list: &SharedStagePreviewList,
+    editor_state: &EditorState,
+    bpm: f64,
 ) -> (Notebook, Vec<Uuid>) {
     let mut render_stage_order = Vec::new();
 
@@ -70,9 +84,25 @@ pub fn build_list_view(
         Some(ConfigPanelMsg::AddRenderStage(render_stage_config))
     });
 
-    add_render_stage_button.show_all();
+    let sort_render_stage_button = Button::new();
+    sort_render_stage_button.set_label(emoji::symbols::arrow::UP_DOWN_ARROW);
+    sort_render_stage_button.set_tooltip_text(Some(
+        "Auto-order: render each stage after the stages it samples",
+    ));
+    sort_render_stage_button.set_property_margin(4);
+    connect!(
+        relm,
+        sort_render_stage_button,
+        connect_clicked(_),
+        ConfigPanelMsg::SortStages
+    );
+
+    let action_container = gtk::Box::new(Horizontal, 0);
+    action_container.add(&sort_render_stage_button);
+    action_container.add(&add_render_stage_button);
+    action_container.show_all();
 
-    render_stage_list_container.set_action_widget(&add_render_stage_button, PackType::End);
+    render_stage_list_container.set_action_widget(&action_container, PackType::End);
 
     let mut available_filter_list =
         load_available_filter_list(&wvr_data::get_filters_path(), true).unwrap();
@@ -82,33 +112,18 @@ pub fn build_list_view(
     for render_stage_config in render_stage_config_list {
         let (id, wrapper, render_stage_config_view) = build_render_stage_config_row(
             relm,
+            project_path,
             &render_stage_config,
             &input_choice_list,
             &available_filter_list,
+            stage_preview_list,
+            editor_state.get_color_editor_list(&render_stage_config.name),
+            bpm,
         );
-        let page_label_container = gtk::Box::new(Horizontal, 4);
-        page_label_container.set_property_margin(0);
-
-        let page_label = Label::new(Some(&render_stage_config.name));
-        page_label.set_xalign(0.0);
-        page_label.set_hexpand(true);
-
-        let remove_button = Button::new();
-        remove_button.set_label("x");
-        remove_button.set_relief(ReliefStyle::None);
-        {
-            connect!(
-                relm,
-                remove_button,
-                connect_clicked(_),
-                Some(ConfigPanelMsg::RemoveRenderStage(id))
-            );
-        }
+        let stage_tab_label =
+            build_page_label(relm, id, &render_stage_config.name, stage_preview_list);
 
-        page_label_container.add(&remove_button);
-        page_label_container.add(&page_label);
-
-        render_stage_list_container.append_page(&wrapper, Some(&page_label_container));
+        render_stage_list_container.append_page(&wrapper, Some(&stage_tab_label.container));
         render_stage_list_container.set_tab_reorderable(&wrapper, true);
         {
             let wrapper = wrapper.clone();
@@ -125,20 +140,158 @@ pub fn build_list_view(
                 }
             );
         }
-        page_label_container.show_all();
 
         render_stage_config_widget_list.insert(id, (render_stage_config_view, wrapper));
+        stage_tab_label_list.insert(id, stage_tab_label);
         render_stage_order.push(id);
     }
 
     (render_stage_list_container, render_stage_order)
 }
 
+/// Widgets of a stage tab label updated by the config panel.
+pub struct StageTabLabel {
+    pub container: gtk::Box,
+    pub name_label: Label,
+    pub dependency_marker: Label,
+    pub bypass_button: ToggleButton,
+    pub solo_button: ToggleButton,
+}
+
+/// Builds a stage tab label, holding the remove button, the stage name, the dependency marker,
+/// the bypass and solo toggles, the stage menu and the stage preview, in that order.
+pub fn build_page_label(
+    relm: &Relm<ConfigPanel>,
+    id: Uuid,
+    name: &str,
+    stage_preview_list: &SharedStagePreviewList,
+) -> StageTabLabel {
+    let page_label_container = gtk::Box::new(Horizontal, 4);
+    page_label_container.set_property_margin(0);
+
+    let page_label = Label::new(Some(name));
+    page_label.set_xalign(0.0);
+    page_label.set_hexpand(true);
+
+    let remove_button = Button::new();
+    remove_button.set_label("x");
+    remove_button.set_relief(ReliefStyle::None);
+    {
+        connect!(
+            relm,
+            remove_button,
+            connect_clicked(_),
+            Some(ConfigPanelMsg::RemoveRenderStage(id))
+        );
+    }
+
+    // Shown by the config panel once the render chain is analyzed
+    let dependency_marker = Label::new(None);
+    dependency_marker.set_no_show_all(true);
+
+    let bypass_button = ToggleButton::with_label("B");
+    bypass_button.set_relief(ReliefStyle::None);
+    bypass_button.set_tooltip_text(Some("Bypass: pass the first input through"));
+    connect!(
+        relm,
+        bypass_button,
+        connect_toggled(bypass_button),
+        ConfigPanelMsg::SetRenderStageBypass(id, bypass_button.get_active())
+    );
+
+    let solo_button = ToggleButton::with_label("S");
+    solo_button.set_relief(ReliefStyle::None);
+    solo_button.set_tooltip_text(Some(
+        "Solo: show this stage instead of the final stage input",
+    ));
+    connect!(
+        relm,
+        solo_button,
+        connect_toggled(solo_button),
+        ConfigPanelMsg::SetRenderStageSolo(id, solo_button.get_active())
+    );
+
+    let stage_menu = Menu::new();
+
+    let duplicate_menu_item = MenuItem::with_label("Duplicate");
+    connect!(
+        relm,
+        duplicate_menu_item,
+        connect_activate(_),
+        ConfigPanelMsg::DuplicateRenderStage(Some(id))
+    );
+
+    let copy_menu_item = MenuItem::with_label("Copy");
+    connect!(
+        relm,
+        copy_menu_item,
+        connect_activate(_),
+        ConfigPanelMsg::CopyRenderStage(Some(id))
+    );
+
+    let paste_menu_item = MenuItem::with_label("Paste");
+    connect!(
+        relm,
+        paste_menu_item,
+        connect_activate(_),
+        ConfigPanelMsg::PasteRenderStage
+    );
+
+    let reset_variables_menu_item = MenuItem::with_label("Reset all variables");
+    connect!(
+        relm,
+        reset_variables_menu_item,
+        connect_activate(_),
+        ConfigPanelMsg::ResetRenderStageVariables(id)
+    );
+
+    stage_menu.append(&duplicate_menu_item);
+    stage_menu.append(&copy_menu_item);
+    stage_menu.append(&paste_menu_item);
+    stage_menu.append(&SeparatorMenuItem::new());
+    stage_menu.append(&reset_variables_menu_item);
+    stage_menu.show_all();
+
+    let stage_menu_button = MenuButton::new();
+    stage_menu_button.set_relief(ReliefStyle::None);
+    stage_menu_button.set_popup(Some(&stage_menu));
+
+    let preview_image =
+        StagePreviewList::build_preview_image(TAB_PREVIEW_WIDTH, TAB_PREVIEW_HEIGHT);
+    stage_preview_list.borrow_mut().add_image(
+        id,
+        &preview_image,
+        TAB_PREVIEW_WIDTH,
+        TAB_PREVIEW_HEIGHT,
+    );
+
+    page_label_container.add(&remove_button);
+    page_label_container.add(&page_label);
+    page_label_container.add(&dependency_marker);
+    page_label_container.add(&bypass_button);
+    page_label_container.add(&solo_button);
+    page_label_container.add(&stage_menu_button);
+    page_label_container.add(&preview_image);
+    page_label_container.show_all();
+
+    StageTabLabel {
+        container: page_label_container,
+        name_label: page_label,
+        dependency_marker,
+        bypass_button,
+        solo_button,
+    }
+}
+
 pub fn build_render_stage_config_row(
     relm: &Relm<ConfigPanel>,
+    project_path: &Path,
     render_stage_config: &RenderStageConfig,
     input_choice_list: &[String],
     available_filter_list: &HashMap<String, (PathBuf, FilterConfig, bool)>,
+    stage_preview_list: &SharedStagePreviewList,
+    color_editor_list: BTreeMap<String, bool>,
+    bpm: f64,
 ) -> (Uuid, gtk::Box, Component<RenderStageConfigView>) {
     let id = Uuid::new_v4();
     let wrapper = gtk::Box::new(Horizontal, 2);
@@ -149,6 +302,10 @@ pub fn build_render_stage_config_row(
         input_choice_list.to_vec(),
         available_filter_list.clone(),
         relm.clone(),
+        stage_preview_list.clone(),
+        color_editor_list,
+        bpm,
+        project_path.to_owned(),
     ));
 
     (id, wrapper, render_stage_config_view)
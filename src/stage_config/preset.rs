@@ -0,0 +1,254 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use wvr_data::config::project_config::Automation;
+use wvr_data::DataHolder;
+
+/// File of the project holding its presets, next to its config.
+pub const PROJECT_PRESET_FILE_NAME: &str = "presets.json";
+
+/// Snapshot of the variables of a stage, values along with their automation.
+pub type VariablePreset = BTreeMap<String, (DataHolder, Automation)>;
+
+// Presets by name
+type PresetList = BTreeMap<String, VariablePreset>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PresetScope {
+    /// Stored in the project, for a single stage of this project.
+    Project,
+    /// Stored in the data folder, for every project using the filter.
+    Library,
+}
+
+impl PresetScope {
+    pub const ALL: [PresetScope; 2] = [PresetScope::Project, PresetScope::Library];
+
+    pub fn get_id(&self) -> &'static str {
+        match self {
+            PresetScope::Project => "project",
+            PresetScope::Library => "library",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|scope| scope.get_id() == id)
+    }
+}
+
+/// Id of a preset in the preset chooser of a stage.
+pub fn get_preset_id(scope: PresetScope, preset_name: &str) -> String {
+    format!("{:}:{:}", scope.get_id(), preset_name)
+}
+
+pub fn parse_preset_id(preset_id: &str) -> Option<(PresetScope, &str)> {
+    let mut id_part_list = preset_id.splitn(2, ':');
+    let scope = PresetScope::from_id(id_part_list.next()?)?;
+
+    Some((scope, id_part_list.next()?))
+}
+
+pub fn get_library_path() -> PathBuf {
+    wvr_data::get_data_path().join("presets")
+}
+
+// Filter names such as `generic/copy` map to sub folders of the library
+fn get_library_file_path(filter_name: &str) -> PathBuf {
+    get_library_path().join(format!("{:}.json", filter_name))
+}
+
+fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    if !path.exists() {
+        return T::default();
+    }
+
+    match File::open(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(serde_json::from_reader::<File, T>(file)?))
+    {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to load presets {:?}: {:?}", path, e);
+            T::default()
+        }
+    }
+}
+
+fn save_json<T: Serialize>(path: &Path, content: &T) -> Result<()> {
+    if let Some(parent_path) = path.parent() {
+        std::fs::create_dir_all(parent_path)?;
+    }
+
+    let content_as_bytes = serde_json::ser::to_string_pretty(content)?.into_bytes();
+
+    let mut file = File::create(path)?;
+    file.write_all(&content_as_bytes)?;
+
+    Ok(())
+}
+
+// Project presets of every stage share a single file, by stage name
+fn load_project_preset_list(project_path: &Path) -> BTreeMap<String, PresetList> {
+    load_json(&project_path.join(PROJECT_PRESET_FILE_NAME))
+}
+
+fn save_project_preset_list(
+    project_path: &Path,
+    project_preset_list: &BTreeMap<String, PresetList>,
+) -> Result<()> {
+    let preset_file_path = project_path.join(PROJECT_PRESET_FILE_NAME);
+
+    // A project without presets leaves no file behind
+    if project_preset_list.is_empty() {
+        if preset_file_path.exists() {
+            std::fs::remove_file(&preset_file_path)?;
+        }
+        Ok(())
+    } else {
+        save_json(&preset_file_path, project_preset_list)
+    }
+}
+
+fn load_scope_preset_list(
+    project_path: &Path,
+    stage_name: &str,
+    filter_name: &str,
+    scope: PresetScope,
+) -> PresetList {
+    match scope {
+        PresetScope::Project => load_project_preset_list(project_path)
+            .remove(stage_name)
+            .unwrap_or_default(),
+        PresetScope::Library => load_json(&get_library_file_path(filter_name)),
+    }
+}
+
+fn save_scope_preset_list(
+    project_path: &Path,
+    stage_name: &str,
+    filter_name: &str,
+    scope: PresetScope,
+    preset_list: PresetList,
+) -> Result<()> {
+    match scope {
+        PresetScope::Project => {
+            let mut project_preset_list = load_project_preset_list(project_path);
+            if preset_list.is_empty() {
+                project_preset_list.remove(stage_name);
+            } else {
+                project_preset_list.insert(stage_name.to_owned(), preset_list);
+            }
+
+            save_project_preset_list(project_path, &project_preset_list)
+        }
+        PresetScope::Library => {
+            let preset_file_path = get_library_file_path(filter_name);
+
+            if preset_list.is_empty() {
+                if preset_file_path.exists() {
+                    std::fs::remove_file(&preset_file_path)?;
+                }
+                Ok(())
+            } else {
+                save_json(&preset_file_path, &preset_list)
+            }
+        }
+    }
+}
+
+/// Presets of a stage and of its filter by id, project ones first.
+pub fn load_preset_list(
+    project_path: &Path,
+    stage_name: &str,
+    filter_name: &str,
+) -> Vec<(String, PresetScope, String, VariablePreset)> {
+    let mut preset_list = Vec::new();
+
+    for scope in PresetScope::ALL.iter().copied() {
+        for (preset_name, preset) in
+            load_scope_preset_list(project_path, stage_name, filter_name, scope)
+        {
+            preset_list.push((
+                get_preset_id(scope, &preset_name),
+                scope,
+                preset_name,
+                preset,
+            ));
+        }
+    }
+
+    preset_list
+}
+
+/// Stores the variables of a stage as a preset, replacing any preset of the same name.
+pub fn save_preset(
+    project_path: &Path,
+    stage_name: &str,
+    filter_name: &str,
+    scope: PresetScope,
+    preset_name: &str,
+    variables: &HashMap<String, (DataHolder, Automation)>,
+) -> Result<String> {
+    let preset_name = preset_name.trim();
+    if preset_name.is_empty() {
+        bail!("A preset needs a name");
+    }
+
+    let mut preset_list = load_scope_preset_list(project_path, stage_name, filter_name, scope);
+    preset_list.insert(
+        preset_name.to_owned(),
+        variables
+            .iter()
+            .map(|(variable_name, variable)| (variable_name.clone(), variable.clone()))
+            .collect(),
+    );
+    save_scope_preset_list(project_path, stage_name, filter_name, scope, preset_list)?;
+
+    Ok(get_preset_id(scope, preset_name))
+}
+
+pub fn delete_preset(
+    project_path: &Path,
+    stage_name: &str,
+    filter_name: &str,
+    preset_id: &str,
+) -> Result<()> {
+    let (scope, preset_name) = match parse_preset_id(preset_id) {
+        Some(preset_id) => preset_id,
+        None => bail!("Invalid preset id {:}", preset_id),
+    };
+
+    let mut preset_list = load_scope_preset_list(project_path, stage_name, filter_name, scope);
+    if preset_list.remove(preset_name).is_none() {
+        bail!("No preset named {:}", preset_name);
+    }
+
+    save_scope_preset_list(project_path, stage_name, filter_name, scope, preset_list)
+}
+
+/// Moves the project presets of a renamed stage to its new name.
+pub fn rename_stage_presets(project_path: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    let mut project_preset_list = load_project_preset_list(project_path);
+    if let Some(preset_list) = project_preset_list.remove(old_name) {
+        project_preset_list.insert(new_name.to_owned(), preset_list);
+        save_project_preset_list(project_path, &project_preset_list)?;
+    }
+
+    Ok(())
+}
+
+pub fn remove_stage_presets(project_path: &Path, stage_name: &str) -> Result<()> {
+    let mut project_preset_list = load_project_preset_list(project_path);
+    if project_preset_list.remove(stage_name).is_some() {
+        save_project_preset_list(project_path, &project_preset_list)?;
+    }
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+
 use gtk::{
-    AdjustmentExt, ContainerExt, Label, LabelExt, Orientation, PositionType, RangeExt, Scale,
-    ScaleExt, Switch, SwitchExt, WidgetExt,
+    AdjustmentExt, ButtonExt, ContainerExt, GtkMenuItemExt, IconSize, Image, Label, LabelExt, Menu,
+    MenuButton, MenuButtonExt, MenuItem, MenuShellExt, Orientation, PositionType, RangeExt,
+    ReliefStyle, Scale, ScaleExt, StyleContextExt, Switch, SwitchExt, ToggleButton,
+    ToggleButtonExt, WidgetExt,
 };
 use gtk::{
     BoxExt,
@@ -11,7 +17,10 @@ use relm::{connect, Relm};
 
 use wvr_data::{DataHolder, DataRange};
 
+use super::automation::SharedAutomationPreview;
+use super::color;
 use super::view::{RenderStageConfigView, RenderStageConfigViewMsg};
+use super::xy_pad;
 
 pub fn create_int_spinner(
     relm: &Relm<RenderStageConfigView>,
@@ -80,80 +89,6 @@ pub fn create_float_spinner(
     wrapper
 }
 
-pub fn create_float2_spinner(
-    relm: &Relm<RenderStageConfigView>,
-    name: &str,
-    x: f64,
-    y: f64,
-    value_range: &DataRange,
-) -> gtk::Box {
-    let (min_value, max_value, step) =
-        if let DataRange::FloatRange(min_value, max_value, step) = value_range {
-            (*min_value, *max_value, *step)
-        } else {
-            (-1.0, 1.0, 0.0001)
-        };
-
-    let components_wrapper = gtk::Box::new(Vertical, 0);
-
-    let x_wrapper = gtk::Box::new(Horizontal, 2);
-
-    let x_label = Label::new(Some("x"));
-    x_label.set_text("x");
-    let x_spinner = Scale::with_range(Orientation::Horizontal, min_value, max_value, step);
-    x_spinner.set_has_origin(false);
-    x_spinner.set_value(x as f64);
-    x_spinner.set_hexpand(true);
-
-    x_spinner.set_value_pos(PositionType::Right);
-
-    x_wrapper.add(&x_label);
-    x_wrapper.add(&x_spinner);
-
-    let y_wrapper = gtk::Box::new(Horizontal, 2);
-
-    let y_label = Label::new(Some("y"));
-    let y_spinner = Scale::with_range(Orientation::Horizontal, min_value, max_value, step);
-    y_spinner.set_has_origin(false);
-    y_spinner.set_value(y as f64);
-    y_spinner.set_hexpand(true);
-
-    y_spinner.set_value_pos(PositionType::Right);
-
-    y_wrapper.add(&y_label);
-    y_wrapper.add(&y_spinner);
-
-    components_wrapper.add(&x_wrapper);
-    components_wrapper.add(&y_wrapper);
-
-    let name = name.to_string();
-    {
-        let name = name.clone();
-        let x_spinner = x_spinner.clone();
-        let y_spinner = y_spinner.clone();
-        connect!(relm, x_spinner, connect_value_changed(val), {
-            Some(RenderStageConfigViewMsg::UpdateVariable(
-                name.clone(),
-                DataHolder::Float2([val.get_value() as f32, y_spinner.get_value() as f32]),
-            ))
-        });
-    }
-
-    {
-        let name = name;
-        let x_spinner = x_spinner;
-        let y_spinner = y_spinner;
-        connect!(relm, y_spinner, connect_value_changed(val), {
-            Some(RenderStageConfigViewMsg::UpdateVariable(
-                name.clone(),
-                DataHolder::Float2([x_spinner.get_value() as f32, val.get_value() as f32]),
-            ))
-        });
-    }
-
-    components_wrapper
-}
-
 pub fn create_float3_spinner(
     relm: &Relm<RenderStageConfigView>,
     name: &str,
@@ -423,11 +358,308 @@ pub fn create_float4_spinner(
     components_wrapper
 }
 
+const COMPONENT_LABEL_LIST: [&str; 4] = ["x", "y", "z", "w"];
+
+/// Writes a variable value the way it is typed in, components being separated by commas.
+pub fn format_variable_value(value: &DataHolder) -> Option<String> {
+    let component_list: Vec<String> = match value {
+        DataHolder::Bool(value) => vec![value.to_string()],
+        DataHolder::Int(value) => vec![value.to_string()],
+        DataHolder::Int2(value) => value.iter().map(ToString::to_string).collect(),
+        DataHolder::Int3(value) => value.iter().map(ToString::to_string).collect(),
+        DataHolder::Int4(value) => value.iter().map(ToString::to_string).collect(),
+        DataHolder::Float(value) => vec![value.to_string()],
+        DataHolder::Float2(value) => value.iter().map(ToString::to_string).collect(),
+        DataHolder::Float3(value) => value.iter().map(ToString::to_string).collect(),
+        DataHolder::Float4(value) => value.iter().map(ToString::to_string).collect(),
+        _ => return None,
+    };
+
+    Some(component_list.join(", "))
+}
+
+fn parse_component_list<T: FromStr>(text: &str, component_count: usize) -> Result<Vec<T>> {
+    let component_list: Vec<&str> = text
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    if component_list.len() != component_count {
+        bail!(
+            "Expected {:} value(s) separated by commas, got {:}",
+            component_count,
+            component_list.len()
+        );
+    }
+
+    component_list
+        .iter()
+        .map(|component| {
+            component
+                .parse::<T>()
+                .map_err(|_| anyhow!("\"{:}\" is not a valid value", component))
+        })
+        .collect()
+}
+
+/// Parses a value typed for a variable of the same kind as `template`.
+pub fn parse_variable_value(text: &str, template: &DataHolder) -> Result<DataHolder> {
+    Ok(match template {
+        DataHolder::Bool(_) => match text.trim().to_lowercase().as_str() {
+            "true" | "on" | "1" => DataHolder::Bool(true),
+            "false" | "off" | "0" => DataHolder::Bool(false),
+            _ => bail!("Expected true or false"),
+        },
+        DataHolder::Int(_) => DataHolder::Int(parse_component_list(text, 1)?[0]),
+        DataHolder::Int2(_) => build_int_vector(&parse_component_list(text, 2)?),
+        DataHolder::Int3(_) => build_int_vector(&parse_component_list(text, 3)?),
+        DataHolder::Int4(_) => build_int_vector(&parse_component_list(text, 4)?),
+        DataHolder::Float(_) => DataHolder::Float(parse_component_list(text, 1)?[0]),
+        DataHolder::Float2(_) => {
+            let value = parse_component_list(text, 2)?;
+            DataHolder::Float2([value[0], value[1]])
+        }
+        DataHolder::Float3(_) => {
+            let value = parse_component_list(text, 3)?;
+            DataHolder::Float3([value[0], value[1], value[2]])
+        }
+        DataHolder::Float4(_) => {
+            let value = parse_component_list(text, 4)?;
+            DataHolder::Float4([value[0], value[1], value[2], value[3]])
+        }
+        _ => bail!("This variable type can not be edited"),
+    })
+}
+
+/// Keeps every component of a typed or pasted value within the range the filter declares, NaN
+/// components going to the lower bound.
+pub fn clamp_variable_value(value: &DataHolder, value_range: &DataRange) -> DataHolder {
+    match value_range {
+        DataRange::FloatRange(min_value, max_value, _) => {
+            let clamp = |component: f32| (component as f64).max(*min_value).min(*max_value) as f32;
+
+            match value {
+                DataHolder::Float(value) => DataHolder::Float(clamp(*value)),
+                DataHolder::Float2(value) => DataHolder::Float2([clamp(value[0]), clamp(value[1])]),
+                DataHolder::Float3(value) => {
+                    DataHolder::Float3([clamp(value[0]), clamp(value[1]), clamp(value[2])])
+                }
+                DataHolder::Float4(value) => DataHolder::Float4([
+                    clamp(value[0]),
+                    clamp(value[1]),
+                    clamp(value[2]),
+                    clamp(value[3]),
+                ]),
+                _ => value.clone(),
+            }
+        }
+        DataRange::IntRange(min_value, max_value, _) => {
+            let clamp = |component: i32| {
+                (component as f64)
+                    .max(*min_value as f64)
+                    .min(*max_value as f64) as i32
+            };
+
+            match value {
+                DataHolder::Int(value) => DataHolder::Int(clamp(*value)),
+                DataHolder::Int2(value) => DataHolder::Int2([clamp(value[0]), clamp(value[1])]),
+                DataHolder::Int3(value) => {
+                    DataHolder::Int3([clamp(value[0]), clamp(value[1]), clamp(value[2])])
+                }
+                DataHolder::Int4(value) => DataHolder::Int4([
+                    clamp(value[0]),
+                    clamp(value[1]),
+                    clamp(value[2]),
+                    clamp(value[3]),
+                ]),
+                _ => value.clone(),
+            }
+        }
+        _ => value.clone(),
+    }
+}
+
+/// Builds the name of a variable followed by the menu of its value actions.
+pub fn build_variable_label(
+    relm: &Relm<RenderStageConfigView>,
+    variable_name: &str,
+) -> (gtk::Box, MenuButton) {
+    let variable_name_label = Label::new(Some(variable_name));
+    variable_name_label.set_xalign(0.0);
+    variable_name_label.set_hexpand(true);
+
+    let variable_menu = Menu::new();
+
+    let reset_menu_item = MenuItem::with_label("Reset to default");
+    let copy_menu_item = MenuItem::with_label("Copy value");
+    let paste_menu_item = MenuItem::with_label("Paste value");
+    let exact_value_menu_item = MenuItem::with_label("Enter exact value…");
+
+    let variable_name = variable_name.to_string();
+    {
+        let variable_name = variable_name.clone();
+        connect!(
+            relm,
+            reset_menu_item,
+            connect_activate(_),
+            RenderStageConfigViewMsg::ResetVariable(variable_name.clone())
+        );
+    }
+    {
+        let variable_name = variable_name.clone();
+        connect!(
+            relm,
+            copy_menu_item,
+            connect_activate(_),
+            RenderStageConfigViewMsg::CopyVariable(variable_name.clone())
+        );
+    }
+    {
+        let variable_name = variable_name.clone();
+        connect!(
+            relm,
+            paste_menu_item,
+            connect_activate(_),
+            RenderStageConfigViewMsg::PasteVariable(variable_name.clone())
+        );
+    }
+    connect!(
+        relm,
+        exact_value_menu_item,
+        connect_activate(_),
+        RenderStageConfigViewMsg::ShowExactValueEntry(variable_name.clone())
+    );
+
+    variable_menu.append(&reset_menu_item);
+    variable_menu.append(&copy_menu_item);
+    variable_menu.append(&paste_menu_item);
+    variable_menu.append(&exact_value_menu_item);
+    variable_menu.show_all();
+
+    let variable_menu_button = MenuButton::new();
+    variable_menu_button.set_relief(ReliefStyle::None);
+    variable_menu_button.set_valign(gtk::Align::Start);
+    variable_menu_button.set_popup(Some(&variable_menu));
+
+    let wrapper = gtk::Box::new(Horizontal, 0);
+    wrapper.add(&variable_name_label);
+    wrapper.add(&variable_menu_button);
+
+    (wrapper, variable_menu_button)
+}
+
+fn build_int_vector(value: &[i32]) -> DataHolder {
+    match *value {
+        [x, y] => DataHolder::Int2([x, y]),
+        [x, y, z] => DataHolder::Int3([x, y, z]),
+        [x, y, z, w] => DataHolder::Int4([x, y, z, w]),
+        _ => DataHolder::Int(value.first().copied().unwrap_or_default()),
+    }
+}
+
+/// Builds one slider per component of an Int2, Int3 or Int4 variable.
+pub fn create_int_vector_spinner(
+    relm: &Relm<RenderStageConfigView>,
+    name: &str,
+    value: &[i32],
+    value_range: &DataRange,
+) -> gtk::Box {
+    let (min_value, max_value, step) =
+        if let DataRange::IntRange(min_value, max_value, step) = value_range {
+            (*min_value as f64, *max_value as f64, *step as f64)
+        } else {
+            (-8192.0, 8192.0, 1.0)
+        };
+
+    let components_wrapper = gtk::Box::new(Vertical, 0);
+
+    let spinner_list: Vec<Scale> = value
+        .iter()
+        .zip(COMPONENT_LABEL_LIST.iter())
+        .map(|(component_value, component_label)| {
+            let component_wrapper = gtk::Box::new(Horizontal, 2);
+
+            let spinner = Scale::with_range(Orientation::Horizontal, min_value, max_value, step);
+            spinner.set_has_origin(false);
+            spinner.set_digits(0);
+            spinner.set_value(*component_value as f64);
+            spinner.set_hexpand(true);
+
+            spinner.set_value_pos(PositionType::Right);
+
+            component_wrapper.add(&Label::new(Some(component_label)));
+            component_wrapper.add(&spinner);
+            components_wrapper.add(&component_wrapper);
+
+            spinner
+        })
+        .collect();
+
+    let name = name.to_string();
+    for spinner in &spinner_list {
+        let name = name.clone();
+        let spinner_list = spinner_list.clone();
+        connect!(relm, spinner, connect_value_changed(_), {
+            let value: Vec<i32> = spinner_list
+                .iter()
+                .map(|spinner| spinner.get_value().round() as i32)
+                .collect();
+
+            Some(RenderStageConfigViewMsg::UpdateVariable(
+                name.clone(),
+                build_int_vector(&value),
+            ))
+        });
+    }
+
+    components_wrapper
+}
+
+/// Wraps the editor of a Float3 or Float4 variable with the toggle switching it to a color editor.
+fn build_color_toggle_row(
+    relm: &Relm<RenderStageConfigView>,
+    variable_name: &str,
+    editor: &gtk::Box,
+    use_color_editor: bool,
+) -> gtk::Box {
+    let color_toggle = ToggleButton::new();
+    color_toggle.set_image(Some(&Image::from_icon_name(
+        Some("preferences-color-symbolic"),
+        IconSize::Button,
+    )));
+    color_toggle.set_relief(ReliefStyle::None);
+    color_toggle.set_valign(gtk::Align::Start);
+    color_toggle.set_active(use_color_editor);
+    color_toggle.set_tooltip_text(Some("Edit as a color"));
+
+    let variable_name = variable_name.to_string();
+    connect!(
+        relm,
+        color_toggle,
+        connect_toggled(val),
+        Some(RenderStageConfigViewMsg::SetColorEditor(
+            variable_name.clone(),
+            val.get_active()
+        ))
+    );
+
+    editor.set_hexpand(true);
+
+    let wrapper = gtk::Box::new(Horizontal, 2);
+    wrapper.add(editor);
+    wrapper.add(&color_toggle);
+
+    wrapper
+}
+
 pub fn build_variable_row(
     relm: &Relm<RenderStageConfigView>,
     variable_name: &str,
     variable_value: &DataHolder,
+    default_value: &DataHolder,
     variable_range: &DataRange,
+    use_color_editor: bool,
+    automation_preview: &SharedAutomationPreview,
 ) -> gtk::Box {
     return match variable_value {
         DataHolder::Bool(value) => {
@@ -457,30 +689,181 @@ pub fn build_variable_row(
         DataHolder::Float(value) => {
             create_float_spinner(relm, variable_name, *value as f64, variable_range)
         }
-        DataHolder::Float2(value) => create_float2_spinner(
-            relm,
-            variable_name,
-            value[0] as f64,
-            value[1] as f64,
-            variable_range,
-        ),
-        DataHolder::Float3(value) => create_float3_spinner(
-            relm,
-            variable_name,
-            value[0] as f64,
-            value[1] as f64,
-            value[2] as f64,
-            variable_range,
-        ),
-        DataHolder::Float4(value) => create_float4_spinner(
-            relm,
-            variable_name,
-            value[0] as f64,
-            value[1] as f64,
-            value[2] as f64,
-            value[3] as f64,
-            variable_range,
-        ),
-        _ => unimplemented!(),
+        DataHolder::Float2(value) => {
+            let default_value = match default_value {
+                DataHolder::Float2(default_value) => *default_value,
+                _ => *value,
+            };
+
+            xy_pad::create_float2_pad(
+                relm,
+                variable_name,
+                *value,
+                default_value,
+                variable_range,
+                automation_preview,
+            )
+        }
+        DataHolder::Float3(value) => {
+            let editor = if use_color_editor {
+                color::create_color_editor(relm, variable_name, value, variable_range)
+            } else {
+                create_float3_spinner(
+                    relm,
+                    variable_name,
+                    value[0] as f64,
+                    value[1] as f64,
+                    value[2] as f64,
+                    variable_range,
+                )
+            };
+
+            build_color_toggle_row(relm, variable_name, &editor, use_color_editor)
+        }
+        DataHolder::Float4(value) => {
+            let editor = if use_color_editor {
+                color::create_color_editor(relm, variable_name, value, variable_range)
+            } else {
+                create_float4_spinner(
+                    relm,
+                    variable_name,
+                    value[0] as f64,
+                    value[1] as f64,
+                    value[2] as f64,
+                    value[3] as f64,
+                    variable_range,
+                )
+            };
+
+            build_color_toggle_row(relm, variable_name, &editor, use_color_editor)
+        }
+        DataHolder::Int2(value) => {
+            create_int_vector_spinner(relm, variable_name, value, variable_range)
+        }
+        DataHolder::Int3(value) => {
+            create_int_vector_spinner(relm, variable_name, value, variable_range)
+        }
+        DataHolder::Int4(value) => {
+            create_int_vector_spinner(relm, variable_name, value, variable_range)
+        }
+        // Kept out of the editor rather than crashing, the value from the filter is left as is
+        _ => {
+            let error_label = Label::new(Some("Unsupported variable type"));
+            error_label.set_xalign(0.0);
+            error_label.set_tooltip_text(Some(&format!(
+                "\"{:}\" can not be edited from the launcher",
+                variable_name
+            )));
+            error_label.get_style_context().add_class("error");
+
+            let wrapper = gtk::Box::new(Horizontal, 0);
+            wrapper.set_hexpand(true);
+            wrapper.add(&error_label);
+
+            wrapper
+        }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn components_are_split_on_any_separator() {
+        let value = parse_variable_value(" 1, 2;3  4 ", &DataHolder::Float4([0.0; 4])).unwrap();
+        assert!(matches!(value, DataHolder::Float4(value) if value == [1.0, 2.0, 3.0, 4.0]));
+
+        let value = parse_variable_value("-2,,7", &DataHolder::Int2([0; 2])).unwrap();
+        assert!(matches!(value, DataHolder::Int2(value) if value == [-2, 7]));
+    }
+
+    #[test]
+    fn component_count_must_match() {
+        let template = DataHolder::Float3([0.0; 3]);
+        assert!(parse_variable_value("1, 2", &template).is_err());
+        assert!(parse_variable_value("1, 2, 3, 4", &template).is_err());
+        assert!(parse_variable_value("", &DataHolder::Float(0.0)).is_err());
+    }
+
+    #[test]
+    fn invalid_components_are_rejected() {
+        assert!(parse_variable_value("1.5", &DataHolder::Int(0)).is_err());
+        assert!(parse_variable_value("a, 2", &DataHolder::Float2([0.0; 2])).is_err());
+    }
+
+    #[test]
+    fn bool_spellings_are_parsed() {
+        let template = DataHolder::Bool(false);
+        for text in &["true", "On", " 1 "] {
+            assert!(matches!(
+                parse_variable_value(text, &template).unwrap(),
+                DataHolder::Bool(true)
+            ));
+        }
+        for text in &["false", "OFF", "0"] {
+            assert!(matches!(
+                parse_variable_value(text, &template).unwrap(),
+                DataHolder::Bool(false)
+            ));
+        }
+        assert!(parse_variable_value("yes", &template).is_err());
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        let value = DataHolder::Float3([0.25, -1.0, 3.5]);
+        let text = format_variable_value(&value).unwrap();
+        assert_eq!(text, "0.25, -1, 3.5");
+        assert!(matches!(
+            parse_variable_value(&text, &value).unwrap(),
+            DataHolder::Float3(parsed_value) if parsed_value == [0.25, -1.0, 3.5]
+        ));
+
+        assert_eq!(
+            format_variable_value(&DataHolder::Bool(true)).as_deref(),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn float_values_are_clamped() {
+        let value_range = DataRange::FloatRange(-1.0, 1.0, 0.01);
+
+        let value = clamp_variable_value(&DataHolder::Float2([-3.0, 0.5]), &value_range);
+        assert!(matches!(value, DataHolder::Float2(value) if value == [-1.0, 0.5]));
+
+        let value = clamp_variable_value(
+            &DataHolder::Float3([f32::NAN, f32::INFINITY, f32::NEG_INFINITY]),
+            &value_range,
+        );
+        assert!(matches!(value, DataHolder::Float3(value) if value == [-1.0, 1.0, -1.0]));
+
+        let value = parse_variable_value("inf", &DataHolder::Float(0.0)).unwrap();
+        assert!(matches!(
+            clamp_variable_value(&value, &value_range),
+            DataHolder::Float(value) if value == 1.0
+        ));
+    }
+
+    #[test]
+    fn int_values_are_clamped() {
+        let value_range = DataRange::IntRange(0, 10, 1);
+
+        let value = clamp_variable_value(&DataHolder::Int(42), &value_range);
+        assert!(matches!(value, DataHolder::Int(10)));
+
+        let value = clamp_variable_value(&DataHolder::Int4([-5, 0, 5, 11]), &value_range);
+        assert!(matches!(value, DataHolder::Int4(value) if value == [0, 0, 5, 10]));
+    }
+
+    #[test]
+    fn mismatched_range_keeps_value() {
+        let value =
+            clamp_variable_value(&DataHolder::Int(42), &DataRange::FloatRange(0.0, 1.0, 0.1));
+        assert!(matches!(value, DataHolder::Int(42)));
+
+        let value = clamp_variable_value(&DataHolder::Bool(true), &DataRange::IntRange(0, 1, 1));
+        assert!(matches!(value, DataHolder::Bool(true)));
+    }
+}
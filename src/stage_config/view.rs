@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
 use uuid::Uuid;
@@ -8,12 +8,14 @@ use glib::{Cast, ToValue};
 //use gtk::prelude::*, };
 use gtk::{
     prelude::{GtkListStoreExtManual, TreeSortableExtManual, TreeStoreExtManual},
-    Adjustment, CellLayoutExt, CellRendererText, ComboBoxExt, ComboBoxText, ComboBoxTextExt,
-    ContainerExt, EditableSignals, Entry, EntryExt, Grid, GridExt, GtkListStoreExt, Label,
-    LabelExt, MenuButton, MenuButtonExt, OrientableExt,
+    Adjustment, Align, Button, ButtonExt, CellLayoutExt, CellRendererText, CheckButton,
+    ComboBoxExt, ComboBoxText, ComboBoxTextExt, ContainerExt, EditableSignals, Entry, EntryExt,
+    Grid, GridExt, GtkListStoreExt, IconSize, Image, Label, LabelExt, MenuButton, MenuButtonExt,
+    OrientableExt,
     Orientation::{self, Horizontal, Vertical},
-    PolicyType, Popover, ScrolledWindow, ScrolledWindowExt, Separator, SortColumn, SortType,
-    TreeModelExt, TreeSelectionExt, TreeStoreExt, TreeViewColumn, TreeViewExt, WidgetExt,
+    PolicyType, Popover, PopoverExt, ReliefStyle, ScrolledWindow, ScrolledWindowExt, Separator,
+    SortColumn, SortType, StyleContextExt, ToggleButtonExt, TreeModelExt, TreeSelectionExt,
+    TreeStoreExt, TreeViewColumn, TreeViewExt, WidgetExt,
 };
 
 use relm::{connect, Component, Relm, Update, Widget};
@@ -26,11 +28,18 @@ use wvr_data::config::project_config::{
 };
 use wvr_data::{DataHolder, DataRange};
 
+use crate::config_panel::history;
 use crate::config_panel::msg::ConfigPanelMsg;
 use crate::config_panel::view::ConfigPanel;
+use crate::wvr_frame::preview::{
+    SharedStagePreviewList, StagePreviewList, STAGE_PREVIEW_HEIGHT, STAGE_PREVIEW_WIDTH,
+};
 
-use super::automation::{self, AutomationView};
+use super::automation::{self, AutomationPreview, AutomationView, SharedAutomationPreview};
+use super::clipboard;
+use super::color;
 use super::input;
+use super::preset::{self, PresetScope, VariablePreset};
 use super::variable;
 
 use super::list_store_sort_function;
@@ -42,8 +51,20 @@ pub enum RenderStageConfigViewMsg {
     SetPrecision(String),
 
     UpdateInput(String, SampledInput),
+    SetInput(String, SampledInput),
     UpdateVariable(String, DataHolder),
     UpdateVariableAutomation(String, Automation),
+    SetColorEditor(String, bool),
+    SetVariable(String, DataHolder),
+    ResetVariable(String),
+    ResetAllVariables,
+    CopyVariable(String),
+    PasteVariable(String),
+    ShowExactValueEntry(String),
+    RecallPreset(String),
+    SavePreset(String, PresetScope),
+    DeletePreset,
+    SetBpm(f64),
     UpdateInputChoiceList(Vec<String>),
 }
 
@@ -54,7 +75,52 @@ pub struct RenderStageConfigViewModel {
     input_choice_list: Vec<String>,
 
     available_filter_list: HashMap<String, (PathBuf, FilterConfig, bool)>,
+    stage_preview_list: SharedStagePreviewList,
+    /// Variables switched to or from the color editor by hand.
+    color_editor_list: BTreeMap<String, bool>,
+    automation_preview: SharedAutomationPreview,
+    project_path: PathBuf,
+    /// Presets of the current filter, by id.
+    preset_list: HashMap<String, VariablePreset>,
 }
+
+impl RenderStageConfigViewModel {
+    fn update_automation_preview(&self) {
+        self.automation_preview.automation_list.replace(
+            self.config
+                .variables
+                .iter()
+                .map(|(variable_name, (_, automation))| (variable_name.clone(), *automation))
+                .collect(),
+        );
+    }
+
+    fn get_color_hint_list(&self) -> HashSet<String> {
+        self.available_filter_list
+            .get(&self.config.filter)
+            .map(|(filter_path, _, _)| color::load_color_hint_list(filter_path))
+            .unwrap_or_default()
+    }
+
+    /// Whether a variable is edited as a color: by hand, else by the filter config hint, else
+    /// by its name.
+    fn use_color_editor(
+        &self,
+        variable_name: &str,
+        variable_value: &DataHolder,
+        color_hint_list: &HashSet<String>,
+    ) -> bool {
+        if !color::can_be_color(variable_value) {
+            return false;
+        }
+
+        match self.color_editor_list.get(variable_name) {
+            Some(use_color_editor) => *use_color_editor,
+            None => color_hint_list.contains(variable_name) || color::is_color_name(variable_name),
+        }
+    }
+}
+
 pub struct RenderStageConfigView {
     model: RenderStageConfigViewModel,
     relm: Relm<Self>,
@@ -63,8 +129,14 @@ pub struct RenderStageConfigView {
     filter_mode_params_label: Label,
     filter_mode_params_container: gtk::Box,
 
+    preset_chooser: ComboBoxText,
+    preset_recall_button: Button,
+    preset_delete_button: Button,
+
     filter_config_container: Grid,
     input_widget_list: HashMap<String, (ComboBoxText, ComboBoxText)>,
+    variable_container_list: HashMap<String, gtk::Box>,
+    variable_menu_button_list: HashMap<String, MenuButton>,
     automation_button_list: Vec<Component<AutomationView>>,
 }
 
@@ -164,6 +236,319 @@ impl RenderStageConfigView {
         }
     }
 
+    /// Shows an input changed from outside of this view, choosers only emit if their value changes.
+    pub fn set_input(&mut self, uniform_name: &str, input_value: &SampledInput) {
+        self.model
+            .config
+            .inputs
+            .insert(uniform_name.to_owned(), input_value.clone());
+
+        if let Some((input_type_chooser, input_name_chooser)) =
+            self.input_widget_list.get(uniform_name)
+        {
+            let (input_type, input_name) = match input_value {
+                SampledInput::Linear(input_name) => ("Linear", input_name),
+                SampledInput::Nearest(input_name) => ("Nearest", input_name),
+                SampledInput::Mipmaps(input_name) => ("Mipmaps", input_name),
+            };
+
+            if input_name.is_empty() {
+                input_name_chooser.set_active_id(None);
+            } else {
+                input_name_chooser.set_active_id(Some(input_name));
+            }
+            input_type_chooser.set_active_id(Some(input_type));
+        }
+    }
+
+    /// Switches a variable between its color editor and its component sliders.
+    pub fn set_color_editor(&mut self, variable_name: &str, use_color_editor: bool) {
+        self.model
+            .color_editor_list
+            .insert(variable_name.to_string(), use_color_editor);
+        self.model
+            .parent_relm
+            .stream()
+            .emit(ConfigPanelMsg::SetRenderStageColorEditor(
+                self.model.id,
+                variable_name.to_string(),
+                use_color_editor,
+            ));
+
+        self.rebuild_variable_row(variable_name);
+    }
+
+    /// Default value and range of a variable, as declared by the filter.
+    fn get_variable_config(&self, variable_name: &str) -> Option<(DataHolder, DataRange)> {
+        self.model
+            .available_filter_list
+            .get(&self.model.config.filter)
+            .and_then(|(_, filter_config, _)| filter_config.variables.get(variable_name))
+            .cloned()
+    }
+
+    /// Value and automation of a variable, the default of the filter until the stage sets it.
+    fn get_variable(&self, variable_name: &str) -> Option<(DataHolder, Automation)> {
+        match self.model.config.variables.get(variable_name) {
+            Some(variable) => Some(variable.clone()),
+            None => self
+                .get_variable_config(variable_name)
+                .map(|(default_value, _)| (default_value, Automation::None)),
+        }
+    }
+
+    /// Builds the editor of a variable again, to show a value that was not set through it.
+    fn rebuild_variable_row(&self, variable_name: &str) {
+        let (default_value, value_range) = match self.get_variable_config(variable_name) {
+            Some(variable_config) => variable_config,
+            None => return,
+        };
+
+        if let (Some(variable_container), Some((variable_value, _))) = (
+            self.variable_container_list.get(variable_name),
+            self.get_variable(variable_name),
+        ) {
+            for children in &variable_container.get_children() {
+                variable_container.remove(children);
+            }
+            variable_container.add(&variable::build_variable_row(
+                &self.relm,
+                variable_name,
+                &variable_value,
+                &default_value,
+                &value_range,
+                self.model.use_color_editor(
+                    variable_name,
+                    &variable_value,
+                    &self.model.get_color_hint_list(),
+                ),
+                &self.model.automation_preview,
+            ));
+            variable_container.show_all();
+        }
+    }
+
+    /// Sets a variable the filter declares, within its range. A variable the stage did not set
+    /// yet is added without automation.
+    pub fn set_variable(&mut self, variable_name: &str, value: DataHolder) {
+        let value = match self.get_variable_config(variable_name) {
+            Some((_, value_range)) => variable::clamp_variable_value(&value, &value_range),
+            None => return,
+        };
+
+        match self.model.config.variables.get_mut(variable_name) {
+            Some((variable_value, _)) => *variable_value = value.clone(),
+            None => {
+                self.model
+                    .config
+                    .variables
+                    .insert(variable_name.to_string(), (value.clone(), Automation::None));
+            }
+        }
+
+        self.rebuild_variable_row(variable_name);
+        self.model
+            .parent_relm
+            .stream()
+            .emit(ConfigPanelMsg::UpdateRenderStageVariable(
+                self.model.id,
+                variable_name.to_string(),
+                value,
+            ));
+    }
+
+    pub fn reset_variable(&mut self, variable_name: &str) {
+        if let Some((default_value, _)) = self.get_variable_config(variable_name) {
+            self.set_variable(variable_name, default_value);
+        }
+    }
+
+    /// Shows a popover below the menu of a variable to type its value in.
+    fn show_exact_value_entry(&self, variable_name: &str) {
+        let (variable_menu_button, variable_value) = match (
+            self.variable_menu_button_list.get(variable_name),
+            self.get_variable(variable_name),
+        ) {
+            (Some(variable_menu_button), Some((variable_value, _))) => {
+                (variable_menu_button, variable_value)
+            }
+            _ => return,
+        };
+
+        let exact_value_entry = Entry::new();
+        exact_value_entry.set_width_chars(24);
+        exact_value_entry
+            .set_text(&variable::format_variable_value(&variable_value).unwrap_or_default());
+        exact_value_entry.set_tooltip_text(Some("Components are separated by commas"));
+
+        let exact_value_popover = Popover::new(Some(variable_menu_button));
+        exact_value_popover.set_border_width(4);
+        exact_value_popover.add(&exact_value_entry);
+
+        {
+            let relm = self.relm.clone();
+            let variable_name = variable_name.to_string();
+            let exact_value_popover = exact_value_popover.clone();
+            exact_value_entry.connect_activate(move |exact_value_entry| {
+                match variable::parse_variable_value(&exact_value_entry.get_text(), &variable_value)
+                {
+                    Ok(value) => {
+                        relm.stream().emit(RenderStageConfigViewMsg::SetVariable(
+                            variable_name.clone(),
+                            value,
+                        ));
+                        exact_value_popover.popdown();
+                    }
+                    Err(e) => {
+                        exact_value_entry.get_style_context().add_class("error");
+                        exact_value_entry.set_tooltip_text(Some(&e.to_string()));
+                    }
+                }
+            });
+        }
+        exact_value_popover.connect_closed(|exact_value_popover| {
+            // Built again each time, so that it starts from the current value
+            exact_value_popover.destroy();
+        });
+
+        exact_value_popover.show_all();
+        exact_value_popover.popup();
+        exact_value_entry.grab_focus();
+    }
+
+    /// Lists the presets of the stage and its filter again, showing `active_preset_id` as
+    /// selected.
+    fn update_preset_chooser(&mut self, active_preset_id: Option<&str>) {
+        let preset_list = preset::load_preset_list(
+            &self.model.project_path,
+            &self.model.config.name,
+            &self.model.config.filter,
+        );
+
+        self.model.preset_list.clear();
+        self.preset_chooser.remove_all();
+        for (preset_id, scope, preset_name, preset) in preset_list {
+            let preset_label = match scope {
+                PresetScope::Project => preset_name,
+                PresetScope::Library => format!("{:} (library)", preset_name),
+            };
+            self.preset_chooser.append(Some(&preset_id), &preset_label);
+            self.model.preset_list.insert(preset_id, preset);
+        }
+
+        // Selecting the preset recalls it, which changes nothing as it was just saved
+        let active_preset_id =
+            active_preset_id.filter(|preset_id| self.model.preset_list.contains_key(*preset_id));
+        self.preset_chooser.set_active_id(active_preset_id);
+        self.preset_chooser
+            .set_sensitive(!self.model.preset_list.is_empty());
+        self.preset_recall_button
+            .set_sensitive(active_preset_id.is_some());
+        self.preset_delete_button
+            .set_sensitive(active_preset_id.is_some());
+    }
+
+    /// Applies a preset to the variables the current filter declares, within their range, the
+    /// changed ones being sent to the renderer together. Variables the stage did not set yet are
+    /// compared to their default and added when the preset changes them.
+    pub fn recall_preset(&mut self, preset_id: &str) {
+        self.preset_recall_button.set_sensitive(true);
+        self.preset_delete_button.set_sensitive(true);
+
+        let changed_variable_list: VariablePreset = match self.model.preset_list.get(preset_id) {
+            Some(preset) => preset
+                .iter()
+                .filter_map(|(variable_name, (value, automation))| {
+                    // Presets saved before the filter changed may be out of its range
+                    let (_, value_range) = self.get_variable_config(variable_name)?;
+                    let variable = (
+                        variable::clamp_variable_value(value, &value_range),
+                        *automation,
+                    );
+
+                    let current_variable = self.get_variable(variable_name)?;
+                    if history::differs(&current_variable, &variable) {
+                        Some((variable_name.clone(), variable))
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            None => return,
+        };
+        if changed_variable_list.is_empty() {
+            return;
+        }
+
+        for (variable_name, variable) in &changed_variable_list {
+            self.model
+                .config
+                .variables
+                .insert(variable_name.clone(), variable.clone());
+        }
+        self.model.update_automation_preview();
+        self.rebuild_filter_config();
+
+        self.model
+            .parent_relm
+            .stream()
+            .emit(ConfigPanelMsg::ApplyRenderStagePreset(
+                self.model.id,
+                changed_variable_list,
+            ));
+    }
+
+    pub fn save_preset(&mut self, preset_name: &str, scope: PresetScope) {
+        match preset::save_preset(
+            &self.model.project_path,
+            &self.model.config.name,
+            &self.model.config.filter,
+            scope,
+            preset_name,
+            &self.model.config.variables,
+        ) {
+            Ok(preset_id) => self.update_preset_chooser(Some(&preset_id)),
+            Err(e) => eprintln!("Failed to save preset {:}: {:?}", preset_name, e),
+        }
+    }
+
+    pub fn delete_preset(&mut self) {
+        let preset_id = match self.preset_chooser.get_active_id() {
+            Some(preset_id) => preset_id.to_string(),
+            None => return,
+        };
+
+        if let Err(e) = preset::delete_preset(
+            &self.model.project_path,
+            &self.model.config.name,
+            &self.model.config.filter,
+            &preset_id,
+        ) {
+            eprintln!("Failed to delete preset {:}: {:?}", preset_id, e);
+        }
+        self.update_preset_chooser(None);
+    }
+
+    /// Builds the input and variable rows again, to show variables that were not set through them.
+    fn rebuild_filter_config(&mut self) {
+        for children in &self.filter_config_container.get_children() {
+            self.filter_config_container.remove(children);
+        }
+
+        let (
+            input_widget_list,
+            variable_container_list,
+            variable_menu_button_list,
+            automation_button_list,
+        ) = fill_filter_config(&self.relm, &self.model, &self.filter_config_container);
+        self.input_widget_list = input_widget_list;
+        self.variable_container_list = variable_container_list;
+        self.variable_menu_button_list = variable_menu_button_list;
+        self.automation_button_list = automation_button_list;
+
+        self.filter_config_container.show_all();
+    }
+
     pub fn set_filter(&mut self, filter_name: &str) {
         self.model.config.filter = filter_name.to_string();
         if let Some((_, filter_config, _)) = &self.model.available_filter_list.get(filter_name) {
@@ -237,6 +622,10 @@ impl RenderStageConfigView {
             let old_variables = self.model.config.variables.clone();
 
             self.model.config.variables.clear();
+            self.variable_container_list.clear();
+            self.variable_menu_button_list.clear();
+
+            let color_hint_list = self.model.get_color_hint_list();
 
             let mut variable_name_list: Vec<String> =
                 filter_config.variables.keys().map(String::clone).collect();
@@ -249,15 +638,24 @@ impl RenderStageConfigView {
                 let (variable_value, variable_automation) =
                     old_variables.get(variable_name).unwrap_or(&default_value);
 
-                let variable_name_label = Label::new(Some(variable_name));
-                variable_name_label.set_xalign(0.0);
+                let (variable_name_label, variable_menu_button) =
+                    variable::build_variable_label(&self.relm, variable_name);
+                self.variable_menu_button_list
+                    .insert(variable_name.clone(), variable_menu_button);
 
-                let variable_wrapper = variable::build_variable_row(
+                let variable_wrapper = gtk::Box::new(Horizontal, 0);
+                variable_wrapper.add(&variable::build_variable_row(
                     &self.relm,
                     variable_name,
                     &variable_value,
+                    &default_value.0,
                     value_range,
-                );
+                    self.model
+                        .use_color_editor(variable_name, &variable_value, &color_hint_list),
+                    &self.model.automation_preview,
+                ));
+                self.variable_container_list
+                    .insert(variable_name.clone(), variable_wrapper.clone());
 
                 let variable_dimension_count: usize = match default_value.0 {
                     DataHolder::Float(_) | DataHolder::Int(_) | DataHolder::Bool(_) => 1,
@@ -305,8 +703,11 @@ impl RenderStageConfigView {
                 self.automation_button_list.push(automation_button);
             }
 
+            self.model.update_automation_preview();
             self.filter_config_container.show_all();
         }
+        self.update_preset_chooser(None);
+
         self.model
             .parent_relm
             .stream()
@@ -345,6 +746,10 @@ impl Update for RenderStageConfigView {
         Vec<String>,
         HashMap<String, (PathBuf, FilterConfig, bool)>,
         Relm<ConfigPanel>,
+        SharedStagePreviewList,
+        BTreeMap<String, bool>,
+        f64,
+        PathBuf,
     );
     type Msg = RenderStageConfigViewMsg;
 
@@ -356,20 +761,41 @@ impl Update for RenderStageConfigView {
             Vec<String>,
             HashMap<String, (PathBuf, FilterConfig, bool)>,
             Relm<ConfigPanel>,
+            SharedStagePreviewList,
+            BTreeMap<String, bool>,
+            f64,
+            PathBuf,
         ),
     ) -> Self::Model {
+        let automation_preview = AutomationPreview::new_shared(
+            model.7,
+            model
+                .1
+                .variables
+                .iter()
+                .map(|(variable_name, (_, automation))| (variable_name.clone(), *automation))
+                .collect(),
+        );
+
         RenderStageConfigViewModel {
             id: model.0,
             config: model.1,
             input_choice_list: model.2,
             available_filter_list: model.3,
             parent_relm: model.4,
+            stage_preview_list: model.5,
+            color_editor_list: model.6,
+            automation_preview,
+            project_path: model.8,
+            preset_list: HashMap::new(),
         }
     }
 
     fn update(&mut self, event: RenderStageConfigViewMsg) {
         match event {
             RenderStageConfigViewMsg::SetName(new_name) => {
+                // Project presets are stored by stage name
+                self.model.config.name = new_name.clone();
                 self.model
                     .parent_relm
                     .stream()
@@ -409,10 +835,22 @@ impl Update for RenderStageConfigView {
 
                 self.model.config.inputs.insert(input_name, input_value);
             }
+            RenderStageConfigViewMsg::SetInput(input_name, input_value) => {
+                self.set_input(&input_name, &input_value);
+            }
             RenderStageConfigViewMsg::UpdateVariable(name, value) => {
                 if name == "_FILTER_MODE_PARAMS" {
                     self.update_filter_params(value);
                 } else {
+                    // Kept current so that a rebuilt editor starts from the edited value
+                    if let Some((variable_value, _)) = self.model.config.variables.get_mut(&name) {
+                        *variable_value = value.clone();
+                    } else {
+                        self.model
+                            .config
+                            .variables
+                            .insert(name.clone(), (value.clone(), Automation::None));
+                    }
                     self.model.parent_relm.stream().emit(
                         ConfigPanelMsg::UpdateRenderStageVariable(self.model.id, name, value),
                     );
@@ -420,6 +858,27 @@ impl Update for RenderStageConfigView {
             }
             RenderStageConfigViewMsg::UpdateVariableAutomation(name, automation) => {
                 println!("{:} : {:?}", name, automation);
+                if !self.model.config.variables.contains_key(&name) {
+                    // The stage gets the variable at the default value it showed, before the
+                    // automation can apply to it
+                    if let Some((default_value, _)) = self.get_variable_config(&name) {
+                        self.model
+                            .config
+                            .variables
+                            .insert(name.clone(), (default_value.clone(), Automation::None));
+                        self.model.parent_relm.stream().emit(
+                            ConfigPanelMsg::UpdateRenderStageVariable(
+                                self.model.id,
+                                name.clone(),
+                                default_value,
+                            ),
+                        );
+                    }
+                }
+                if let Some((_, variable_automation)) = self.model.config.variables.get_mut(&name) {
+                    *variable_automation = automation;
+                }
+                self.model.update_automation_preview();
                 self.model.parent_relm.stream().emit(
                     ConfigPanelMsg::UpdateRenderStageVariableAutomation(
                         self.model.id,
@@ -428,6 +887,58 @@ impl Update for RenderStageConfigView {
                     ),
                 );
             }
+            RenderStageConfigViewMsg::SetVariable(name, value) => {
+                self.set_variable(&name, value);
+            }
+            RenderStageConfigViewMsg::ResetVariable(name) => {
+                self.reset_variable(&name);
+            }
+            RenderStageConfigViewMsg::ResetAllVariables => {
+                // Every variable the filter declares, including those the stage never set
+                let mut variable_name_list: Vec<String> = self
+                    .model
+                    .available_filter_list
+                    .get(&self.model.config.filter)
+                    .map(|(_, filter_config, _)| filter_config.variables.keys().cloned().collect())
+                    .unwrap_or_default();
+                variable_name_list.sort();
+                for variable_name in variable_name_list {
+                    self.reset_variable(&variable_name);
+                }
+            }
+            RenderStageConfigViewMsg::CopyVariable(name) => {
+                if let Some((variable_value, _)) = self.get_variable(&name) {
+                    if let Err(e) = clipboard::copy_variable_value(&variable_value) {
+                        eprintln!("Failed to copy variable {:}: {:?}", name, e);
+                    }
+                }
+            }
+            RenderStageConfigViewMsg::PasteVariable(name) => {
+                if let Some((variable_value, _)) = self.get_variable(&name) {
+                    match clipboard::paste_variable_value(&variable_value) {
+                        Ok(value) => self.set_variable(&name, value),
+                        Err(e) => eprintln!("Failed to paste variable {:}: {:?}", name, e),
+                    }
+                }
+            }
+            RenderStageConfigViewMsg::ShowExactValueEntry(name) => {
+                self.show_exact_value_entry(&name);
+            }
+            RenderStageConfigViewMsg::RecallPreset(preset_id) => {
+                self.recall_preset(&preset_id);
+            }
+            RenderStageConfigViewMsg::SavePreset(preset_name, scope) => {
+                self.save_preset(&preset_name, scope);
+            }
+            RenderStageConfigViewMsg::DeletePreset => {
+                self.delete_preset();
+            }
+            RenderStageConfigViewMsg::SetBpm(bpm) => {
+                self.model.automation_preview.bpm.set(bpm);
+            }
+            RenderStageConfigViewMsg::SetColorEditor(name, use_color_editor) => {
+                self.set_color_editor(&name, use_color_editor);
+            }
             RenderStageConfigViewMsg::UpdateInputChoiceList(choice_list) => {
                 self.update_input_choice_list(&choice_list);
             }
@@ -446,6 +957,18 @@ impl Widget for RenderStageConfigView {
         let root = gtk::Box::new(Vertical, 4);
         root.set_property_margin(8);
 
+        // Filled by the renderer while the project runs locally
+        let preview_image =
+            StagePreviewList::build_preview_image(STAGE_PREVIEW_WIDTH, STAGE_PREVIEW_HEIGHT);
+        preview_image.set_halign(Align::Center);
+        preview_image.set_tooltip_text(Some("Stage output"));
+        model.stage_preview_list.borrow_mut().add_image(
+            model.id,
+            &preview_image,
+            STAGE_PREVIEW_WIDTH,
+            STAGE_PREVIEW_HEIGHT,
+        );
+
         // Building of the input name widget
         let name_entry = Entry::new();
         name_entry.set_hexpand(true);
@@ -555,18 +1078,124 @@ impl Widget for RenderStageConfigView {
         base_config.attach(&precision_chooser, 2, 1, 1, 1);
         base_config.attach(&filter_mode_params_button, 3, 1, 1, 1);
 
+        // Building of the preset widgets, filled once the view is built
+        let preset_chooser = ComboBoxText::new();
+        preset_chooser.set_hexpand(true);
+        preset_chooser.set_tooltip_text(Some("Recall a preset of the filter variables"));
+        connect!(
+            relm,
+            preset_chooser,
+            connect_changed(val),
+            val.get_active_id()
+                .map(|preset_id| RenderStageConfigViewMsg::RecallPreset(preset_id.to_string()))
+        );
+
+        let preset_save_button = MenuButton::new();
+        preset_save_button.add(&Image::from_icon_name(
+            Some("document-save-symbolic"),
+            IconSize::Button,
+        ));
+        preset_save_button.set_tooltip_text(Some("Save the variables as a preset"));
+
+        let preset_save_popover = Popover::new(Some(&preset_save_button));
+        preset_save_button.set_popover(Some(&preset_save_popover));
+
+        let preset_name_entry = Entry::new();
+        preset_name_entry.set_placeholder_text(Some("Preset name"));
+
+        let preset_library_check = CheckButton::with_label("Share with every project");
+        preset_library_check.set_tooltip_text(Some(
+            "Store the preset in the library of the filter instead of the project",
+        ));
+
+        let preset_confirm_button = Button::with_label("Save");
+        {
+            let preset_name_entry = preset_name_entry.clone();
+            let preset_library_check = preset_library_check.clone();
+            let preset_save_popover = preset_save_popover.clone();
+            connect!(relm, preset_confirm_button, connect_clicked(_), {
+                let preset_name = preset_name_entry.get_text().trim().to_string();
+                if preset_name.is_empty() {
+                    None
+                } else {
+                    preset_save_popover.popdown();
+                    Some(RenderStageConfigViewMsg::SavePreset(
+                        preset_name,
+                        if preset_library_check.get_active() {
+                            PresetScope::Library
+                        } else {
+                            PresetScope::Project
+                        },
+                    ))
+                }
+            });
+        }
+        {
+            let preset_confirm_button = preset_confirm_button.clone();
+            preset_name_entry.connect_activate(move |_| preset_confirm_button.clicked());
+        }
+
+        let preset_save_wrapper = gtk::Box::new(Vertical, 4);
+        preset_save_wrapper.set_property_margin(4);
+        preset_save_wrapper.add(&preset_name_entry);
+        preset_save_wrapper.add(&preset_library_check);
+        preset_save_wrapper.add(&preset_confirm_button);
+
+        preset_save_popover.add(&preset_save_wrapper);
+        preset_save_wrapper.show_all();
+
+        // Selecting a preset recalls it, this recalls the selected one again after edits
+        let preset_recall_button =
+            Button::from_icon_name(Some("document-revert-symbolic"), IconSize::Button);
+        preset_recall_button.set_relief(ReliefStyle::None);
+        preset_recall_button.set_tooltip_text(Some("Recall the selected preset again"));
+        {
+            let preset_chooser = preset_chooser.clone();
+            connect!(
+                relm,
+                preset_recall_button,
+                connect_clicked(_),
+                preset_chooser
+                    .get_active_id()
+                    .map(|preset_id| RenderStageConfigViewMsg::RecallPreset(preset_id.to_string()))
+            );
+        }
+
+        let preset_delete_button =
+            Button::from_icon_name(Some("user-trash-symbolic"), IconSize::Button);
+        preset_delete_button.set_relief(ReliefStyle::None);
+        preset_delete_button.set_tooltip_text(Some("Delete the selected preset"));
+        connect!(
+            relm,
+            preset_delete_button,
+            connect_clicked(_),
+            RenderStageConfigViewMsg::DeletePreset
+        );
+
+        let preset_button_wrapper = gtk::Box::new(Horizontal, 0);
+        preset_button_wrapper.add(&preset_recall_button);
+        preset_button_wrapper.add(&preset_save_button);
+        preset_button_wrapper.add(&preset_delete_button);
+
+        base_config.attach(&Label::new(Some("Preset")), 0, 2, 1, 1);
+        base_config.attach(&preset_chooser, 1, 2, 2, 1);
+        base_config.attach(&preset_button_wrapper, 3, 2, 1, 1);
+
         let (
             filter_config_container,
             filter_config_panel,
             input_widget_list,
+            variable_container_list,
+            variable_menu_button_list,
             automation_button_list,
         ) = build_filter_config(relm, &model);
 
+        root.add(&preview_image);
         root.add(&base_config);
         root.add(&Separator::new(Horizontal));
         root.add(&filter_config_panel);
 
-        Self {
+        let mut render_stage_config_view = Self {
             relm: relm.clone(),
             model,
             root,
@@ -574,11 +1203,20 @@ impl Widget for RenderStageConfigView {
             filter_mode_params_label,
             filter_mode_params_container,
 
+            preset_chooser,
+            preset_recall_button,
+            preset_delete_button,
+
             filter_config_container,
             input_widget_list,
+            variable_container_list,
+            variable_menu_button_list,
 
             automation_button_list,
-        }
+        };
+        render_stage_config_view.update_preset_chooser(None);
+
+        render_stage_config_view
     }
 }
 
@@ -589,17 +1227,58 @@ pub fn build_filter_config(
     Grid,
     ScrolledWindow,
     HashMap<String, (ComboBoxText, ComboBoxText)>,
+    HashMap<String, gtk::Box>,
+    HashMap<String, MenuButton>,
     Vec<Component<AutomationView>>,
 ) {
-    let mut input_widget_list = HashMap::new();
-    let mut automation_button_list = Vec::new();
-
     let filter_config_panel = gtk::Box::new(Vertical, 16);
     let filter_config_container = gtk::Grid::new();
     filter_config_container.set_row_spacing(4);
     filter_config_container.set_column_spacing(4);
     filter_config_container.set_orientation(Orientation::Vertical);
 
+    let (
+        input_widget_list,
+        variable_container_list,
+        variable_menu_button_list,
+        automation_button_list,
+    ) = fill_filter_config(relm, model, &filter_config_container);
+
+    filter_config_panel.add(&filter_config_container);
+
+    let filter_config_wrapper = ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
+
+    filter_config_wrapper.set_policy(PolicyType::Never, PolicyType::Automatic);
+    filter_config_wrapper.set_hexpand(true);
+    filter_config_wrapper.set_vexpand(true);
+    filter_config_wrapper.add(&filter_config_panel);
+
+    (
+        filter_config_container,
+        filter_config_wrapper,
+        input_widget_list,
+        variable_container_list,
+        variable_menu_button_list,
+        automation_button_list,
+    )
+}
+
+/// Attaches to `filter_config_container` a row per input and variable of the stage filter.
+pub fn fill_filter_config(
+    relm: &Relm<RenderStageConfigView>,
+    model: &RenderStageConfigViewModel,
+    filter_config_container: &Grid,
+) -> (
+    HashMap<String, (ComboBoxText, ComboBoxText)>,
+    HashMap<String, gtk::Box>,
+    HashMap<String, MenuButton>,
+    Vec<Component<AutomationView>>,
+) {
+    let mut input_widget_list = HashMap::new();
+    let mut variable_container_list = HashMap::new();
+    let mut variable_menu_button_list = HashMap::new();
+    let mut automation_button_list = Vec::new();
+
     if model
         .available_filter_list
         .contains_key(&model.config.filter)
@@ -636,6 +1315,8 @@ pub fn build_filter_config(
             filter_config.variables.keys().map(String::clone).collect();
         variable_name_list.sort();
 
+        let color_hint_list = model.get_color_hint_list();
+
         for (variable_index, variable_name) in variable_name_list.iter().enumerate() {
             let (default_value, value_range) = filter_config.variables.get(variable_name).unwrap();
             let default_value = (default_value.clone(), Automation::None);
@@ -646,8 +1327,17 @@ pub fn build_filter_config(
                 .get(variable_name)
                 .unwrap_or(&default_value);
 
-            let variable_wrapper =
-                variable::build_variable_row(relm, &variable_name, &variable_value, &value_range);
+            let variable_wrapper = gtk::Box::new(Horizontal, 0);
+            variable_wrapper.add(&variable::build_variable_row(
+                relm,
+                &variable_name,
+                &variable_value,
+                &default_value.0,
+                &value_range,
+                model.use_color_editor(&variable_name, &variable_value, &color_hint_list),
+                &model.automation_preview,
+            ));
+            variable_container_list.insert(variable_name.clone(), variable_wrapper.clone());
 
             let variable_dimension_count: usize = match default_value.0 {
                 DataHolder::Float(_) | DataHolder::Int(_) | DataHolder::Bool(_) => 1,
@@ -665,8 +1355,9 @@ pub fn build_filter_config(
                 variable_automation.clone(),
             );
 
-            let variable_name_label = Label::new(Some(variable_name));
-            variable_name_label.set_xalign(0.0);
+            let (variable_name_label, variable_menu_button) =
+                variable::build_variable_label(relm, variable_name);
+            variable_menu_button_list.insert(variable_name.clone(), variable_menu_button);
 
             filter_config_container.attach(
                 &variable_name_label,
@@ -696,19 +1387,10 @@ pub fn build_filter_config(
         }
     }
 
-    filter_config_panel.add(&filter_config_container);
-
-    let filter_config_wrapper = ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
-
-    filter_config_wrapper.set_policy(PolicyType::Never, PolicyType::Automatic);
-    filter_config_wrapper.set_hexpand(true);
-    filter_config_wrapper.set_vexpand(true);
-    filter_config_wrapper.add(&filter_config_panel);
-
     (
-        filter_config_container,
-        filter_config_wrapper,
         input_widget_list,
+        variable_container_list,
+        variable_menu_button_list,
         automation_button_list,
     )
 }
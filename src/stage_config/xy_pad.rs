@@ -0,0 +1,364 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gdk::{EventMask, EventType, ModifierType};
+
+use gtk::Orientation::Vertical;
+use gtk::{ContainerExt, DrawingArea, Inhibit, Label, LabelExt, WidgetExt};
+
+use relm::Relm;
+
+use wvr_data::config::project_config::Automation;
+use wvr_data::{DataHolder, DataRange};
+
+use super::automation::{evaluate_lfo, SharedAutomationPreview};
+use super::view::{RenderStageConfigView, RenderStageConfigViewMsg};
+
+const PAD_HEIGHT: i32 = 160;
+const GRID_DIVISION_COUNT: usize = 8;
+
+// Share of the pointer movement applied while dragging with Shift held
+const FINE_DRAG_FACTOR: f64 = 0.1;
+
+// Beats of the LFO path drawn ahead of the moving dot
+const LFO_PATH_BEAT_COUNT: f64 = 16.0;
+const LFO_PATH_POINT_COUNT: usize = 256;
+
+#[derive(Clone, Copy)]
+struct Drag {
+    is_fine: bool,
+    pointer_origin: (f64, f64),
+    value_origin: (f64, f64),
+}
+
+struct XyPad {
+    variable_name: String,
+    min_value: f64,
+    max_value: f64,
+    step: f64,
+    default_value: (f64, f64),
+
+    value: Cell<(f64, f64)>,
+    drag: Cell<Option<Drag>>,
+
+    drawing_area: DrawingArea,
+    value_label: Label,
+    lfo_preview_label: Label,
+    automation_preview: SharedAutomationPreview,
+}
+
+impl XyPad {
+    fn get_size(&self) -> (f64, f64) {
+        (
+            self.drawing_area.get_allocated_width().max(1) as f64,
+            self.drawing_area.get_allocated_height().max(1) as f64,
+        )
+    }
+
+    /// Position on the pad of a value, y growing upward.
+    fn to_pad_position(&self, value: (f64, f64)) -> (f64, f64) {
+        let (width, height) = self.get_size();
+        let span = (self.max_value - self.min_value).max(f64::EPSILON);
+
+        (
+            (value.0 - self.min_value) / span * width,
+            height - (value.1 - self.min_value) / span * height,
+        )
+    }
+
+    fn get_pointer_value(&self, pointer_position: (f64, f64)) -> (f64, f64) {
+        let (width, height) = self.get_size();
+        let span = self.max_value - self.min_value;
+
+        (
+            self.min_value + pointer_position.0 / width * span,
+            self.min_value + (height - pointer_position.1) / height * span,
+        )
+    }
+
+    fn snap(&self, value: f64) -> f64 {
+        let value = if self.step > 0.0 {
+            self.min_value + ((value - self.min_value) / self.step).round() * self.step
+        } else {
+            value
+        };
+
+        value.max(self.min_value).min(self.max_value)
+    }
+
+    fn set_value(&self, relm: &Relm<RenderStageConfigView>, value: (f64, f64)) {
+        let value = (self.snap(value.0), self.snap(value.1));
+        if value == self.value.get() {
+            return;
+        }
+
+        self.value.set(value);
+        self.update_value_label();
+        self.drawing_area.queue_draw();
+
+        relm.stream().emit(RenderStageConfigViewMsg::UpdateVariable(
+            self.variable_name.clone(),
+            DataHolder::Float2([value.0 as f32, value.1 as f32]),
+        ));
+    }
+
+    fn update_value_label(&self) {
+        let (x, y) = self.value.get();
+        self.value_label
+            .set_text(&format!("x {:.3}    y {:.3}", x, y));
+    }
+
+    fn is_automated(&self) -> bool {
+        if let Automation::Lfo2d(_, _) = self.automation_preview.get_automation(&self.variable_name)
+        {
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Value the LFOs of the variable reach at `beat`, if it is automated.
+    fn get_automated_value(&self, beat: f64) -> Option<(f64, f64)> {
+        match self.automation_preview.get_automation(&self.variable_name) {
+            Automation::Lfo2d(lfo_x, lfo_y) => {
+                let (x, y) = self.value.get();
+                Some((
+                    (x + evaluate_lfo(&lfo_x, beat))
+                        .max(self.min_value)
+                        .min(self.max_value),
+                    (y + evaluate_lfo(&lfo_y, beat))
+                        .max(self.min_value)
+                        .min(self.max_value),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let (width, height) = self.get_size();
+
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.25);
+        cr.paint();
+
+        cr.set_line_width(1.0);
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.1);
+        for division_index in 1..GRID_DIVISION_COUNT {
+            let ratio = division_index as f64 / GRID_DIVISION_COUNT as f64;
+            cr.move_to((ratio * width).round() + 0.5, 0.0);
+            cr.line_to((ratio * width).round() + 0.5, height);
+            cr.move_to(0.0, (ratio * height).round() + 0.5);
+            cr.line_to(width, (ratio * height).round() + 0.5);
+        }
+        cr.stroke();
+
+        // Axes are drawn when the origin is part of the range
+        if self.min_value < 0.0 && self.max_value > 0.0 {
+            let (origin_x, origin_y) = self.to_pad_position((0.0, 0.0));
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.3);
+            cr.move_to(origin_x.round() + 0.5, 0.0);
+            cr.line_to(origin_x.round() + 0.5, height);
+            cr.move_to(0.0, origin_y.round() + 0.5);
+            cr.line_to(width, origin_y.round() + 0.5);
+            cr.stroke();
+        }
+
+        let (x, y) = self.to_pad_position(self.value.get());
+        cr.set_source_rgba(1.0, 0.6, 0.2, 0.8);
+        cr.move_to(x.round() + 0.5, 0.0);
+        cr.line_to(x.round() + 0.5, height);
+        cr.move_to(0.0, y.round() + 0.5);
+        cr.line_to(width, y.round() + 0.5);
+        cr.stroke();
+        cr.arc(x, y, 4.0, 0.0, 2.0 * std::f64::consts::PI);
+        cr.stroke();
+
+        // The renderer does not share its clock with the editor, the beat only follows the
+        // tempo from whenever GTK started counting frames so the dot previews the shape of
+        // the LFOs, not where the output is
+        let beat = self.automation_preview.get_beat(
+            self.drawing_area
+                .get_frame_clock()
+                .map(|frame_clock| frame_clock.get_frame_time() as f64 / 1_000_000.0)
+                .unwrap_or_default(),
+        );
+        if let Some(automated_value) = self.get_automated_value(beat) {
+            cr.set_source_rgba(0.4, 0.8, 1.0, 0.3);
+            for point_index in 0..LFO_PATH_POINT_COUNT {
+                let point_beat =
+                    beat + point_index as f64 / LFO_PATH_POINT_COUNT as f64 * LFO_PATH_BEAT_COUNT;
+                if let Some(point_value) = self.get_automated_value(point_beat) {
+                    let (point_x, point_y) = self.to_pad_position(point_value);
+                    if point_index == 0 {
+                        cr.move_to(point_x, point_y);
+                    } else {
+                        cr.line_to(point_x, point_y);
+                    }
+                }
+            }
+            cr.stroke();
+
+            let (automated_x, automated_y) = self.to_pad_position(automated_value);
+            cr.set_source_rgba(0.4, 0.8, 1.0, 1.0);
+            cr.arc(
+                automated_x,
+                automated_y,
+                5.0,
+                0.0,
+                2.0 * std::f64::consts::PI,
+            );
+            cr.fill();
+        }
+    }
+}
+
+/// Builds a pad setting both components of a Float2 variable by dragging, finely with Shift
+/// held. A double click resets the variable to the default of the filter.
+pub fn create_float2_pad(
+    relm: &Relm<RenderStageConfigView>,
+    name: &str,
+    value: [f32; 2],
+    default_value: [f32; 2],
+    value_range: &DataRange,
+    automation_preview: &SharedAutomationPreview,
+) -> gtk::Box {
+    let (min_value, max_value, step) =
+        if let DataRange::FloatRange(min_value, max_value, step) = value_range {
+            (*min_value, *max_value, *step)
+        } else {
+            (-1.0, 1.0, 0.0001)
+        };
+
+    let drawing_area = DrawingArea::new();
+    drawing_area.set_size_request(-1, PAD_HEIGHT);
+    drawing_area.set_hexpand(true);
+    drawing_area.set_tooltip_text(Some(
+        "Drag to move, hold Shift for fine moves, double click to reset",
+    ));
+    drawing_area.add_events(
+        EventMask::BUTTON_PRESS_MASK
+            | EventMask::BUTTON_RELEASE_MASK
+            | EventMask::BUTTON1_MOTION_MASK,
+    );
+
+    let value_label = Label::new(None);
+    value_label.set_xalign(1.0);
+
+    let lfo_preview_label = Label::new(Some("LFO shape preview, not synced to the output"));
+    lfo_preview_label.set_xalign(0.0);
+    lfo_preview_label.set_no_show_all(true);
+
+    let pad = Rc::new(XyPad {
+        variable_name: name.to_string(),
+        min_value,
+        max_value,
+        step,
+        default_value: (default_value[0] as f64, default_value[1] as f64),
+
+        value: Cell::new((value[0] as f64, value[1] as f64)),
+        drag: Cell::new(None),
+
+        drawing_area: drawing_area.clone(),
+        value_label: value_label.clone(),
+        lfo_preview_label: lfo_preview_label.clone(),
+        automation_preview: automation_preview.clone(),
+    });
+    pad.update_value_label();
+
+    {
+        let pad = pad.clone();
+        drawing_area.connect_draw(move |_, cr| {
+            pad.draw(cr);
+            Inhibit(false)
+        });
+    }
+    {
+        let pad = pad.clone();
+        let relm = relm.clone();
+        drawing_area.connect_button_press_event(move |_, event| {
+            if event.get_button() != 1 {
+                return Inhibit(false);
+            }
+
+            if event.get_event_type() == EventType::DoubleButtonPress {
+                pad.drag.set(None);
+                pad.set_value(&relm, pad.default_value);
+                return Inhibit(true);
+            }
+
+            let is_fine = event.get_state().contains(ModifierType::SHIFT_MASK);
+            if !is_fine {
+                pad.set_value(&relm, pad.get_pointer_value(event.get_position()));
+            }
+            pad.drag.set(Some(Drag {
+                is_fine,
+                pointer_origin: event.get_position(),
+                value_origin: pad.value.get(),
+            }));
+
+            Inhibit(true)
+        });
+    }
+    {
+        let pad = pad.clone();
+        let relm = relm.clone();
+        drawing_area.connect_motion_notify_event(move |_, event| {
+            let mut drag = match pad.drag.get() {
+                Some(drag) => drag,
+                None => return Inhibit(false),
+            };
+
+            // Switching between fine and direct moves restarts the drag from where it is
+            let is_fine = event.get_state().contains(ModifierType::SHIFT_MASK);
+            if is_fine != drag.is_fine {
+                drag = Drag {
+                    is_fine,
+                    pointer_origin: event.get_position(),
+                    value_origin: pad.value.get(),
+                };
+                pad.drag.set(Some(drag));
+            }
+
+            let value = if drag.is_fine {
+                let pointer_value = pad.get_pointer_value(event.get_position());
+                let origin_value = pad.get_pointer_value(drag.pointer_origin);
+                (
+                    drag.value_origin.0 + (pointer_value.0 - origin_value.0) * FINE_DRAG_FACTOR,
+                    drag.value_origin.1 + (pointer_value.1 - origin_value.1) * FINE_DRAG_FACTOR,
+                )
+            } else {
+                pad.get_pointer_value(event.get_position())
+            };
+            pad.set_value(&relm, value);
+
+            Inhibit(true)
+        });
+    }
+    {
+        let pad = pad.clone();
+        drawing_area.connect_button_release_event(move |_, _| {
+            pad.drag.set(None);
+            Inhibit(false)
+        });
+    }
+
+    // Redrawn on every frame only while an LFO moves the dot. Like the handlers above, the
+    // callback is dropped along with the pad when the drawing area is destroyed
+    drawing_area.add_tick_callback(move |_, _| {
+        let is_automated = pad.is_automated();
+        if pad.lfo_preview_label.get_visible() != is_automated {
+            pad.lfo_preview_label.set_visible(is_automated);
+        }
+        if is_automated {
+            pad.drawing_area.queue_draw();
+        }
+        glib::Continue(true)
+    });
+
+    let wrapper = gtk::Box::new(Vertical, 2);
+    wrapper.add(&drawing_area);
+    wrapper.add(&value_label);
+    wrapper.add(&lfo_preview_label);
+
+    wrapper
+}
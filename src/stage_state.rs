@@ -0,0 +1,199 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+
+use wvr_com::data::{Message, RenderStageUpdate};
+use wvr_data::config::project_config::{FilterMode, ProjectConfig};
+
+use crate::config_panel::validation::{get_sampled_input_name, load_filter_list};
+use crate::stage_config::input::resample;
+
+pub const STAGE_STATE_FILE_NAME: &str = "stage_state.json";
+
+const BYPASS_FILTER_NAME: &str = "generic/copy";
+
+/// Launcher-side toggles of the render chain, the debugging ones being applied on top of the
+/// project config when rendering.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct StageState {
+    /// Names of the stages passing their first input through instead of applying their filter.
+    pub bypassed_stage_list: BTreeSet<String>,
+    /// Name of the stage sent straight to the final stage.
+    pub solo_stage: Option<String>,
+}
+
+impl StageState {
+    pub fn load(project_path: &Path) -> Self {
+        let stage_state_path = project_path.join(STAGE_STATE_FILE_NAME);
+        if !stage_state_path.exists() {
+            return Self::default();
+        }
+
+        match File::open(&stage_state_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(serde_json::from_reader::<File, Self>(file)?))
+        {
+            Ok(stage_state) => stage_state,
+            Err(e) => {
+                eprintln!("Failed to load stage state {:?}: {:?}", stage_state_path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<()> {
+        let stage_state_path = project_path.join(STAGE_STATE_FILE_NAME);
+
+        // An untouched chain leaves no file behind
+        if self == &Self::default() {
+            if stage_state_path.exists() {
+                std::fs::remove_file(&stage_state_path)?;
+            }
+            return Ok(());
+        }
+
+        let stage_state_as_bytes = serde_json::ser::to_string_pretty(self)?.into_bytes();
+
+        let mut stage_state_file = File::create(stage_state_path)?;
+        stage_state_file.write_all(&stage_state_as_bytes)?;
+
+        Ok(())
+    }
+
+    pub fn is_bypassed(&self, stage_name: &str) -> bool {
+        self.bypassed_stage_list.contains(stage_name)
+    }
+
+    pub fn is_solo(&self, stage_name: &str) -> bool {
+        self.solo_stage.as_deref() == Some(stage_name)
+    }
+
+    pub fn rename_stage(&mut self, old_name: &str, new_name: &str) {
+        if self.bypassed_stage_list.remove(old_name) {
+            self.bypassed_stage_list.insert(new_name.to_owned());
+        }
+        if self.is_solo(old_name) {
+            self.solo_stage = Some(new_name.to_owned());
+        }
+    }
+
+    pub fn remove_stage(&mut self, stage_name: &str) {
+        self.bypassed_stage_list.remove(stage_name);
+        if self.is_solo(stage_name) {
+            self.solo_stage = None;
+        }
+    }
+
+    /// Builds the config actually rendered, with bypassed stages copying their first input.
+    pub fn apply(&self, project_path: &Path, config: &ProjectConfig) -> ProjectConfig {
+        let mut config = config.clone();
+
+        if !self.bypassed_stage_list.is_empty() {
+            let available_filter_list = load_filter_list(project_path);
+
+            for stage in config
+                .render_chain
+                .iter_mut()
+                .filter(|stage| self.bypassed_stage_list.contains(&stage.name))
+            {
+                // Inputs declared by the filter come first, in declaration order
+                let mut uniform_name_list = available_filter_list
+                    .get(&stage.filter)
+                    .map(|(_, filter_config, _)| filter_config.inputs.clone())
+                    .unwrap_or_default();
+                let mut extra_uniform_name_list: Vec<String> = stage
+                    .inputs
+                    .keys()
+                    .filter(|uniform_name| !uniform_name_list.contains(uniform_name))
+                    .cloned()
+                    .collect();
+                extra_uniform_name_list.sort();
+                uniform_name_list.extend(extra_uniform_name_list);
+
+                let first_input = uniform_name_list
+                    .iter()
+                    .filter_map(|uniform_name| stage.inputs.get(uniform_name))
+                    .find(|sampled_input| !get_sampled_input_name(sampled_input).is_empty())
+                    .cloned();
+
+                stage.filter = BYPASS_FILTER_NAME.to_owned();
+                stage.filter_mode_params = FilterMode::Rectangle(0.0, 0.0, 1.0, 1.0);
+                stage.inputs.clear();
+                if let Some(first_input) = first_input {
+                    stage.inputs.insert("iChannel0".to_owned(), first_input);
+                }
+            }
+        }
+
+        if let Some(solo_stage) = self.get_solo_stage(&config).cloned() {
+            let final_stage_input =
+                resample(config.final_stage.inputs.get("iChannel0"), &solo_stage);
+            config
+                .final_stage
+                .inputs
+                .insert("iChannel0".to_owned(), final_stage_input);
+        }
+
+        config
+    }
+
+    fn get_solo_stage(&self, config: &ProjectConfig) -> Option<&String> {
+        self.solo_stage.as_ref().filter(|solo_stage| {
+            config
+                .render_chain
+                .iter()
+                .any(|stage| &stage.name == *solo_stage)
+        })
+    }
+
+    /// Adapts messages built from the project config so that they keep the toggles applied.
+    pub fn filter_message_list(
+        &self,
+        config: &ProjectConfig,
+        message_list: Vec<Message>,
+    ) -> Vec<Message> {
+        message_list
+            .into_iter()
+            .filter_map(|message| match message {
+                Message::UpdateRenderStage(stage_index, update) => {
+                    let is_bypassed = config
+                        .render_chain
+                        .get(stage_index)
+                        .map(|stage| self.is_bypassed(&stage.name))
+                        .unwrap_or(false);
+
+                    match update {
+                        RenderStageUpdate::Filter(_)
+                        | RenderStageUpdate::FilterModeParams(_)
+                        | RenderStageUpdate::Input(_, _)
+                            if is_bypassed =>
+                        {
+                            None
+                        }
+                        update => Some(Message::UpdateRenderStage(stage_index, update)),
+                    }
+                }
+                Message::UpdateFinalStage(RenderStageUpdate::Input(
+                    uniform_name,
+                    sampled_input,
+                )) => {
+                    let sampled_input = match self.get_solo_stage(config) {
+                        Some(solo_stage) => resample(Some(&sampled_input), solo_stage),
+                        None => sampled_input,
+                    };
+                    Some(Message::UpdateFinalStage(RenderStageUpdate::Input(
+                        uniform_name,
+                        sampled_input,
+                    )))
+                }
+                message => Some(message),
+            })
+            .collect()
+    }
+}
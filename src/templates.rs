@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use wvr_data::config::project_config::ProjectConfig;
+
+use crate::preferences::NewProjectDefaults;
+
+pub const TEMPLATE_DESCRIPTION_FILE_NAME: &str = "template_description.txt";
+
+const DEFAULT_TEMPLATE_NAME: &str = "Default";
+const DEFAULT_TEMPLATE_DESCRIPTION: &str =
+    "A dot pattern generator copied to the output, a minimal starting point.";
+
+pub struct ProjectTemplate {
+    pub name: String,
+    pub description: String,
+    /// Directory holding the template files, `None` for the built-in default project.
+    pub path: Option<PathBuf>,
+}
+
+pub fn get_templates_path() -> PathBuf {
+    wvr_data::get_data_path().join("templates")
+}
+
+fn load_template(template_path: &Path) -> Option<ProjectTemplate> {
+    if !template_path.join("config.json").is_file() {
+        return None;
+    }
+
+    let name = template_path.file_name()?.to_str()?.to_owned();
+    let description = std::fs::read_to_string(template_path.join(TEMPLATE_DESCRIPTION_FILE_NAME))
+        .map(|description| description.trim().to_owned())
+        .unwrap_or_default();
+
+    Some(ProjectTemplate {
+        name,
+        description,
+        path: Some(template_path.to_owned()),
+    })
+}
+
+/// Lists the built-in default template followed by the ones found in the templates folder.
+pub fn list_templates() -> Vec<ProjectTemplate> {
+    let mut template_list = Vec::new();
+
+    if let Ok(path_list) = std::fs::read_dir(get_templates_path()) {
+        for path in path_list {
+            if let Ok(path) = path {
+                if let Some(template) = load_template(&path.path()) {
+                    template_list.push(template);
+                }
+            }
+        }
+    }
+    template_list.sort_by_key(|template| template.name.to_lowercase());
+
+    template_list.insert(
+        0,
+        ProjectTemplate {
+            name: DEFAULT_TEMPLATE_NAME.to_owned(),
+            description: DEFAULT_TEMPLATE_DESCRIPTION.to_owned(),
+            path: None,
+        },
+    );
+
+    template_list
+}
+
+pub fn create_project_from_template(
+    template: &ProjectTemplate,
+    project_path: &Path,
+    defaults: &NewProjectDefaults,
+) -> Result<ProjectConfig> {
+    match &template.path {
+        Some(template_path) => {
+            let template_config =
+                crate::utils::load_project_config(&template_path.join("config.json"))?;
+
+            crate::utils::copy_project(template_path, project_path, &template_config)
+        }
+        None => crate::utils::create_project(project_path, defaults)
+            .ok_or_else(|| anyhow!("Could not create project {:?}", project_path)),
+    }
+}
+
+/// Stores a copy of a project in the templates folder, returns the path of the new template.
+pub fn save_as_template(
+    project_path: &Path,
+    project_config: &ProjectConfig,
+    template_name: &str,
+    description: &str,
+) -> Result<PathBuf> {
+    let template_name = template_name.trim();
+    if !crate::utils::is_valid_folder_name(template_name) {
+        bail!("{:?} is not a valid template name", template_name);
+    }
+
+    let template_path = get_templates_path().join(template_name);
+    if template_path.exists() {
+        bail!("A template named {:} already exists", template_name);
+    }
+
+    std::fs::create_dir_all(get_templates_path())?;
+    crate::utils::copy_project(project_path, &template_path, project_config)?;
+
+    let mut description_file = File::create(template_path.join(TEMPLATE_DESCRIPTION_FILE_NAME))?;
+    description_file.write_all(description.trim().as_bytes())?;
+
+    Ok(template_path)
+}
@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use glium::texture::RawImage2d;
+
+use gtk::{
+    AspectFrame, ContainerExt, FrameExt, IconSize, Image, ShadowType, StyleContextExt, WidgetExt,
+};
+
+pub const THUMBNAIL_FILE_NAME: &str = "thumbnail.png";
+
+const THUMBNAIL_WIDTH: i32 = 192;
+const THUMBNAIL_HEIGHT: i32 = 108;
+
+/// Size to render the output at for its thumbnail, keeping its aspect ratio.
+pub fn get_thumbnail_render_size(output_width: u32, output_height: u32) -> (u32, u32) {
+    let scale = get_fit_scale(
+        output_width as f64,
+        output_height as f64,
+        THUMBNAIL_WIDTH as f64,
+        THUMBNAIL_HEIGHT as f64,
+    );
+
+    (
+        ((output_width as f64 * scale).round() as u32).max(1),
+        ((output_height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+/// Uses a rendered frame of the output, bottom row first, as the project thumbnail.
+pub fn save_thumbnail(project_path: &Path, pixels: &RawImage2d<u8>) -> Result<()> {
+    let image =
+        crate::wvr_frame::preview::build_image_surface(&pixels.data, pixels.width, pixels.height)?;
+    let surface = build_letterboxed_surface(&image, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)?;
+
+    write_thumbnail(&surface, project_path)
+}
+
+/// Uses a captured image, scaled down, as the project thumbnail.
+pub fn set_thumbnail_from_file(project_path: &Path, image_path: &Path) -> Result<()> {
+    let surface = load_scaled_png(image_path, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)?;
+
+    write_thumbnail(&surface, project_path)
+}
+
+fn write_thumbnail(surface: &cairo::ImageSurface, project_path: &Path) -> Result<()> {
+    let thumbnail_tmp_path = project_path.join(format!("{:}.tmp", THUMBNAIL_FILE_NAME));
+    let mut thumbnail_file = File::create(&thumbnail_tmp_path)?;
+    surface
+        .write_to_png(&mut thumbnail_file)
+        .map_err(|e| anyhow!("Failed to encode thumbnail: {:?}", e))?;
+    std::fs::rename(&thumbnail_tmp_path, project_path.join(THUMBNAIL_FILE_NAME))?;
+
+    Ok(())
+}
+
+// Largest scale fitting the source size in the target size
+fn get_fit_scale(source_width: f64, source_height: f64, width: f64, height: f64) -> f64 {
+    (width / source_width).min(height / source_height)
+}
+
+/// Draws an image scaled to fit the given size, centered on black bars.
+fn build_letterboxed_surface(
+    image: &cairo::ImageSurface,
+    width: i32,
+    height: i32,
+) -> Result<cairo::ImageSurface> {
+    let (image_width, image_height) = (image.get_width() as f64, image.get_height() as f64);
+    let scale = get_fit_scale(image_width, image_height, width as f64, height as f64);
+
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    {
+        let cairo_context = cairo::Context::new(&surface);
+        cairo_context.set_source_rgb(0.0, 0.0, 0.0);
+        cairo_context.paint();
+
+        cairo_context.translate(
+            (width as f64 - image_width * scale) / 2.0,
+            (height as f64 - image_height * scale) / 2.0,
+        );
+        cairo_context.scale(scale, scale);
+        cairo_context.set_source_surface(image, 0.0, 0.0);
+        cairo_context.paint();
+    }
+
+    Ok(surface)
+}
+
+/// Loads a PNG image scaled to fit the given size, keeping its aspect ratio.
+pub fn load_scaled_png(image_path: &Path, width: i32, height: i32) -> Result<cairo::ImageSurface> {
+    let mut image_file = File::open(image_path)?;
+    let image = cairo::ImageSurface::create_from_png(&mut image_file)
+        .map_err(|e| anyhow!("Failed to decode {:?}: {:?}", image_path, e))?;
+    if image.get_width() <= 0 || image.get_height() <= 0 {
+        bail!("{:?} is empty", image_path);
+    }
+
+    build_letterboxed_surface(&image, width, height)
+}
+
+/// Builds the thumbnail of a project, or a placeholder if it does not have one yet.
+pub fn build_thumbnail_view(project_path: &Path) -> AspectFrame {
+    let thumbnail_wrapper = AspectFrame::new(
+        None,
+        0.5,
+        0.5,
+        THUMBNAIL_WIDTH as f32 / THUMBNAIL_HEIGHT as f32,
+        false,
+    );
+    thumbnail_wrapper.set_size_request(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+    thumbnail_wrapper.set_shadow_type(ShadowType::EtchedIn);
+
+    let thumbnail_path = project_path.join(THUMBNAIL_FILE_NAME);
+    let thumbnail = if thumbnail_path.is_file() {
+        Image::from_file(&thumbnail_path)
+    } else {
+        let placeholder = Image::from_icon_name(Some("image-x-generic"), IconSize::Dialog);
+        placeholder.get_style_context().add_class("dim-label");
+        placeholder
+    };
+
+    thumbnail_wrapper.add(&thumbnail);
+
+    thumbnail_wrapper
+}
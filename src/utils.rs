@@ -1,17 +1,30 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
+
+use anyhow::{bail, Context, Result};
 
 use nfd2::Response;
 
+use serde_json::error::Category;
+use serde_json::Value;
+
 use wvr_data::config::project_config::{
-    BufferPrecision, FilterMode, ProjectConfig, RenderStageConfig, SampledInput, ViewConfig,
+    BufferPrecision, FilterMode, InputConfig, ProjectConfig, RenderStageConfig, SampledInput,
+    ViewConfig,
 };
 use wvr_data::config::server_config::ServerConfig;
 
-pub fn create_project(project_config_path: &Path) -> Option<ProjectConfig> {
+use crate::migration;
+use crate::preferences::NewProjectDefaults;
+
+pub fn create_project(
+    project_config_path: &Path,
+    defaults: &NewProjectDefaults,
+) -> Option<ProjectConfig> {
     std::fs::create_dir_all(&project_config_path).unwrap();
     std::fs::create_dir_all(&project_config_path.join("filters")).unwrap();
 
@@ -40,17 +53,17 @@ pub fn create_project(project_config_path: &Path) -> Option<ProjectConfig> {
     };
 
     let project_config = ProjectConfig {
-        bpm: 89.0,
+        bpm: defaults.bpm,
         view: ViewConfig {
-            width: 640,
-            height: 480,
+            width: defaults.width,
+            height: defaults.height,
             fullscreen: false,
             dynamic: true,
             vsync: true,
             screenshot: false,
             screenshot_path: PathBuf::from("output/"),
             screenshot_frame_count: -1,
-            target_fps: 60.0,
+            target_fps: defaults.target_fps,
             locked_speed: false,
         },
         server: ServerConfig {
@@ -62,45 +75,321 @@ pub fn create_project(project_config_path: &Path) -> Option<ProjectConfig> {
         render_chain: vec![patterns_stage],
         final_stage,
     };
-    if let Ok(mut project_config_file) =
-        std::fs::File::create(&project_config_path.join("config.json"))
-    {
-        project_config_file
-            .write_all(
-                &serde_json::ser::to_string_pretty(&project_config)
-                    .unwrap()
-                    .into_bytes(),
-            )
-            .unwrap();
-
+    if save_config(&project_config_path.join("config.json"), &project_config).is_ok() {
         Some(project_config)
     } else {
         None
     }
 }
 
-pub fn get_config() -> std::option::Option<(PathBuf, ProjectConfig)> {
-    let wvr_data_path = wvr_data::get_data_path();
+pub fn save_config(config_path: &Path, project_config: &ProjectConfig) -> Result<()> {
+    let mut config = serde_json::to_value(project_config)?;
+    migration::set_schema_version(&mut config);
 
-    let mut config_path = None;
-    let projects_path = wvr_data_path.join("projects");
+    let config_as_bytes = serde_json::ser::to_string_pretty(&config)?.into_bytes();
 
-    while config_path.is_none() {
-        match nfd2::open_file_dialog(None, Some(&projects_path)).expect("oh no") {
-            Response::Okay(file_path) => config_path = Some(file_path),
-            Response::OkayMultiple(_) => (),
-            Response::Cancel => return None,
+    migration::backup_outdated_config(config_path)?;
+
+    let mut project_config_file = File::create(config_path)?;
+    project_config_file.write_all(&config_as_bytes)?;
+
+    Ok(())
+}
+
+/// Describes why a project config could not be deserialized, and where the problem is located.
+#[derive(Debug)]
+pub struct ConfigLoadError {
+    pub config_path: PathBuf,
+    pub kind: &'static str,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub line_content: Option<String>,
+}
+
+impl fmt::Display for ConfigLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(
+                f,
+                "{:} in {:?} at line {:}, column {:}: {:}",
+                self.kind, self.config_path, self.line, self.column, self.message
+            )
+        } else {
+            write!(
+                f,
+                "{:} in {:?}: {:}",
+                self.kind, self.config_path, self.message
+            )
         }
     }
+}
+
+impl std::error::Error for ConfigLoadError {}
+
+impl ConfigLoadError {
+    fn new(config_path: &Path, config_content: &str, error: serde_json::Error) -> Self {
+        let message = error.to_string();
+        let kind = match error.classify() {
+            Category::Io => "Read error",
+            Category::Syntax => "Malformed JSON",
+            Category::Eof => "Unexpected end of file",
+            Category::Data => {
+                if message.starts_with("unknown variant") {
+                    "Unknown variant"
+                } else if message.starts_with("missing field") {
+                    "Missing field"
+                } else if message.starts_with("unknown field") {
+                    "Unknown field"
+                } else {
+                    "Invalid value"
+                }
+            }
+        };
 
-    let config_path = config_path.unwrap();
+        // The location is reported separately
+        let location_suffix = format!(" at line {:} column {:}", error.line(), error.column());
+        let message = message
+            .strip_suffix(&location_suffix)
+            .unwrap_or(&message)
+            .to_owned();
 
-    let project_path = config_path.parent().unwrap().to_owned();
-    let config: ProjectConfig = if let Ok(file) = File::open(&config_path) {
-        serde_json::from_reader::<File, ProjectConfig>(file).unwrap()
+        let line_content = if error.line() > 0 {
+            config_content
+                .lines()
+                .nth(error.line() - 1)
+                .map(|line| line.to_owned())
+        } else {
+            None
+        };
+
+        Self {
+            config_path: config_path.to_owned(),
+            kind,
+            message,
+            line: error.line(),
+            column: error.column(),
+            line_content,
+        }
+    }
+}
+
+pub fn load_project_config(config_path: &Path) -> Result<ProjectConfig> {
+    let config_content = std::fs::read_to_string(config_path)
+        .with_context(|| format!("Could not read {:?}", config_path))?;
+
+    let mut config: Value = serde_json::from_str(&config_content)
+        .map_err(|error| ConfigLoadError::new(config_path, &config_content, error))?;
+
+    // Deserializing from the file content keeps the location of errors
+    if migration::migrate(&mut config).is_empty() {
+        serde_json::from_str::<ProjectConfig>(&config_content)
+            .map_err(|error| ConfigLoadError::new(config_path, &config_content, error).into())
     } else {
-        panic!("Could not find config file {:?}", project_path);
+        serde_json::from_value::<ProjectConfig>(config)
+            .map_err(|error| ConfigLoadError::new(config_path, &config_content, error).into())
+    }
+}
+
+fn copy_directory(
+    source_path: &Path,
+    target_path: &Path,
+    excluded_file_names: &[&str],
+) -> Result<()> {
+    std::fs::create_dir_all(target_path)?;
+
+    for entry in std::fs::read_dir(source_path)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+
+        if let Some(file_name) = entry.file_name().to_str() {
+            if excluded_file_names.contains(&file_name) {
+                continue;
+            }
+        }
+
+        let target_entry_path = target_path.join(entry.file_name());
+        if entry_path.is_dir() {
+            copy_directory(&entry_path, &target_entry_path, excluded_file_names)?;
+        } else {
+            std::fs::copy(&entry_path, &target_entry_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Names of projects and templates are used as folder names, they must stay a single folder of
+/// the folder they are created in.
+pub fn is_valid_folder_name(folder_name: &str) -> bool {
+    if folder_name.contains(&['/', '\\'][..]) {
+        return false;
+    }
+
+    let mut component_list = Path::new(folder_name).components();
+    matches!(
+        (component_list.next(), component_list.next()),
+        (Some(Component::Normal(component)), None) if component == folder_name
+    )
+}
+
+/// Folder of a copied project receiving the media that was stored outside of the original.
+const IMPORTED_MEDIA_FOLDER_NAME: &str = "media";
+
+/// Resolves a path which may not exist yet, through its closest existing ancestor.
+fn canonicalize_new_path(path: &Path) -> PathBuf {
+    let mut missing_component_list = Vec::new();
+    let mut existing_path = path;
+
+    loop {
+        if let Ok(canonical_path) = existing_path.canonicalize() {
+            return missing_component_list
+                .iter()
+                .rev()
+                .fold(canonical_path, |canonical_path, component| {
+                    canonical_path.join(component)
+                });
+        }
+
+        match (existing_path.parent(), existing_path.file_name()) {
+            (Some(parent_path), Some(file_name)) => {
+                missing_component_list.push(file_name);
+                existing_path = parent_path;
+            }
+            _ => return path.to_owned(),
+        }
+    }
+}
+
+/// Path of the copied project to store an imported media at, not overwriting another one.
+fn get_imported_media_path(target_project_path: &Path, media_path: &Path) -> PathBuf {
+    let media_folder_path = target_project_path.join(IMPORTED_MEDIA_FOLDER_NAME);
+
+    let file_stem = media_path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "media".to_owned());
+    let extension = media_path
+        .extension()
+        .map(|extension| format!(".{:}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut imported_media_path = media_folder_path.join(format!("{:}{:}", file_stem, extension));
+    let mut copy_index = 1;
+    while imported_media_path.exists() {
+        imported_media_path =
+            media_folder_path.join(format!("{:}_{:}{:}", file_stem, copy_index, extension));
+        copy_index += 1;
+    }
+
+    imported_media_path
+}
+
+/// Copies an input media stored outside of the source project into the copied one, returning
+/// the path to use in the copied config.
+fn import_input_media(
+    source_project_path: &Path,
+    target_project_path: &Path,
+    path: &str,
+) -> Result<String> {
+    // Missing media cannot be copied, relative paths keep pointing where the original did for
+    // the user to fix
+    let absolute_input_path = match source_project_path.join(path).canonicalize() {
+        Ok(absolute_input_path) => absolute_input_path,
+        Err(_) if !path.is_empty() && Path::new(path).is_relative() => {
+            return Ok(source_project_path.join(path).to_string_lossy().to_string());
+        }
+        Err(_) => return Ok(path.to_string()),
     };
 
-    Some((project_path, config))
+    // Media stored inside the project directory is copied along with it
+    if absolute_input_path.starts_with(&source_project_path) {
+        if Path::new(path).is_absolute() {
+            return Ok(absolute_input_path
+                .strip_prefix(&source_project_path)
+                .unwrap_or(&absolute_input_path)
+                .to_string_lossy()
+                .to_string());
+        }
+        return Ok(path.to_string());
+    }
+
+    let imported_media_path = get_imported_media_path(target_project_path, &absolute_input_path);
+    if absolute_input_path.is_dir() {
+        copy_directory(&absolute_input_path, &imported_media_path, &[])?;
+    } else {
+        std::fs::create_dir_all(target_project_path.join(IMPORTED_MEDIA_FOLDER_NAME))?;
+        std::fs::copy(&absolute_input_path, &imported_media_path)
+            .with_context(|| format!("Failed to copy {:?}", absolute_input_path))?;
+    }
+
+    Ok(imported_media_path
+        .strip_prefix(target_project_path)
+        .unwrap_or(&imported_media_path)
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Copies a project directory (filters, media, ...) and writes the given config in the copy.
+pub fn copy_project(
+    source_project_path: &Path,
+    target_project_path: &Path,
+    project_config: &ProjectConfig,
+) -> Result<ProjectConfig> {
+    if target_project_path.exists() {
+        bail!("{:?} already exists", target_project_path);
+    }
+
+    // Symbolic links and `..` components would hide a target inside the source
+    let source_project_path = source_project_path.canonicalize()?;
+    if canonicalize_new_path(target_project_path).starts_with(&source_project_path) {
+        bail!("A project cannot be copied inside itself");
+    }
+
+    copy_directory(
+        &source_project_path,
+        target_project_path,
+        &[
+            "config.tmp.json",
+            crate::recovery::AUTOSAVE_FILE_NAME,
+            crate::recovery::JOURNAL_FILE_NAME,
+            crate::templates::TEMPLATE_DESCRIPTION_FILE_NAME,
+        ],
+    )?;
+
+    let mut project_config = project_config.clone();
+    for input_config in project_config.inputs.values_mut() {
+        match input_config {
+            InputConfig::Video { path, .. } | InputConfig::Picture { path, .. } => {
+                *path = import_input_media(&source_project_path, target_project_path, path)?;
+            }
+            InputConfig::Cam { .. } | InputConfig::Midi { .. } => (),
+        }
+    }
+
+    save_config(&target_project_path.join("config.json"), &project_config)?;
+
+    Ok(project_config)
+}
+
+/// Asks for a project config file, returns the directory of the selected project.
+pub fn get_project_path() -> Option<PathBuf> {
+    let wvr_data_path = wvr_data::get_data_path();
+
+    let mut config_path = None;
+    let projects_path = wvr_data_path.join("projects");
+
+    while config_path.is_none() {
+        match nfd2::open_file_dialog(None, Some(&projects_path)) {
+            Ok(Response::Okay(file_path)) => config_path = Some(file_path),
+            Ok(Response::OkayMultiple(_)) => (),
+            Ok(Response::Cancel) => return None,
+            Err(e) => {
+                eprintln!("Failed to open file dialog: {:?}", e);
+                return None;
+            }
+        }
+    }
+
+    config_path?.parent().map(Path::to_owned)
 }
@@ -1,17 +1,26 @@
 use gtk::Orientation::{Horizontal, Vertical};
 use gtk::{
-    Adjustment, ContainerExt, EditableSignals, EntryExt, Label, SpinButton, Switch, SwitchExt,
-    WidgetExt,
+    Adjustment, Button, ButtonExt, ContainerExt, EditableSignals, Entry, EntryExt, Inhibit, Label,
+    SpinButton, Switch, SwitchExt, WidgetExt,
 };
 
+use nfd2::Response;
+
+use std::path::{Path, PathBuf};
+
 use relm::{connect, Relm};
 
 use crate::config_panel::msg::ConfigPanelMsg;
 use crate::config_panel::view::ConfigPanel;
+use crate::gallery;
 
 use wvr_data::config::project_config::ViewConfig;
 
-pub fn build_view(relm: &Relm<ConfigPanel>, view_config: &ViewConfig) -> gtk::Box {
+pub fn build_view(
+    relm: &Relm<ConfigPanel>,
+    project_path: &Path,
+    view_config: &ViewConfig,
+) -> gtk::Box {
     let view_config_container = gtk::Box::new(Vertical, 2);
     view_config_container.set_property_margin(8);
 
@@ -151,6 +160,94 @@ pub fn build_view(relm: &Relm<ConfigPanel>, view_config: &ViewConfig) -> gtk::Bo
     screenshot_row.add(&padding);
     screenshot_row.add(&screenshot_switch);
 
+    // Recording folder row creation
+    let screenshot_path_row = gtk::Box::new(Horizontal, 8);
+
+    let screenshot_path_entry = Entry::new();
+    screenshot_path_entry.set_hexpand(true);
+    screenshot_path_entry.set_text(view_config.screenshot_path.to_str().unwrap_or_default());
+    screenshot_path_entry.set_tooltip_text(Some(
+        "Relative paths start at the project folder, a running project keeps recording to the folder it started with",
+    ));
+    connect!(
+        relm,
+        screenshot_path_entry,
+        connect_changed(val),
+        Some(ConfigPanelMsg::SetScreenshotPath(
+            val.get_text().to_string()
+        ))
+    );
+    // The gallery only follows the folder once it is typed in, not on each key press
+    connect!(
+        relm,
+        screenshot_path_entry,
+        connect_activate(_),
+        ConfigPanelMsg::RefreshCaptureGallery
+    );
+    connect!(
+        relm,
+        screenshot_path_entry,
+        connect_focus_out_event(_, _),
+        return (Some(ConfigPanelMsg::RefreshCaptureGallery), Inhibit(false))
+    );
+
+    let screenshot_path_button = Button::with_label("…");
+    {
+        let screenshot_path_entry = screenshot_path_entry.clone();
+        let project_path: PathBuf = project_path.to_owned();
+        let stream = relm.stream().clone();
+        screenshot_path_button.connect_clicked(move |_| match nfd2::open_pick_folder(None) {
+            Ok(Response::Okay(folder_path)) => {
+                // The entry shows the folder as it is stored, relative to the project
+                let folder_path = gallery::get_stored_capture_folder(&project_path, &folder_path);
+                screenshot_path_entry.set_text(folder_path.to_str().unwrap_or_default());
+                stream.emit(ConfigPanelMsg::RefreshCaptureGallery);
+            }
+            Ok(_) => (),
+            Err(e) => eprintln!("Failed to open folder dialog: {:?}", e),
+        });
+    }
+
+    screenshot_path_row.add(&Label::new(Some(
+        "Recording folder (applies on next start)",
+    )));
+    screenshot_path_row.add(&screenshot_path_entry);
+    screenshot_path_row.add(&screenshot_path_button);
+
+    // Recorded frame count row creation
+    let screenshot_frame_count_row = gtk::Box::new(Horizontal, 8);
+
+    let padding = gtk::Box::new(Horizontal, 0);
+    padding.set_hexpand(true);
+
+    let screenshot_frame_count_spin_button = SpinButton::new(
+        Some(&Adjustment::new(
+            view_config.screenshot_frame_count as f64,
+            -1.0,
+            1_000_000.0,
+            1.0,
+            10.0,
+            10.0,
+        )),
+        1.0,
+        0,
+    );
+    screenshot_frame_count_spin_button.set_tooltip_text(Some("-1 records until disabled"));
+    connect!(
+        relm,
+        screenshot_frame_count_spin_button,
+        connect_changed(val),
+        if let Ok(value) = val.get_text().as_str().replace(',', ".").parse::<f64>() {
+            Some(ConfigPanelMsg::SetScreenshotFrameCount(value as i64))
+        } else {
+            None
+        }
+    );
+
+    screenshot_frame_count_row.add(&Label::new(Some("Recorded frames (applies on next start)")));
+    screenshot_frame_count_row.add(&padding);
+    screenshot_frame_count_row.add(&screenshot_frame_count_spin_button);
+
     // Locked speed activation row creation
     let locked_speed_row = gtk::Box::new(Horizontal, 8);
 
@@ -200,6 +297,8 @@ pub fn build_view(relm: &Relm<ConfigPanel>, view_config: &ViewConfig) -> gtk::Bo
     view_config_container.add(&dynamic_size_row);
     view_config_container.add(&vsync_row);
     view_config_container.add(&screenshot_row);
+    view_config_container.add(&screenshot_path_row);
+    view_config_container.add(&screenshot_frame_count_row);
 
     view_config_container
 }
@@ -1,19 +1,97 @@
-use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use gtk::Orientation::{Horizontal, Vertical};
 use gtk::{
-    Adjustment, Align, Button, ButtonExt, ContainerExt, Grid, GridExt, Label, LabelExt,
-    OrientableExt, PolicyType, ScrolledWindow, ScrolledWindowExt, Separator, WidgetExt,
+    Adjustment, Align, Button, ButtonExt, ComboBoxExt, ComboBoxText, ComboBoxTextExt, ContainerExt,
+    EditableSignals, Entry, EntryExt, FlowBox, FlowBoxExt, Grid, GridExt, Label, LabelExt,
+    OrientableExt, PolicyType, ScrolledWindow, ScrolledWindowExt, SelectionMode, Separator,
+    StyleContextExt, WidgetExt,
 };
 
-use relm::{connect, Relm};
+use relm::{connect, Relm, Update, Widget};
+use relm_derive::Msg;
 
 use wvr_data::config::project_config::ProjectConfig;
 
+use crate::main_window::MainWindow;
 use crate::main_window::Msg;
 
-use crate::main_window::MainWindow;
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProjectSortOrder {
+    Name,
+    LastModified,
+    StageCount,
+}
+
+impl ProjectSortOrder {
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "name" => Some(Self::Name),
+            "last_modified" => Some(Self::LastModified),
+            "stage_count" => Some(Self::StageCount),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Msg)]
+pub enum WelcomePanelMsg {
+    Refresh,
+    SetSearchFilter(String),
+    SetSortOrder(ProjectSortOrder),
+    SetRecentProjectList(Vec<PathBuf>),
+}
+
+pub struct ProjectInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub modified: Option<SystemTime>,
+    /// `None` if the project config could not be read.
+    pub config: Option<ProjectConfig>,
+}
+
+impl ProjectInfo {
+    fn load(project_path: &Path) -> Self {
+        let config_path = project_path.join("config.json");
+
+        let name = project_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_owned();
+        let modified = std::fs::metadata(&config_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let config = crate::utils::load_project_config(&config_path).ok();
+
+        Self {
+            path: project_path.to_owned(),
+            name,
+            modified,
+            config,
+        }
+    }
+
+    fn matches(&self, search_filter: &str) -> bool {
+        let search_filter = search_filter.trim().to_lowercase();
+
+        search_filter.is_empty()
+            || self.name.to_lowercase().contains(&search_filter)
+            || self
+                .path
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&search_filter)
+    }
+
+    fn get_stage_count(&self) -> usize {
+        self.config
+            .as_ref()
+            .map(|config| config.render_chain.len())
+            .unwrap_or(0)
+    }
+}
 
 fn list_projects(projects_folder_path: &Path) -> Vec<PathBuf> {
     let mut is_project = false;
@@ -51,6 +129,17 @@ fn list_projects(projects_folder_path: &Path) -> Vec<PathBuf> {
     available_projects_list_widget
 }
 
+fn format_elapsed_time(time: SystemTime) -> String {
+    let elapsed_seconds = time.elapsed().map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+
+    match elapsed_seconds {
+        0..=59 => "just now".to_owned(),
+        60..=3599 => format!("{:} minute(s) ago", elapsed_seconds / 60),
+        3600..=86399 => format!("{:} hour(s) ago", elapsed_seconds / 3600),
+        _ => format!("{:} day(s) ago", elapsed_seconds / 86400),
+    }
+}
+
 pub fn build_description_panel() -> gtk::Box {
     let description_panel = gtk::Box::new(Vertical, 4);
 
@@ -67,75 +156,395 @@ pub fn build_description_panel() -> gtk::Box {
 
     description_panel
 }
-pub fn build_available_projects_panel(relm: &Relm<MainWindow>) -> gtk::Box {
-    let available_projects_panel = gtk::Box::new(Vertical, 4);
-    available_projects_panel.set_hexpand(true);
 
-    let available_projects_label = Label::new(Some("Available projects"));
-    available_projects_label.set_xalign(0.0);
+fn build_project_info_panel(project_info: &ProjectInfo, projects_folder_path: &Path) -> gtk::Box {
+    let project_info_panel = gtk::Box::new(Vertical, 2);
+    project_info_panel.set_hexpand(true);
 
-    let available_projects_list_panel = Grid::new();
+    let project_name_label = Label::new(None);
+    project_name_label.set_markup(&format!(
+        "<b>{:}</b>",
+        glib::markup_escape_text(&project_info.name)
+    ));
+    project_name_label.set_xalign(0.0);
+
+    let project_path = project_info
+        .path
+        .strip_prefix(projects_folder_path)
+        .unwrap_or(&project_info.path);
+    let project_path_label = Label::new(project_path.to_str());
+    project_path_label.set_xalign(0.0);
+    project_path_label
+        .get_style_context()
+        .add_class("dim-label");
+
+    let mut details = match &project_info.config {
+        Some(config) => format!(
+            "{:} stage(s), {:} input(s), {:} BPM",
+            config.render_chain.len(),
+            config.inputs.len(),
+            config.bpm
+        ),
+        None => "Unreadable project config".to_owned(),
+    };
+    if let Some(modified) = project_info.modified {
+        details.push_str(&format!(", modified {:}", format_elapsed_time(modified)));
+    }
+    let project_details_label = Label::new(Some(&details));
+    project_details_label.set_xalign(0.0);
+
+    project_info_panel.add(&project_name_label);
+    project_info_panel.add(&project_path_label);
+    project_info_panel.add(&project_details_label);
+
+    project_info_panel
+}
+
+pub struct WelcomePanelModel {
+    parent_relm: Relm<MainWindow>,
+    recent_project_list: Vec<PathBuf>,
+    project_info_list: Vec<ProjectInfo>,
+    search_filter: String,
+    sort_order: ProjectSortOrder,
+}
+
+pub struct WelcomePanel {
+    model: WelcomePanelModel,
+    root: gtk::Box,
+
+    recent_projects_panel: gtk::Box,
+    recent_project_list_panel: FlowBox,
+    project_list_panel: Grid,
+}
+
+impl WelcomePanel {
+    fn load_project_info_list(&mut self) {
+        self.model.project_info_list = list_projects(&wvr_data::get_data_path().join("projects"))
+            .iter()
+            .map(|project_path| ProjectInfo::load(project_path))
+            .collect();
+    }
+
+    fn sort_project_info_list(&mut self) {
+        match self.model.sort_order {
+            ProjectSortOrder::Name => self
+                .model
+                .project_info_list
+                .sort_by_key(|project_info| project_info.name.to_lowercase()),
+            ProjectSortOrder::LastModified => self
+                .model
+                .project_info_list
+                .sort_by(|a, b| b.modified.cmp(&a.modified)),
+            ProjectSortOrder::StageCount => self
+                .model
+                .project_info_list
+                .sort_by(|a, b| b.get_stage_count().cmp(&a.get_stage_count())),
+        }
+    }
+
+    fn rebuild_recent_project_list(&self) {
+        for child in &self.recent_project_list_panel.get_children() {
+            self.recent_project_list_panel.remove(child);
+        }
+
+        let parent_relm = &self.model.parent_relm;
+        for project_path in &self.model.recent_project_list {
+            let recent_project_button = Button::new();
+            recent_project_button.set_label(
+                project_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or(""),
+            );
+            recent_project_button.set_tooltip_text(project_path.to_str());
+
+            let project_path = project_path.clone();
+            connect!(
+                parent_relm,
+                recent_project_button,
+                connect_clicked(_),
+                Msg::OpenProject(project_path.clone())
+            );
+
+            self.recent_project_list_panel.add(&recent_project_button);
+        }
+
+        self.recent_project_list_panel.show_all();
+        self.recent_projects_panel
+            .set_visible(!self.model.recent_project_list.is_empty());
+    }
 
-    available_projects_list_panel.set_hexpand(true);
-    available_projects_list_panel.set_row_spacing(4);
-    available_projects_list_panel.set_column_spacing(8);
-    available_projects_list_panel.set_orientation(Vertical);
+    fn rebuild_project_list(&self) {
+        for child in &self.project_list_panel.get_children() {
+            self.project_list_panel.remove(child);
+        }
 
-    let available_projects_list = list_projects(&wvr_data::get_data_path().join("projects"));
+        let projects_folder_path = wvr_data::get_data_path().join("projects");
+        let parent_relm = &self.model.parent_relm;
 
-    let mut row_index = 0;
-    for project_path in &available_projects_list {
-        if let Some(project_path_as_str) = project_path.to_str() {
-            let project_path_label = Label::new(Some(project_path_as_str));
-            project_path_label.set_hexpand(true);
-            project_path_label.set_xalign(0.0);
+        let mut row_index = 0;
+        for project_info in &self.model.project_info_list {
+            if !project_info.matches(&self.model.search_filter) {
+                continue;
+            }
 
             let load_project_button = Button::new();
             load_project_button.set_label("Load");
             load_project_button.set_property_margin(4);
+            load_project_button.set_valign(Align::Center);
 
-            available_projects_list_panel.attach(&load_project_button, 0, row_index, 1, 1);
-            available_projects_list_panel.attach(&project_path_label, 1, row_index, 1, 1);
+            let rename_project_button = Button::new();
+            rename_project_button.set_label("Rename");
+            rename_project_button.set_valign(Align::Center);
 
-            let project_path = project_path.clone();
-            connect!(relm, load_project_button, connect_clicked(_), {
-                let config_path = project_path.join("config.json");
-
-                if let Ok(file) = File::open(&config_path) {
-                    if let Ok(project_config) = serde_json::from_reader::<File, ProjectConfig>(file)
-                    {
-                        Some(Msg::OpenProject(project_path.clone(), project_config))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            });
+            let duplicate_project_button = Button::new();
+            duplicate_project_button.set_label("Duplicate");
+            duplicate_project_button.set_valign(Align::Center);
+
+            let reveal_project_button = Button::new();
+            reveal_project_button.set_label("Show in folder");
+            reveal_project_button.set_valign(Align::Center);
+
+            let trash_project_button = Button::new();
+            trash_project_button.set_label("Delete");
+            trash_project_button.set_valign(Align::Center);
+
+            self.project_list_panel
+                .attach(&load_project_button, 0, row_index, 1, 1);
+            self.project_list_panel.attach(
+                &crate::thumbnail::build_thumbnail_view(&project_info.path),
+                1,
+                row_index,
+                1,
+                1,
+            );
+            self.project_list_panel.attach(
+                &build_project_info_panel(project_info, &projects_folder_path),
+                2,
+                row_index,
+                1,
+                1,
+            );
+            self.project_list_panel
+                .attach(&rename_project_button, 3, row_index, 1, 1);
+            self.project_list_panel
+                .attach(&duplicate_project_button, 4, row_index, 1, 1);
+            self.project_list_panel
+                .attach(&reveal_project_button, 5, row_index, 1, 1);
+            self.project_list_panel
+                .attach(&trash_project_button, 6, row_index, 1, 1);
+
+            let project_path = project_info.path.clone();
+            connect!(
+                parent_relm,
+                load_project_button,
+                connect_clicked(_),
+                Msg::OpenProject(project_path.clone())
+            );
+
+            let project_path = project_info.path.clone();
+            connect!(
+                parent_relm,
+                rename_project_button,
+                connect_clicked(_),
+                Msg::RenameProject(project_path.clone())
+            );
+
+            let project_path = project_info.path.clone();
+            connect!(
+                parent_relm,
+                duplicate_project_button,
+                connect_clicked(_),
+                Msg::DuplicateProject(project_path.clone())
+            );
+
+            let project_path = project_info.path.clone();
+            connect!(
+                parent_relm,
+                reveal_project_button,
+                connect_clicked(_),
+                Msg::RevealProject(project_path.clone())
+            );
+
+            let project_path = project_info.path.clone();
+            connect!(
+                parent_relm,
+                trash_project_button,
+                connect_clicked(_),
+                Msg::TrashProject(project_path.clone())
+            );
 
             row_index += 1;
         }
+
+        if row_index == 0 {
+            let empty_list_label = Label::new(Some(if self.model.project_info_list.is_empty() {
+                "No project found"
+            } else {
+                "No project matches the search"
+            }));
+            empty_list_label.set_hexpand(true);
+            empty_list_label.set_property_margin(16);
+            self.project_list_panel
+                .attach(&empty_list_label, 0, 0, 7, 1);
+        }
+
+        self.project_list_panel.show_all();
     }
+}
 
-    let available_projects_list_wrapper = ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
-    //available_projects_list_wrapper.set_size_request(0, 240);
-    available_projects_list_wrapper.set_policy(PolicyType::Never, PolicyType::Automatic);
-    available_projects_list_wrapper.set_hexpand(true);
-    available_projects_list_wrapper.set_vexpand(true);
-    available_projects_list_wrapper.add(&available_projects_list_panel);
+impl Update for WelcomePanel {
+    type Model = WelcomePanelModel;
+    type ModelParam = (Relm<MainWindow>, Vec<PathBuf>);
+    type Msg = WelcomePanelMsg;
 
-    available_projects_panel.add(&available_projects_label);
-    available_projects_panel.add(&Separator::new(Horizontal));
-    available_projects_panel.add(&available_projects_list_wrapper);
+    fn model(_: &Relm<Self>, (parent_relm, recent_project_list): Self::ModelParam) -> Self::Model {
+        WelcomePanelModel {
+            parent_relm,
+            recent_project_list,
+            project_info_list: Vec::new(),
+            search_filter: String::new(),
+            sort_order: ProjectSortOrder::LastModified,
+        }
+    }
 
-    available_projects_panel
+    fn update(&mut self, event: Self::Msg) {
+        match event {
+            WelcomePanelMsg::Refresh => {
+                self.load_project_info_list();
+                self.sort_project_info_list();
+            }
+            WelcomePanelMsg::SetSearchFilter(search_filter) => {
+                self.model.search_filter = search_filter;
+            }
+            WelcomePanelMsg::SetSortOrder(sort_order) => {
+                self.model.sort_order = sort_order;
+                self.sort_project_info_list();
+            }
+            WelcomePanelMsg::SetRecentProjectList(recent_project_list) => {
+                self.model.recent_project_list = recent_project_list;
+                self.rebuild_recent_project_list();
+                return;
+            }
+        }
+
+        self.rebuild_project_list();
+    }
 }
 
-pub fn build_welcome_panel(relm: &Relm<MainWindow>) -> gtk::Box {
-    let welcome_panel = gtk::Box::new(Vertical, 16);
-    welcome_panel.set_property_margin(16);
-    welcome_panel.add(&build_description_panel());
-    welcome_panel.add(&build_available_projects_panel(relm));
+impl Widget for WelcomePanel {
+    type Root = gtk::Box;
 
-    welcome_panel
+    fn root(&self) -> Self::Root {
+        self.root.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
+        let welcome_panel = gtk::Box::new(Vertical, 16);
+        welcome_panel.set_property_margin(16);
+
+        let recent_projects_panel = gtk::Box::new(Vertical, 4);
+        recent_projects_panel.set_hexpand(true);
+
+        let recent_projects_label = Label::new(Some("Recent projects"));
+        recent_projects_label.set_xalign(0.0);
+
+        let recent_project_list_panel = FlowBox::new();
+        recent_project_list_panel.set_selection_mode(SelectionMode::None);
+        recent_project_list_panel.set_column_spacing(4);
+        recent_project_list_panel.set_row_spacing(4);
+
+        recent_projects_panel.add(&recent_projects_label);
+        recent_projects_panel.add(&Separator::new(Horizontal));
+        recent_projects_panel.add(&recent_project_list_panel);
+
+        // The section visibility depends on the recent project list, not on the parent's
+        recent_projects_panel.show_all();
+        recent_projects_panel.set_no_show_all(true);
+
+        let available_projects_panel = gtk::Box::new(Vertical, 4);
+        available_projects_panel.set_hexpand(true);
+
+        let available_projects_header = gtk::Box::new(Horizontal, 8);
+
+        let available_projects_label = Label::new(Some("Available projects"));
+        available_projects_label.set_xalign(0.0);
+        available_projects_label.set_hexpand(true);
+
+        let search_entry = Entry::new();
+        search_entry.set_placeholder_text(Some("Search"));
+        connect!(
+            relm,
+            search_entry,
+            connect_changed(val),
+            Some(WelcomePanelMsg::SetSearchFilter(val.get_text().to_string()))
+        );
+
+        let sort_order_chooser = ComboBoxText::new();
+        sort_order_chooser.append(Some("last_modified"), "Last modified");
+        sort_order_chooser.append(Some("name"), "Name");
+        sort_order_chooser.append(Some("stage_count"), "Stage count");
+        sort_order_chooser.set_active_id(Some("last_modified"));
+        connect!(
+            relm,
+            sort_order_chooser,
+            connect_changed(val),
+            val.get_active_id()
+                .and_then(|id| ProjectSortOrder::from_id(id.as_str()))
+                .map(WelcomePanelMsg::SetSortOrder)
+        );
+
+        let refresh_button = Button::new();
+        refresh_button.set_label("Refresh");
+        connect!(
+            relm,
+            refresh_button,
+            connect_clicked(_),
+            WelcomePanelMsg::Refresh
+        );
+
+        available_projects_header.add(&available_projects_label);
+        available_projects_header.add(&search_entry);
+        available_projects_header.add(&sort_order_chooser);
+        available_projects_header.add(&refresh_button);
+
+        let project_list_panel = Grid::new();
+
+        project_list_panel.set_hexpand(true);
+        project_list_panel.set_row_spacing(4);
+        project_list_panel.set_column_spacing(8);
+        project_list_panel.set_orientation(Vertical);
+
+        let available_projects_list_wrapper =
+            ScrolledWindow::new::<Adjustment, Adjustment>(None, None);
+        available_projects_list_wrapper.set_policy(PolicyType::Never, PolicyType::Automatic);
+        available_projects_list_wrapper.set_hexpand(true);
+        available_projects_list_wrapper.set_vexpand(true);
+        available_projects_list_wrapper.add(&project_list_panel);
+
+        available_projects_panel.add(&available_projects_header);
+        available_projects_panel.add(&Separator::new(Horizontal));
+        available_projects_panel.add(&available_projects_list_wrapper);
+
+        welcome_panel.add(&build_description_panel());
+        welcome_panel.add(&recent_projects_panel);
+        welcome_panel.add(&available_projects_panel);
+
+        let mut welcome_panel = WelcomePanel {
+            model,
+            root: welcome_panel,
+
+            recent_projects_panel,
+            recent_project_list_panel,
+            project_list_panel,
+        };
+
+        welcome_panel.rebuild_recent_project_list();
+
+        welcome_panel.load_project_info_list();
+        welcome_panel.sort_project_info_list();
+        welcome_panel.rebuild_project_list();
+
+        welcome_panel
+    }
 }
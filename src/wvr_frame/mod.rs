@@ -1,19 +1,24 @@
 use core::ffi::c_void;
 
-use std::path::Path;
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::mpsc::channel;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Mutex;
 use std::thread;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 
 use gtk::prelude::*;
 
 use gtk::GLArea;
 
-use glium::Surface;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::{Dimensions, MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat};
+use glium::uniforms::MagnifySamplerFilter;
 use glium::SwapBuffersError;
+use glium::{BlitTarget, GlObject, Surface};
 
 use wvr_com::data::{Message, SetInfo};
 use wvr_com::server::OrderServer;
@@ -21,6 +26,23 @@ use wvr_data::config::project_config::ProjectConfig;
 
 use wvr::Wvr;
 
+pub mod output;
+pub mod preview;
+
+use output::SharedOutputWindow;
+use preview::{SharedStagePreviewList, PREVIEW_RENDER_WIDTH};
+
+/// Capture of the output, written along with the next rendered frame.
+pub enum CaptureRequest {
+    /// Full resolution image saved to the given path, the callback runs once it is written.
+    Image(PathBuf, Box<dyn FnOnce()>),
+    /// Thumbnail of the project at the given path.
+    Thumbnail(PathBuf),
+}
+
+/// Captures of the output requested since the last rendered frame.
+pub type SharedCaptureQueue = Rc<RefCell<Vec<CaptureRequest>>>;
+
 struct GtkWvrBackend {
     glarea: gtk::GLArea,
 }
@@ -54,11 +76,8 @@ impl GtkWvrBackend {
     }
 }
 
-pub fn build_wvr_frame(
-    glarea: &GLArea,
-    project_path: &Path,
-    project_config: &ProjectConfig,
-) -> Result<Sender<Message>> {
+/// Creates a glium context drawing through the GL context of `glarea`, which must be realized.
+pub fn build_glium_context(glarea: &GLArea) -> Result<Rc<glium::backend::Context>> {
     gl_loader::init_gl();
 
     let context = unsafe {
@@ -69,7 +88,212 @@ pub fn build_wvr_frame(
         )?
     };
 
+    Ok(context)
+}
+
+/// Renders the final stage to an offscreen texture and reads it back, bottom row first.
+pub fn render_final_stage_to_pixels(
+    app: &mut Wvr,
+    context: &Rc<glium::backend::Context>,
+    width: u32,
+    height: u32,
+) -> Result<RawImage2d<'static, u8>> {
+    let texture = Texture2d::empty(context, width, height)?;
+    {
+        let mut framebuffer = SimpleFrameBuffer::new(context, &texture)?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        app.render_final_stage(context, &mut framebuffer)?;
+    }
+
+    Ok(texture.read())
+}
+
+// Applies the orders received since the last frame, from whichever view is drawn first
+fn handle_pending_messages(
+    app: &mut Wvr,
+    context: &Rc<glium::backend::Context>,
+    order_receiver: &Receiver<Message>,
+    glarea: &GLArea,
+) {
+    for message in order_receiver.try_iter() {
+        if let Message::Set(SetInfo::DynamicResolution(_)) = &message {
+        } else {
+            app.handle_message(context, &message).unwrap();
+        }
+
+        if let Message::Set(set_info) = &message {
+            match &set_info {
+                SetInfo::Width(width) => {
+                    glarea.set_size_request(*width as i32 / 4, app.get_height() as i32 / 4);
+                }
+                SetInfo::Height(height) => {
+                    glarea.set_size_request(app.get_width() as i32 / 4, *height as i32 / 4);
+                }
+                SetInfo::VSync(_vsync) => (),
+                SetInfo::Fullscreen(_fullscreen) => (),
+                SetInfo::LockedSpeed(_fullscreen) => (),
+                SetInfo::Screenshot(_screenshot) => (),
+                _ => (),
+            }
+        }
+    }
+}
+
+// The renderer advances once per tick of the frame timer, the first view drawn after it does
+fn advance_frame(app: &mut Wvr, context: &Rc<glium::backend::Context>, frame_due: &Cell<bool>) {
+    if !app.is_playing() || !frame_due.replace(false) {
+        return;
+    }
+
+    let resolution = context.get_framebuffer_dimensions();
+    if let Err(error) = app.update(context, (resolution.0 as usize, resolution.1 as usize)) {
+        eprintln!("Failed to update app: {:?}", error);
+    }
+
+    if let Err(error) = app.render_stages(context) {
+        eprintln!("Failed to render app: {:?}", error);
+    }
+}
+
+/// Renders the final stage to the texture shown by the output window, in the preview context.
+fn render_output_texture(
+    app: &mut Wvr,
+    context: &Rc<glium::backend::Context>,
+    output_texture: &RefCell<Option<Texture2d>>,
+) -> Result<()> {
+    let (width, height) = (app.get_width() as u32, app.get_height() as u32);
+
+    // The output texture is only reallocated when the output size changes
+    let mut output_texture = output_texture.borrow_mut();
+    if output_texture
+        .as_ref()
+        .map(|texture| (texture.get_width(), texture.get_height().unwrap_or(0)))
+        != Some((width, height))
+    {
+        *output_texture = Some(Texture2d::empty(context, width, height)?);
+    }
+
+    {
+        let mut framebuffer = SimpleFrameBuffer::new(context, output_texture.as_ref().unwrap())?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        app.render_final_stage(context, &mut framebuffer)?;
+    }
+
+    // The output context reads the texture next, once the preview context is done with it
+    context.finish();
+
+    Ok(())
+}
+
+/// Copies the output texture to the output window, letterboxed, without leaving the GPU.
+fn draw_output(
+    output_context: &Rc<glium::backend::Context>,
+    output_texture: Option<&Texture2d>,
+    resolution: (u32, u32),
+) -> Result<()> {
+    let mut frame = glium::Frame::new(output_context.clone(), resolution);
+    frame.clear_color(0.0, 0.0, 0.0, 1.0);
+
+    if let Some(output_texture) = output_texture {
+        let (width, height) = (
+            output_texture.get_width(),
+            output_texture.get_height().unwrap_or(1),
+        );
+
+        // The texture belongs to the preview context, it is only borrowed through the output one
+        let output_texture = unsafe {
+            Texture2d::from_id(
+                output_context,
+                UncompressedFloatFormat::U8U8U8U8,
+                output_texture.get_id(),
+                false,
+                MipmapsOption::NoMipmap,
+                Dimensions::Texture2d { width, height },
+            )
+        };
+
+        let scale = (resolution.0 as f64 / width.max(1) as f64)
+            .min(resolution.1 as f64 / height.max(1) as f64);
+        let (target_width, target_height) = (
+            (width as f64 * scale).round() as u32,
+            (height as f64 * scale).round() as u32,
+        );
+        output_texture.as_surface().blit_whole_color_to(
+            &frame,
+            &BlitTarget {
+                left: (resolution.0 - target_width.min(resolution.0)) / 2,
+                bottom: (resolution.1 - target_height.min(resolution.1)) / 2,
+                width: target_width as i32,
+                height: target_height as i32,
+            },
+            MagnifySamplerFilter::Linear,
+        );
+    }
+
+    frame.finish()?;
+
+    Ok(())
+}
+
+#[cfg(feature = "stage-previews")]
+fn get_render_stage_texture<'a>(app: &'a Wvr, stage_name: &str) -> Result<&'a Texture2d> {
+    app.get_render_stage_texture(stage_name)
+        .ok_or_else(|| anyhow!("No render target for stage {:}", stage_name))
+}
+
+#[cfg(not(feature = "stage-previews"))]
+fn get_render_stage_texture<'a>(_app: &'a Wvr, stage_name: &str) -> Result<&'a Texture2d> {
+    Err(anyhow!(
+        "Stage previews are disabled, {:} cannot be previewed",
+        stage_name
+    ))
+}
+
+/// Copies the render target of a stage at the preview size, the final stage is left untouched.
+fn render_stage_preview(
+    app: &Wvr,
+    context: &Rc<glium::backend::Context>,
+    stage_name: &str,
+) -> Result<cairo::ImageSurface> {
+    let stage_texture = get_render_stage_texture(app, stage_name)?;
+
+    let width = PREVIEW_RENDER_WIDTH;
+    let height = (width as usize * stage_texture.get_height().unwrap_or(1) as usize
+        / stage_texture.get_width().max(1) as usize)
+        .max(1) as u32;
+
+    let preview_texture = Texture2d::empty(context, width, height)?;
+    {
+        let framebuffer = SimpleFrameBuffer::new(context, &preview_texture)?;
+        stage_texture.as_surface().blit_whole_color_to(
+            &framebuffer,
+            &BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: width as i32,
+                height: height as i32,
+            },
+            MagnifySamplerFilter::Linear,
+        );
+    }
+
+    let pixels: RawImage2d<u8> = preview_texture.read();
+    preview::build_image_surface(&pixels.data, pixels.width, pixels.height)
+}
+
+pub fn build_wvr_frame(
+    glarea: &GLArea,
+    project_path: &Path,
+    project_config: &ProjectConfig,
+    stage_preview_list: SharedStagePreviewList,
+    output_window: SharedOutputWindow,
+    capture_queue: SharedCaptureQueue,
+) -> Result<Sender<Message>> {
+    let context = build_glium_context(glarea)?;
+
     let (order_sender, order_receiver) = channel();
+    let order_receiver = Rc::new(order_receiver);
+    let frame_due = Rc::new(Cell::new(true));
 
     {
         let glarea = glarea.clone();
@@ -87,7 +311,7 @@ pub fn build_wvr_frame(
             }
         }
 
-        let app = Mutex::new({
+        let app = Rc::new(Mutex::new({
             let mut app = Wvr::new(&project_path, project_config.clone(), &context)
                 .context("Failed creating Wvr app")
                 .unwrap();
@@ -95,55 +319,143 @@ pub fn build_wvr_frame(
             app.handle_message(&context, &Message::Set(SetInfo::DynamicResolution(false)))
                 .unwrap();
             app
-        });
+        }));
 
-        glarea.connect_render(move |glarea, _glcontext| {
-            if let Ok(mut app) = app.lock() {
-                let resolution = context.get_framebuffer_dimensions();
+        {
+            let app = app.clone();
+            let context = context.clone();
+            let order_receiver = order_receiver.clone();
+            let preview_glarea = glarea.clone();
+            let frame_due = frame_due.clone();
+            let output_texture = RefCell::new(None);
+            // Built again whenever the output area gets a new GL context
+            let output_context: RefCell<Option<(gdk::GLContext, Rc<glium::backend::Context>)>> =
+                RefCell::new(None);
 
-                for message in order_receiver.try_iter() {
-                    if let Message::Set(SetInfo::DynamicResolution(_)) = &message {
-                    } else {
-                        app.handle_message(&context, &message).unwrap();
-                    }
+            output_window
+                .borrow_mut()
+                .set_renderer(move |output_glarea, blackout| {
+                    if let Ok(mut app) = app.lock() {
+                        // The renderer only draws in the preview context, even when the preview
+                        // is not on screen
+                        preview_glarea.make_current();
+                        handle_pending_messages(
+                            &mut app,
+                            &context,
+                            &order_receiver,
+                            &preview_glarea,
+                        );
+                        advance_frame(&mut app, &context, &frame_due);
 
-                    if let Message::Set(set_info) = &message {
-                        match &set_info {
-                            SetInfo::Width(width) => {
-                                glarea.set_size_request(
-                                    *width as i32 / 4,
-                                    app.get_height() as i32 / 4,
-                                );
+                        let is_rendered = !blackout && {
+                            match render_output_texture(&mut app, &context, &output_texture) {
+                                Ok(()) => true,
+                                Err(error) => {
+                                    eprintln!("Failed to render output: {:?}", error);
+                                    false
+                                }
                             }
-                            SetInfo::Height(height) => {
-                                glarea.set_size_request(
-                                    app.get_width() as i32 / 4,
-                                    *height as i32 / 4,
-                                );
+                        };
+
+                        output_glarea.make_current();
+                        let glcontext = match output_glarea.get_context() {
+                            Some(glcontext) => glcontext,
+                            None => return,
+                        };
+                        let mut output_context = output_context.borrow_mut();
+                        if output_context
+                            .as_ref()
+                            .map(|(cached_glcontext, _)| cached_glcontext != &glcontext)
+                            .unwrap_or(true)
+                        {
+                            match build_glium_context(output_glarea) {
+                                Ok(new_output_context) => {
+                                    *output_context = Some((glcontext, new_output_context))
+                                }
+                                Err(error) => {
+                                    eprintln!("Failed to create output context: {:?}", error);
+                                    return;
+                                }
                             }
-                            SetInfo::VSync(_vsync) => (),
-                            SetInfo::Fullscreen(_fullscreen) => (),
-                            SetInfo::LockedSpeed(_fullscreen) => (),
-                            SetInfo::Screenshot(_screenshot) => (),
-                            _ => (),
+                        }
+
+                        let resolution = (
+                            output_glarea.get_allocated_width().max(1) as u32,
+                            output_glarea.get_allocated_height().max(1) as u32,
+                        );
+                        let output_texture = output_texture.borrow();
+                        if let Err(error) = draw_output(
+                            &output_context.as_ref().unwrap().1,
+                            output_texture.as_ref().filter(|_| is_rendered),
+                            resolution,
+                        ) {
+                            eprintln!("Failed to draw output window: {:?}", error);
                         }
                     }
-                }
+                });
+        }
+
+        let frame_due = frame_due.clone();
+        glarea.connect_render(move |glarea, _glcontext| {
+            if let Ok(mut app) = app.lock() {
+                let resolution = context.get_framebuffer_dimensions();
+
+                handle_pending_messages(&mut app, &context, &order_receiver, glarea);
 
                 let mut frame = glium::Frame::new(context.clone(), resolution);
                 frame.clear_color(0.0, 1.0, 0.0, 1.0);
                 if let Err(error) = app.render_final_stage(&context, &mut frame) {
                     eprintln!("Failed to render to window: {:?}", error);
                 }
-                if app.is_playing() {
-                    if let Err(error) =
-                        app.update(&context, (resolution.0 as usize, resolution.1 as usize))
-                    {
-                        eprintln!("Failed to update app: {:?}", error);
+
+                let capture_request_list: Vec<CaptureRequest> =
+                    capture_queue.borrow_mut().drain(..).collect();
+                for capture_request in capture_request_list {
+                    let (width, height) = (app.get_width() as u32, app.get_height() as u32);
+                    match capture_request {
+                        CaptureRequest::Image(capture_path, on_written) => {
+                            match render_final_stage_to_pixels(&mut app, &context, width, height)
+                                .and_then(|pixels| {
+                                    crate::export::png::write_png(&capture_path, &pixels)
+                                }) {
+                                Ok(()) => on_written(),
+                                Err(error) => {
+                                    eprintln!("Failed to capture {:?}: {:?}", capture_path, error)
+                                }
+                            }
+                        }
+                        CaptureRequest::Thumbnail(project_path) => {
+                            let (width, height) =
+                                crate::thumbnail::get_thumbnail_render_size(width, height);
+                            if let Err(error) =
+                                render_final_stage_to_pixels(&mut app, &context, width, height)
+                                    .and_then(|pixels| {
+                                        crate::thumbnail::save_thumbnail(&project_path, &pixels)
+                                    })
+                            {
+                                eprintln!("Failed to save project thumbnail: {:?}", error);
+                            }
+                        }
                     }
+                }
+
+                advance_frame(&mut app, &context, &frame_due);
 
-                    if let Err(error) = app.render_stages(&context) {
-                        eprintln!("Failed to render app: {:?}", error);
+                let preview_stage_list = {
+                    let mut stage_preview_list = stage_preview_list.borrow_mut();
+                    if stage_preview_list.is_update_due() {
+                        stage_preview_list.get_visible_stage_list()
+                    } else {
+                        Vec::new()
+                    }
+                };
+                for (stage_id, stage_name) in &preview_stage_list {
+                    if let Err(error) =
+                        render_stage_preview(&app, &context, stage_name).and_then(|surface| {
+                            stage_preview_list.borrow().set_preview(stage_id, &surface)
+                        })
+                    {
+                        eprintln!("Failed to render preview of {:}: {:?}", stage_name, error);
                     }
                 }
                 frame
@@ -158,7 +470,9 @@ pub fn build_wvr_frame(
 
     let glarea = glarea.clone();
     glib::source::timeout_add_local(1_000 / 60, move || {
+        frame_due.set(true);
         glarea.queue_draw();
+        output_window.borrow().queue_draw();
         glib::source::Continue(true)
     });
 
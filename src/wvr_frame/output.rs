@@ -0,0 +1,148 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use gdk::MonitorExt;
+
+use gtk::{ContainerExt, GLArea, GLAreaExt, GtkWindowExt, Inhibit, WidgetExt, Window, WindowType};
+
+pub type SharedOutputWindow = Rc<RefCell<OutputWindow>>;
+
+/// Borderless window showing the rendered output fullscreen on a chosen monitor, for live shows.
+///
+/// Frames are rendered by the renderer of the embedded preview, then copied by a GL area of its
+/// own, whose context shares textures with the other GL contexts of the display.
+pub struct OutputWindow {
+    window: Window,
+    glarea: GLArea,
+    blackout: Rc<Cell<bool>>,
+    monitor: i32,
+}
+
+impl OutputWindow {
+    pub fn new_shared() -> SharedOutputWindow {
+        let window = Window::new(WindowType::Toplevel);
+        window.set_title("wvr output");
+        window.set_decorated(false);
+
+        // Replaced once the renderer of the project is started
+        let glarea = GLArea::new();
+        window.add(&glarea);
+
+        Rc::new(RefCell::new(Self {
+            window,
+            glarea,
+            blackout: Rc::new(Cell::new(false)),
+            monitor: 0,
+        }))
+    }
+
+    /// Names of the connected monitors, indexed as `gdk` numbers them.
+    pub fn get_monitor_name_list() -> Vec<String> {
+        let display = match gdk::Display::get_default() {
+            Some(display) => display,
+            None => return Vec::new(),
+        };
+
+        (0..display.get_n_monitors())
+            .map(|monitor_index| {
+                let monitor = display.get_monitor(monitor_index);
+                let model = monitor
+                    .as_ref()
+                    .and_then(|monitor| monitor.get_model())
+                    .map(|model| model.to_string())
+                    .unwrap_or_else(|| "Monitor".to_owned());
+                let geometry = monitor.map(|monitor| monitor.get_geometry());
+
+                match geometry {
+                    Some(geometry) => format!(
+                        "{:}: {:} ({:}x{:})",
+                        monitor_index, model, geometry.width, geometry.height
+                    ),
+                    None => format!("{:}: {:}", monitor_index, model),
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.window.get_visible()
+    }
+
+    pub fn is_blackout(&self) -> bool {
+        self.blackout.get()
+    }
+
+    pub fn show(&self) {
+        self.window.show_all();
+        self.move_to_monitor();
+    }
+
+    pub fn hide(&self) {
+        self.window.hide();
+    }
+
+    /// Destroys the window, closing it would only hide it as asked by the config panel.
+    pub fn destroy(&self) {
+        self.window.destroy();
+    }
+
+    pub fn set_monitor(&mut self, monitor: i32) {
+        self.monitor = monitor;
+        if self.is_visible() {
+            self.move_to_monitor();
+        }
+    }
+
+    fn move_to_monitor(&self) {
+        let screen = match self.window.get_screen() {
+            Some(screen) => screen,
+            None => return,
+        };
+
+        if let Some(geometry) = gdk::Display::get_default()
+            .and_then(|display| display.get_monitor(self.monitor))
+            .map(|monitor| monitor.get_geometry())
+        {
+            self.window.move_(geometry.x, geometry.y);
+        }
+        self.window.fullscreen_on_monitor(&screen, self.monitor);
+    }
+
+    /// Paints the output black at once, whatever the renderer does.
+    pub fn set_blackout(&self, blackout: bool) {
+        self.blackout.set(blackout);
+        self.glarea.queue_draw();
+    }
+
+    /// Draws the output with `render`, given the output area and whether the output is blacked
+    /// out. The area creates its context for its own window, as GTK expects.
+    pub fn set_renderer<F: Fn(&GLArea, bool) + 'static>(&mut self, render: F) {
+        let glarea = GLArea::new();
+        glarea.set_required_version(3, 2);
+        {
+            let blackout = self.blackout.clone();
+            glarea.connect_render(move |glarea, _glcontext| {
+                render(glarea, blackout.get());
+                Inhibit(true)
+            });
+        }
+
+        self.window.remove(&self.glarea);
+        self.window.add(&glarea);
+        if self.is_visible() {
+            glarea.show();
+        }
+        self.glarea = glarea;
+    }
+
+    /// Asks for a new frame, nothing is rendered while the window is hidden.
+    pub fn queue_draw(&self) {
+        if self.is_visible() {
+            self.glarea.queue_draw();
+        }
+    }
+}
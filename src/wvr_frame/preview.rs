@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use uuid::Uuid;
+
+use gtk::{Image, ImageExt, WidgetExt};
+
+/// Width of the texture stage previews are rendered to, images are scaled down from it.
+pub const PREVIEW_RENDER_WIDTH: u32 = 320;
+
+pub const TAB_PREVIEW_WIDTH: i32 = 48;
+pub const TAB_PREVIEW_HEIGHT: i32 = 27;
+
+pub const STAGE_PREVIEW_WIDTH: i32 = 320;
+pub const STAGE_PREVIEW_HEIGHT: i32 = 180;
+
+/// Stage previews need the render targets of the stages, only exposed by newer wvr revisions.
+pub const STAGE_PREVIEWS_ENABLED: bool = cfg!(feature = "stage-previews");
+
+// Previews are refreshed less often than the output to keep their cost low
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(200);
+
+pub type SharedStagePreviewList = Rc<RefCell<StagePreviewList>>;
+
+/// Images showing the output of the render stages, filled by the renderer of the project.
+#[derive(Default)]
+pub struct StagePreviewList {
+    stage_list: Vec<(Uuid, String)>,
+    image_list: HashMap<Uuid, Vec<(Image, i32, i32)>>,
+    last_update: Option<Instant>,
+}
+
+impl StagePreviewList {
+    pub fn new_shared() -> SharedStagePreviewList {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    /// Builds an empty image to register for the previews of a stage, kept hidden when previews
+    /// are disabled so that no preview is ever rendered.
+    pub fn build_preview_image(width: i32, height: i32) -> Image {
+        let image = Image::new();
+        image.set_size_request(width, height);
+        image.set_no_show_all(!STAGE_PREVIEWS_ENABLED);
+
+        image
+    }
+
+    pub fn add_image(&mut self, stage_id: Uuid, image: &Image, width: i32, height: i32) {
+        self.image_list
+            .entry(stage_id)
+            .or_insert_with(Vec::new)
+            .push((image.clone(), width, height));
+    }
+
+    /// Sets the stages of the render chain, forgetting the images of the removed ones.
+    pub fn set_stage_list(&mut self, stage_list: Vec<(Uuid, String)>) {
+        self.image_list.retain(|stage_id, _| {
+            stage_list
+                .iter()
+                .any(|(candidate_id, _)| candidate_id == stage_id)
+        });
+        self.stage_list = stage_list;
+    }
+
+    /// Tells whether previews are due, and if so restarts the interval.
+    pub fn is_update_due(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last_update {
+            Some(last_update) if now.duration_since(last_update) < PREVIEW_INTERVAL => false,
+            _ => {
+                self.last_update = Some(now);
+                true
+            }
+        }
+    }
+
+    /// Stages having at least one preview on screen, along with their name.
+    pub fn get_visible_stage_list(&self) -> Vec<(Uuid, String)> {
+        self.stage_list
+            .iter()
+            .filter(|(stage_id, _)| {
+                self.image_list
+                    .get(stage_id)
+                    .map(|image_list| image_list.iter().any(|(image, _, _)| image.get_mapped()))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Fills the images of a stage with its rendered output, scaled to fit each of them.
+    pub fn set_preview(&self, stage_id: &Uuid, surface: &cairo::ImageSurface) -> Result<()> {
+        let image_list = match self.image_list.get(stage_id) {
+            Some(image_list) => image_list,
+            None => return Ok(()),
+        };
+
+        let (source_width, source_height) =
+            (surface.get_width() as f64, surface.get_height() as f64);
+
+        for (image, width, height) in image_list.iter().filter(|(image, _, _)| image.get_mapped()) {
+            let scaled_surface =
+                cairo::ImageSurface::create(cairo::Format::ARgb32, *width, *height)?;
+            {
+                let scale = (*width as f64 / source_width).min(*height as f64 / source_height);
+
+                let cairo_context = cairo::Context::new(&scaled_surface);
+                cairo_context.translate(
+                    (*width as f64 - source_width * scale) / 2.0,
+                    (*height as f64 - source_height * scale) / 2.0,
+                );
+                cairo_context.scale(scale, scale);
+                cairo_context.set_source_surface(surface, 0.0, 0.0);
+                cairo_context.paint();
+            }
+
+            image.set_from_surface(Some(&*scaled_surface));
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts pixels read back from a texture, bottom row first, to a cairo surface.
+pub fn build_image_surface(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<cairo::ImageSurface> {
+    let stride = width as usize * 4;
+    let mut argb_data = vec![0; stride * height as usize];
+
+    for (row_index, row) in rgba_data
+        .chunks_exact(stride)
+        .take(height as usize)
+        .enumerate()
+    {
+        let target_row_start = (height as usize - 1 - row_index) * stride;
+        let target_row = &mut argb_data[target_row_start..target_row_start + stride];
+
+        for (pixel, target_pixel) in row.chunks_exact(4).zip(target_row.chunks_exact_mut(4)) {
+            // Cairo stores opaque pixels as native endian 32 bits words
+            let argb = u32::from_be_bytes([255, pixel[0], pixel[1], pixel[2]]);
+            target_pixel.copy_from_slice(&argb.to_ne_bytes());
+        }
+    }
+
+    Ok(cairo::ImageSurface::create_for_data(
+        argb_data,
+        cairo::Format::ARgb32,
+        width as i32,
+        height as i32,
+        stride as i32,
+    )?)
+}